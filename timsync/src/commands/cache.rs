@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use simplelog::info;
+
+use crate::project::project::Project;
+
+/// Remove the cache folder of a project.
+/// Used by the global `--clean-cache` flag before running a command.
+///
+/// # Arguments
+///
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub fn clean_cache(project_dir: &Path) -> Result<()> {
+    let project = Project::resolve_from_directory(project_dir, None)
+        .context("Could not resolve the project to clean the cache of")?;
+    let cache = project.cache();
+    if cache.clear()? {
+        info!("Removed the cache folder {}", cache.get_path().display());
+    }
+    Ok(())
+}
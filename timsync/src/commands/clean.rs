@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Error, Result};
+use clap::builder::RangedU64ValueParser;
+use clap::Args;
+use futures::{stream, StreamExt, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use simplelog::{debug, info};
+
+use crate::commands::sync::{connect_to_target, parse_var, DEFAULT_MAX_CONCURRENCY};
+use crate::processing::prepared_document::PreparedDocument;
+use crate::project::project::Project;
+use crate::util::tim_client::ItemType;
+
+#[derive(Debug, Args)]
+pub struct CleanOpts {
+    #[arg(default_value = "default")]
+    /// The name of the sync target to clean. Defaults to "default".
+    target: String,
+
+    #[arg(short, long)]
+    /// The config environment to use. Applies overrides from `.timsync/config.<env>.toml`.
+    env: Option<String>,
+
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    /// Set the value of a `{NAME}` placeholder in the folder root of the sync target.
+    /// Overrides the value set in the sync target config. Can be given multiple times.
+    vars: Vec<(String, String)>,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_CONCURRENCY, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    /// The maximum number of concurrent requests to TIM.
+    max_concurrency: usize,
+}
+
+/// Remove the TIMSync settings blocks from all documents in the folder root of a sync target.
+///
+/// Only documents that contain a settings block are re-uploaded.
+/// The documents are otherwise left as-is, so they can be edited in TIM without TIMSync.
+///
+/// # Arguments
+///
+/// * `opts`: Clean options
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub async fn clean_target(opts: CleanOpts, project_dir: &Path) -> Result<()> {
+    let mut project = Project::resolve_from_directory(project_dir, opts.env.as_deref())
+        .context("Could not resolve project")?;
+
+    project
+        .config
+        .require_target_mut(&opts.target)?
+        .resolve_placeholders(&opts.vars.iter().cloned().collect())?;
+
+    // The target was checked to exist above
+    let target_info = project.config.get_target(&opts.target).unwrap();
+
+    info!("Cleaning {} ({})...", opts.target, target_info.host);
+
+    let multi_progress = MultiProgress::new();
+
+    let tick_progress = multi_progress.add(ProgressBar::new_spinner());
+
+    tick_progress.set_message("Logging in");
+    tick_progress.enable_steady_tick(Duration::from_millis(100));
+
+    let client = connect_to_target(target_info, &multi_progress).await?;
+
+    tick_progress.set_message("Fetching folder contents");
+    let documents = client
+        .get_items_in_folder(&target_info.folder_root)
+        .await?
+        .into_values()
+        .filter(|item| matches!(item.item_type, ItemType::Document))
+        .map(|item| item.path())
+        .collect::<Vec<_>>();
+
+    tick_progress.disable_steady_tick();
+    tick_progress.set_message("Removing TIMSync settings blocks");
+
+    let progress_bar = multi_progress.add(
+        ProgressBar::new(documents.len() as u64).with_style(
+            ProgressStyle::default_bar()
+                .template("{msg} [{wide_bar}] {pos:>3}/{len:3}")
+                .unwrap()
+                .progress_chars("##-"),
+        ),
+    );
+
+    let results = stream::iter(documents.iter().map(|doc_path| {
+        let client = &client;
+        let progress_bar = &progress_bar;
+        async move {
+            progress_bar.set_message(format!("Cleaning document: {}", doc_path));
+
+            let current_doc_markdown = client.download_markdown(doc_path).await?;
+            let cleaned = match PreparedDocument::without_timsync_settings(&current_doc_markdown) {
+                Some(markdown) => {
+                    client
+                        .upload_markdown(doc_path, &markdown, Some(&current_doc_markdown))
+                        .await?;
+                    true
+                }
+                None => {
+                    debug!("Document {} has no TIMSync settings, skipping", doc_path);
+                    false
+                }
+            };
+
+            progress_bar.inc(1);
+
+            Ok::<bool, Error>(cleaned)
+        }
+    }))
+    .buffer_unordered(opts.max_concurrency)
+    .try_collect::<Vec<_>>()
+    .await
+    .context("Could not clean documents")?;
+
+    progress_bar.finish_and_clear();
+    tick_progress.finish_and_clear();
+
+    let cleaned_count = results.iter().filter(|cleaned| **cleaned).count();
+    info!(
+        "Removed TIMSync settings from {} of {} documents in {}",
+        cleaned_count,
+        documents.len(),
+        target_info.folder_root
+    );
+
+    Ok(())
+}
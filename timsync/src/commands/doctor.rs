@@ -0,0 +1,239 @@
+use std::path::Path;
+
+use anyhow::{Context, Error, Result};
+use clap::Args;
+use handlebars::Handlebars;
+use indicatif::MultiProgress;
+use simplelog::__private::paris::LogIcon;
+use simplelog::{error, info, warn};
+
+use crate::commands::sync::{build_client, log_in, parse_var};
+use crate::project::config::SyncTarget;
+use crate::project::project::Project;
+use crate::templating::tim_handlebars::TimRendererExt;
+use crate::util::tim_client::{ItemType, TimClientErrors};
+
+#[derive(Debug, Args)]
+pub struct DoctorOpts {
+    /// The name of the sync target to check. Checks all configured targets if not given.
+    target: Option<String>,
+
+    #[arg(short, long)]
+    /// The config environment to use. Applies overrides from `.timsync/config.<env>.toml`.
+    env: Option<String>,
+
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    /// Set the value of a `{NAME}` placeholder in the folder root of the sync target.
+    /// Overrides the value set in the sync target config. Can be given multiple times.
+    vars: Vec<(String, String)>,
+}
+
+/// Results of the checks run by `timsync doctor`.
+/// Each check is printed as soon as it is reported.
+#[derive(Default)]
+struct CheckReport {
+    warnings: usize,
+    failures: usize,
+}
+
+impl CheckReport {
+    fn pass(&mut self, message: &str) {
+        info!("<green>{}</> {}", LogIcon::Tick, message);
+    }
+
+    fn warn(&mut self, message: &str) {
+        self.warnings += 1;
+        warn!("<yellow>{}</> {}", LogIcon::Warning, message);
+    }
+
+    fn fail(&mut self, message: &str, e: &Error) {
+        self.failures += 1;
+        error!("<red>{}</> {}: {:#}", LogIcon::Cross, message, e);
+    }
+}
+
+/// Check the templates, helpers and global data of the project.
+///
+/// # Arguments
+///
+/// * `project`: The project to check.
+/// * `report`: The report to add the results to.
+fn check_project(project: &Project, report: &mut CheckReport) {
+    match project.global_context() {
+        Ok(_) => report.pass("Global data (_config.yml) is valid"),
+        Err(e) => report.fail("Could not read global data (_config.yml)", &e),
+    }
+
+    match Handlebars::new()
+        .with_tim_doc_helpers()
+        .with_project_templates(project)
+    {
+        Ok(_) => report.pass("Templates (_templates) compile"),
+        Err(e) => report.fail("Could not compile templates (_templates)", &e),
+    }
+
+    match Handlebars::new()
+        .with_tim_doc_helpers()
+        .with_project_helpers(project)
+    {
+        Ok(_) => report.pass("Helpers (_helpers) compile"),
+        Err(e) => report.fail("Could not compile helpers (_helpers)", &e),
+    }
+}
+
+/// Check that a sync target is reachable, the credentials are valid
+/// and the folder root exists and is writable.
+/// The checks stop at the first failure, since the later checks depend on the earlier ones.
+///
+/// # Arguments
+///
+/// * `name`: Name of the sync target.
+/// * `target_info`: The sync target to check.
+/// * `report`: The report to add the results to.
+async fn check_target(name: &str, target_info: &SyncTarget, report: &mut CheckReport) {
+    info!("Checking sync target {} ({})...", name, target_info.host);
+
+    let progress = MultiProgress::new();
+    let mut client = match build_client(target_info, &progress).await {
+        Ok(client) => client,
+        Err(e) => return report.fail(&format!("{} is not reachable", target_info.host), &e),
+    };
+    match client
+        .server_info()
+        .await
+        .ok()
+        .and_then(|info| info.version)
+    {
+        Some(version) => report.pass(&format!(
+            "{} is a TIM instance (version {})",
+            target_info.host, version
+        )),
+        None => report.pass(&format!("{} is a TIM instance", target_info.host)),
+    }
+    if target_info.danger_accept_invalid_certs {
+        report.warn("TLS certificate verification is disabled");
+    }
+
+    if let Err(e) = log_in(&mut client, target_info).await {
+        return report.fail("Could not log in", &e);
+    }
+    match client.current_user().await {
+        Ok(user) => report.pass(&format!("Logged in as {}", user.name)),
+        Err(e) => {
+            report.pass("Logged in");
+            report.warn(&format!("Could not verify the logged in user: {:#}", e));
+        }
+    }
+
+    let folder_root = &target_info.folder_root;
+    let folder_root_info = match client.get_item_info(folder_root).await {
+        Ok(info) => info,
+        Err(e) => {
+            let message = match e.downcast_ref::<TimClientErrors>() {
+                Some(TimClientErrors::ItemNotFound(_, _)) => {
+                    format!("Folder root {} does not exist", folder_root)
+                }
+                _ => format!("Could not get folder root {}", folder_root),
+            };
+            return report.fail(&message, &e);
+        }
+    };
+    if !matches!(folder_root_info.item_type, ItemType::Folder) {
+        return report.fail(
+            &format!("Folder root {} is not a folder", folder_root),
+            &anyhow::anyhow!("documents can only be synced into a folder"),
+        );
+    }
+    match client.can_edit(folder_root).await {
+        Ok(true) => report.pass(&format!(
+            "Folder root {} exists and is writable",
+            folder_root
+        )),
+        Ok(false) => report.fail(
+            &format!("Folder root {} is not writable", folder_root),
+            &anyhow::anyhow!("the user has no edit rights to the folder"),
+        ),
+        Err(e) => report.warn(&format!(
+            "Folder root {} exists, but could not check whether it is writable: {:#}",
+            folder_root, e
+        )),
+    }
+}
+
+/// Diagnose common setup issues without syncing anything.
+///
+/// The project config, global data, templates and helpers are checked first,
+/// followed by the connection, credentials and folder root of the sync targets.
+/// Each check is reported as passed, warned or failed.
+///
+/// # Arguments
+///
+/// * `opts`: Doctor options
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub async fn run_doctor(opts: DoctorOpts, project_dir: &Path) -> Result<()> {
+    let mut report = CheckReport::default();
+
+    let mut project = match Project::resolve_from_directory(project_dir, opts.env.as_deref()) {
+        Ok(project) => project,
+        Err(e) => {
+            report.fail("Could not read the project config", &e);
+            return Err(anyhow::anyhow!("Doctor found 1 failed check"));
+        }
+    };
+    report.pass(&format!(
+        "Project config in {} is valid",
+        project.get_root_path().display()
+    ));
+
+    check_project(&project, &mut report);
+
+    let target_names = match &opts.target {
+        Some(target) => vec![target.clone()],
+        None => project
+            .config
+            .target_names()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    };
+    if target_names.is_empty() {
+        report.warn("No sync targets configured. Use `timsync target add` to add a target.");
+    }
+
+    let vars = opts.vars.iter().cloned().collect();
+    for name in target_names {
+        let target_info = match project.config.require_target_mut(&name) {
+            Ok(target_info) => target_info,
+            Err(e) => {
+                report.fail(&format!("Sync target {} is not valid", name), &e);
+                continue;
+            }
+        };
+        if let Err(e) = target_info
+            .resolve_placeholders(&vars)
+            .context("Could not resolve the folder root")
+        {
+            report.fail(&format!("Sync target {} is not valid", name), &e);
+            continue;
+        }
+        check_target(&name, target_info, &mut report).await;
+    }
+
+    if report.failures > 0 {
+        return Err(anyhow::anyhow!(
+            "Doctor found {} failed checks and {} warnings",
+            report.failures,
+            report.warnings
+        ));
+    }
+
+    info!(
+        "{} All checks passed with {} warnings",
+        LogIcon::Tick,
+        report.warnings
+    );
+
+    Ok(())
+}
@@ -1,11 +1,12 @@
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use anyhow::{Context, Result};
 use clap::{arg, Args};
 use dialoguer::Confirm;
 use path_absolutize::*;
-use simplelog::{error, info};
+use simplelog::{error, info, warn};
 use thiserror::Error;
 
 use crate::commands::target::prompt_user_details_interactive;
@@ -16,7 +17,8 @@
 #[derive(Debug, Args)]
 pub struct InitOptions {
     #[arg()]
-    /// The path to the project directory. If not specified, the current directory is used.
+    /// The path to the project directory.
+    /// If not specified, the directory given with `--project` or the current directory is used.
     path: Option<PathBuf>,
     #[arg(short, long)]
     /// Force the initialization, even if the directory is already initialized.
@@ -26,6 +28,13 @@ pub struct InitOptions {
     /// Do not prompt for user details.
     /// This will create an empty configuration.
     no_prompt: bool,
+    #[arg(long)]
+    /// Initialize a git repository in the project directory if there is none yet.
+    init_git: bool,
+    #[arg(long)]
+    /// Also create an example document, template, task and helper to get started with.
+    /// Existing files are not overwritten.
+    with_examples: bool,
 }
 
 #[derive(Debug, Error)]
@@ -40,6 +49,68 @@ enum InitError {
 .timsync
 "#;
 
+/// Example files created with `--with-examples`.
+/// The paths are relative to the project root.
+const EXAMPLE_FILES: &[(&str, &str)] = &[
+    (
+        "index.md",
+        r#"---
+title: Welcome to TIMSync
+---
+
+{{> base.hbs }}
+
+{{#area "introduction"}}
+This is an area. Areas can be referenced from other documents with the `ref_area` helper.
+{{/area}}
+
+A greeting from the custom helper in `_helpers/example.rhai`: {{example "TIM"}}
+
+An example task defined in `tasks/example.task.yml`:
+
+{{task "example-task"}}
+"#,
+    ),
+    (
+        "_templates/base.hbs",
+        r#"# {{ title }}
+
+This part of the document comes from the template `_templates/base.hbs`.
+The current document is synced to `{{ path }}`.
+"#,
+    ),
+    (
+        "_helpers/example.rhai",
+        r#"// An example helper script written in Rhai.
+// The helper is available in the templates by the file name, e.g. `{{example "world"}}`.
+//
+// The helper receives the parameters in two variables:
+// `params` (for indexed arguments) and `hash` (for key=value arguments).
+// The last expression of the script is the output of the helper.
+//
+// More information on the Rhai scripting language: https://rhai.rs/book/language/index.html
+
+`Hello, ${params[0]}!`
+"#,
+    ),
+    (
+        "tasks/example.task.yml",
+        r#"---
+# Identifier of the task that is used to reference the task in the documents
+uid: example-task
+# TIM plugin to use for the task
+plugin: csPlugin
+---
+# The task definition passed to the plugin
+header: Example task
+stem: Write a program that prints "Hello, World!".
+type: python
+fullprogram: |
+  print("Hello, World!")
+"#,
+    ),
+];
+
 async fn get_default_sync_target(no_prompt: bool) -> Result<Option<SyncTarget>> {
     if no_prompt || !console::user_attended() {
         info!("Skipping default sync target setup. Use `timsync target add` to add a sync target.");
@@ -63,14 +134,42 @@ async fn get_default_sync_target(no_prompt: bool) -> Result<Option<SyncTarget>>
         .context("Could not get user details")
 }
 
+/// Initialize a git repository in the given directory unless it already has one.
+/// Initialization is optional, so failures (e.g. git not being installed) are only reported as warnings.
+///
+/// # Arguments
+///
+/// * `path`: The directory to initialize the repository in.
+fn init_git_repo(path: &Path) {
+    if path.join(".git").exists() {
+        info!("Git repository already exists in {}", path.display());
+        return;
+    }
+
+    match Command::new("git").arg("init").current_dir(path).output() {
+        Ok(output) if output.status.success() => {
+            info!("Initialized a git repository in {}", path.display());
+        }
+        Ok(output) => warn!(
+            "Could not initialize a git repository: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!(
+            "Could not initialize a git repository. Is git installed? ({})",
+            e
+        ),
+    }
+}
+
 /// Initialize a new TIMSync project.
 ///
 /// # Arguments
 ///
 /// * `opts`: Initialization options
+/// * `project_dir`: The project directory used if no path is given in the options.
 ///
 /// returns: Result<(), Error>
-pub async fn init_repo(opts: InitOptions) -> Result<()> {
+pub async fn init_repo(opts: InitOptions, project_dir: &Path) -> Result<()> {
     let target_path = match opts.path {
         Some(path) => {
             if path.exists() && !path.is_dir() {
@@ -80,7 +179,7 @@ pub async fn init_repo(opts: InitOptions) -> Result<()> {
                 .context("Could not resolve the full path")?
                 .to_path_buf()
         }
-        None => std::env::current_dir()?,
+        None => project_dir.to_path_buf(),
     };
 
     let timsync_path = target_path.join(&CONFIG_FOLDER);
@@ -106,6 +205,10 @@ pub async fn init_repo(opts: InitOptions) -> Result<()> {
 
     config.write_file(&timsync_config_file)?;
 
+    if opts.init_git {
+        init_git_repo(&target_path);
+    }
+
     let gitignore_file = target_path.join(".gitignore");
 
     // Create or update the .gitignore file
@@ -142,5 +245,29 @@ pub async fn init_repo(opts: InitOptions) -> Result<()> {
             .context("Could not create TIMSync ignore file")?;
     }
 
+    if opts.with_examples {
+        let mut created = 0;
+        for (path, contents) in EXAMPLE_FILES {
+            let example_file = target_path.join(path);
+            if example_file.exists() {
+                info!("Skipping example file {}: file already exists", path);
+                continue;
+            }
+            if let Some(parent) = example_file.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Could not create folder {}", parent.display()))?;
+            }
+            std::fs::write(&example_file, contents)
+                .with_context(|| format!("Could not create example file {}", path))?;
+            created += 1;
+        }
+        if created > 0 {
+            info!(
+                "Created {} example files. Try `timsync validate` to check the project.",
+                created
+            );
+        }
+    }
+
     Ok(())
 }
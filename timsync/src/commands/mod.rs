@@ -1,8 +1,29 @@
+pub use cache::clean_cache;
+pub use clean::clean_target;
+pub use clean::CleanOpts;
+pub use doctor::run_doctor;
+pub use doctor::DoctorOpts;
 pub use init::init_repo;
 pub use init::InitOptions;
+pub use rollback::rollback_sync;
+pub use rollback::RollbackOpts;
+pub use sync::sync_project;
 pub use sync::sync_target;
+pub use sync::SyncOptions;
 pub use sync::SyncOpts;
+pub use sync::SyncReport;
+pub use sync::SyncReportDocument;
+pub use sync::SyncSummary;
+pub use target::run_target_command;
+pub use target::TargetOpts;
+pub use validate::validate_project;
+pub use validate::ValidateOpts;
 
+mod cache;
+mod clean;
+mod doctor;
 mod init;
+mod rollback;
 mod sync;
 mod target;
+mod validate;
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Error, Result};
+use clap::builder::RangedU64ValueParser;
+use clap::Args;
+use futures::{stream, StreamExt, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use simplelog::__private::paris::LogIcon;
+use simplelog::{debug, info, warn};
+
+use crate::commands::sync::{connect_to_target, parse_var, SyncReport, DEFAULT_MAX_CONCURRENCY};
+use crate::project::project::Project;
+
+#[derive(Debug, Args)]
+pub struct RollbackOpts {
+    #[arg(long, value_name = "PATH")]
+    /// The sync report written with `timsync sync --report`.
+    to: PathBuf,
+
+    #[arg(short, long)]
+    /// The config environment to use. Applies overrides from `.timsync/config.<env>.toml`.
+    env: Option<String>,
+
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    /// Set the value of a `{NAME}` placeholder in the folder root of the sync target.
+    /// Overrides the value set in the sync target config. Can be given multiple times.
+    vars: Vec<(String, String)>,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_CONCURRENCY, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    /// The maximum number of concurrent requests to TIM.
+    max_concurrency: usize,
+}
+
+/// Restore the documents uploaded in a sync to their versions before the sync.
+///
+/// The documents and versions are read from a sync report written with `timsync sync --report`.
+/// The sync target of the report is used to connect to TIM.
+///
+/// # Arguments
+///
+/// * `opts`: Rollback options
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub async fn rollback_sync(opts: RollbackOpts, project_dir: &Path) -> Result<()> {
+    let report_json = std::fs::read_to_string(&opts.to)
+        .with_context(|| format!("Could not read the sync report {}", opts.to.display()))?;
+    let report: SyncReport = serde_json::from_str(&report_json)
+        .with_context(|| format!("Could not parse the sync report {}", opts.to.display()))?;
+
+    let mut project = Project::resolve_from_directory(project_dir, opts.env.as_deref())
+        .context("Could not resolve project")?;
+
+    project
+        .config
+        .require_target_mut(&report.target)
+        .context("Could not find the sync target of the sync report")?
+        .resolve_placeholders(&opts.vars.iter().cloned().collect())?;
+
+    // The target was checked to exist above
+    let target_info = project.config.get_target(&report.target).unwrap();
+
+    info!(
+        "Rolling back {} documents in {} ({})...",
+        report.documents.len(),
+        report.target,
+        target_info.host
+    );
+
+    let multi_progress = MultiProgress::new();
+
+    let tick_progress = multi_progress.add(ProgressBar::new_spinner());
+
+    tick_progress.set_message("Logging in");
+    tick_progress.enable_steady_tick(Duration::from_millis(100));
+
+    let client = connect_to_target(target_info, &multi_progress).await?;
+
+    tick_progress.disable_steady_tick();
+    tick_progress.set_message("Restoring documents");
+
+    let progress_bar = multi_progress.add(
+        ProgressBar::new(report.documents.len() as u64).with_style(
+            ProgressStyle::default_bar()
+                .template("{msg} [{wide_bar}] {pos:>3}/{len:3}")
+                .unwrap()
+                .progress_chars("##-"),
+        ),
+    );
+
+    let results = stream::iter(report.documents.iter().map(|doc| {
+        let client = &client;
+        let progress_bar = &progress_bar;
+        let multi_progress = &multi_progress;
+        async move {
+            progress_bar.set_message(format!("Restoring document: {}", doc.path));
+
+            let restored = match doc.previous_version {
+                Some(previous_version) => {
+                    client
+                        .revert_document(&doc.path, previous_version)
+                        .await
+                        .with_context(|| format!("Could not restore document {}", doc.path))?;
+                    debug!("Restored {} to version {}", doc.path, previous_version);
+                    true
+                }
+                None => {
+                    multi_progress.suspend(|| {
+                        warn!(
+                            "The sync report has no previous version for {}, skipping",
+                            doc.path
+                        )
+                    });
+                    false
+                }
+            };
+
+            progress_bar.inc(1);
+
+            Ok::<bool, Error>(restored)
+        }
+    }))
+    .buffer_unordered(opts.max_concurrency)
+    .try_collect::<Vec<_>>()
+    .await
+    .context("Could not roll back the sync")?;
+
+    progress_bar.finish_and_clear();
+    tick_progress.finish_and_clear();
+
+    let restored_count = results.iter().filter(|restored| **restored).count();
+    info!(
+        "{} Restored {} of {} documents",
+        LogIcon::Tick,
+        restored_count,
+        report.documents.len()
+    );
+
+    Ok(())
+}
@@ -1,35 +1,121 @@
-use std::cell::OnceCell;
-use std::collections::{HashMap, HashSet, LinkedList};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet, LinkedList};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::time::Duration;
 
 use anyhow::{Context, Error, Result};
+use clap::builder::RangedU64ValueParser;
 use clap::Args;
-use futures::future::try_join_all;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use futures::{stream, Future, StreamExt, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 use simplelog::__private::paris::LogIcon;
-use simplelog::info;
+use simplelog::{debug, error, info, warn};
 use thiserror::Error;
 use walkdir::WalkDir;
 
+use crate::commands::validate::placeholder_target;
 use crate::processing::markdown_processor::MarkdownProcessor;
+use crate::processing::postprocess::postprocess_markdown;
+use crate::processing::prepared_document::PreparedDocument;
 use crate::processing::processors::{FileProcessor, FileProcessorAPI, FileProcessorType};
 use crate::processing::style_theme_processor::StyleThemeProcessor;
 use crate::processing::task_processor::TaskProcessor;
 use crate::processing::tim_document::TIMDocument;
+use crate::project::cache::IMAGE_DIMENSIONS_CACHE_FILE;
+use crate::project::config::{SyncTarget, SHARED_FILES_DOCPATH, SHARED_FILES_TITLE};
 use crate::project::files::project_files::{ProjectFile, ProjectFileAPI};
 use crate::project::global_ctx::GlobalContext;
 use crate::project::project::Project;
+use crate::templating::tim_handlebars::SHARED_FILES_DOC_KEY;
+use crate::util::image::{load_dimensions_cache, save_dimensions_cache};
 use crate::util::json::Merge;
-use crate::util::tim_client::{ItemType, TimClient, TimClientBuilder, TimClientErrors};
+use crate::util::text::{count_words, reading_minutes};
+use crate::util::tim_api::TimApi;
+use crate::util::tim_client::{DocVersion, ItemType, TimClient, TimClientBuilder, TimClientErrors};
 
 #[derive(Debug, Args)]
 pub struct SyncOpts {
     #[arg(default_value = "default")]
     /// The name of the sync target to send document to. Defaults to "default".
     target: String,
+
+    #[arg(short, long)]
+    /// The config environment to use. Applies overrides from `.timsync/config.<env>.toml`.
+    env: Option<String>,
+
+    #[arg(long)]
+    /// Remove document aliases that are no longer declared in the front matter.
+    prune: bool,
+
+    #[arg(short, long)]
+    /// Overwrite documents that were modified in TIM since the last sync.
+    force: bool,
+
+    #[arg(long)]
+    /// Upload all documents even if their contents have not changed since the last sync.
+    force_upload: bool,
+
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    /// Set the value of a `{NAME}` placeholder in the folder root of the sync target.
+    /// Overrides the value set in the sync target config. Can be given multiple times.
+    vars: Vec<(String, String)>,
+
+    #[arg(long)]
+    /// Fail on references to undefined template variables instead of rendering them as empty.
+    /// Same as setting `strict_templating = true` in the config.
+    strict_templating: bool,
+
+    #[arg(long)]
+    /// Fail on links to local files that do not exist instead of only warning about them.
+    /// Same as setting `strict_links = true` in the config.
+    strict_links: bool,
+
+    #[arg(long, value_name = "DIR")]
+    /// Render the documents into Markdown files in the given directory instead of uploading them to TIM.
+    /// TIM is not contacted, so the document IDs are placeholders.
+    output_dir: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    /// Write a report of the uploaded documents and their TIM versions to the given JSON file.
+    /// The report can be used to undo the sync with `timsync rollback`.
+    report: Option<PathBuf>,
+
+    #[arg(long)]
+    /// Upload all documents even if some of them fail, and report every failure at the end.
+    /// By default, the sync stops at the first document that fails to upload.
+    keep_going: bool,
+
+    #[arg(long)]
+    /// Update only the changed paragraphs of documents where every paragraph has an ID (e.g. task documents),
+    /// instead of uploading the whole document. Other documents are uploaded as a whole.
+    paragraph_updates: bool,
+
+    #[arg(long)]
+    /// Hide the progress bars and the messages of the sync steps, e.g. in CI logs.
+    /// Only a summary of the synced documents and files is printed at the end. Errors and warnings are still printed.
+    summary_only: bool,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_CONCURRENCY, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    /// The maximum number of concurrent requests to TIM.
+    /// Use 1 to send the requests one at a time in document path order, e.g. to get reproducible logs.
+    max_concurrency: usize,
+}
+
+/// Default maximum number of concurrent requests to TIM.
+pub(super) const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// Parse a placeholder value given in format `NAME=VALUE`.
+///
+/// returns: Result<(String, String), String>
+pub(super) fn parse_var(s: &str) -> std::result::Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid variable '{}', use format NAME=VALUE", s))?;
+    Ok((name.trim().to_string(), value.to_string()))
 }
 
 fn is_hidden(entry: &walkdir::DirEntry) -> bool {
@@ -46,11 +132,104 @@ enum SyncError {
     SyncTargetDoesNotExist(String),
     #[error("The sync target path {0} is not a folder in TIM. The target path must be a folder for sync to work.")]
     SyncTargetNotAFolder(String),
-    // TODO: Include paths of all files that map to the same TIM document
-    #[error("Multiple documents found for the same TIM path '{0}'. Make sure there are no duplicate paths in the project.")]
-    ItemNameConflict(String),
+    #[error("The current user has no edit rights to the sync target folder {0}. Grant the user at least edit rights to the folder in TIM before syncing files.")]
+    SyncTargetNotWritable(String),
+    #[error("Multiple documents found for the same TIM path '{0}': {1}. Make sure there are no duplicate paths in the project.")]
+    ItemNameConflict(String, String),
     #[error("There is a document and a folder with the same path '{0}'. TIM requires that all items (folders, documents) have a unique path.")]
     ItemTypeConflict(String),
+    #[error("Document '{0}' contains duplicate paragraph IDs: {1}. Make sure that each task is referenced only once in a document and that `gen_par_id` seeds are unique within the document.")]
+    DuplicateParIds(String, String),
+    #[error("Multiple documents use the same UID '{0}': {1}. Make sure that each document has a unique `uid` in its front matter.")]
+    DuplicateDocumentUid(String, String),
+    #[error("Document '{0}' was modified in TIM since the last sync. Copy the changes to the local project or use `timsync sync --force` to overwrite them.")]
+    RemoteModified(String),
+    #[error(
+        "Document '{0}' was removed from TIM during the sync. Run the sync again to recreate it."
+    )]
+    DocumentMissing(String),
+}
+
+/// Report of the documents uploaded in a sync.
+/// Written with `timsync sync --report` and used by `timsync rollback` to undo the sync.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// The name of the sync target
+    pub target: String,
+    /// The uploaded documents
+    pub documents: Vec<SyncReportDocument>,
+    /// Counts of the synced documents and files
+    #[serde(default)]
+    pub summary: SyncSummary,
+    /// Errors of the documents that failed to upload if `keep_going` is set. Not written to the report file.
+    #[serde(skip)]
+    pub errors: Vec<Error>,
+}
+
+/// A document uploaded in a sync.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncReportDocument {
+    /// Full path of the document in TIM
+    pub path: String,
+    /// Version of the document before the upload, if `record_previous_versions` is set
+    pub previous_version: Option<DocVersion>,
+    /// Version of the document created by the upload, if reported by TIM
+    pub version: Option<DocVersion>,
+}
+
+/// Counts of the documents and files synced in a sync.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncSummary {
+    /// Documents synced for the first time, i.e. without an earlier TIMSync settings block in TIM
+    pub created_documents: usize,
+    /// Documents that were synced before and were uploaded again because their contents changed
+    pub updated_documents: usize,
+    /// Documents that were not uploaded because their contents did not change
+    pub skipped_documents: usize,
+    /// Files uploaded to TIM
+    pub uploaded_files: usize,
+    /// Files that were not uploaded because they were already present in TIM
+    pub skipped_files: usize,
+}
+
+/// Options for syncing a project with `sync_project()`.
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Remove document aliases that are no longer declared in the front matter.
+    pub prune: bool,
+    /// Overwrite documents that were modified in TIM since the last sync.
+    pub force: bool,
+    /// Upload all documents even if their contents have not changed since the last sync.
+    pub force_upload: bool,
+    /// Upload all documents even if some of them fail, and return the failures in the report.
+    pub keep_going: bool,
+    /// Request the version of each document before uploading it, so that the sync can be rolled back.
+    pub record_previous_versions: bool,
+    /// Update only the changed paragraphs of documents where every paragraph has an ID.
+    pub paragraph_updates: bool,
+    /// Hide the progress bars and the messages of the sync steps.
+    pub summary_only: bool,
+    /// Draw progress bars to the terminal. Off by default, so that embedding applications
+    /// only get the messages logged through the `log` crate.
+    pub show_progress: bool,
+    /// The maximum number of concurrent requests to TIM.
+    pub max_concurrency: usize,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        SyncOptions {
+            prune: false,
+            force: false,
+            force_upload: false,
+            keep_going: false,
+            record_previous_versions: false,
+            paragraph_updates: false,
+            summary_only: false,
+            show_progress: false,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        }
+    }
 }
 
 /// A single item entry. Used as a helper struct to manage item creation in TIM.
@@ -81,12 +260,18 @@ enum ItemEntries<'a> {
 
 /// The pipeline for synchronizing the project with a remote TIM target.
 /// TODO: Perhaps refactor into a proper pipeline pattern (using enums) to ensure order in which pipeline steps execute.
-struct SyncPipeline<'a> {
+pub(super) struct SyncPipeline<'a> {
     project: &'a Project,
     global_context: Rc<OnceCell<GlobalContext>>,
     sync_target: &'a str,
     processors: HashMap<FileProcessorType, FileProcessor<'a>>,
     progress: MultiProgress,
+    max_concurrency: usize,
+    keep_going: bool,
+    paragraph_updates: bool,
+    summary_only: bool,
+    /// Full paths of the documents created from the template document of the sync target in this sync
+    created_from_template: RefCell<HashSet<String>>,
 }
 
 impl<'a> SyncPipeline<'a> {
@@ -99,8 +284,13 @@ impl<'a> SyncPipeline<'a> {
     /// * `progress`: The multi-progress bar to display progress.
     ///
     /// returns: Result<SyncPipeline<'a>, Error>
-    fn new(project: &'a Project, sync_target: &'a str, progress: MultiProgress) -> Result<Self> {
+    pub(super) fn new(
+        project: &'a Project,
+        sync_target: &'a str,
+        progress: MultiProgress,
+    ) -> Result<Self> {
         let global_context = Rc::new(OnceCell::new());
+        load_dimensions_cache(&project.cache().get_path().join(IMAGE_DIMENSIONS_CACHE_FILE));
         Ok(SyncPipeline {
             project,
             processors: HashMap::from([
@@ -120,11 +310,140 @@ fn new(project: &'a Project, sync_target: &'a str, progress: MultiProgress) -> R
             sync_target,
             progress,
             global_context,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            keep_going: false,
+            paragraph_updates: false,
+            summary_only: false,
+            created_from_template: RefCell::new(HashSet::new()),
         })
     }
 
+    /// Set the maximum number of concurrent requests to TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_concurrency`: The maximum number of concurrent requests. Must be at least 1.
+    ///
+    /// returns: SyncPipeline<'a>
+    pub(super) fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Set whether the upload step continues after documents fail to upload.
+    /// If set, the failures are collected and returned after all documents are processed.
+    ///
+    /// # Arguments
+    ///
+    /// * `keep_going`: Whether to continue after failed uploads.
+    ///
+    /// returns: SyncPipeline<'a>
+    pub(super) fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Set whether only the changed paragraphs of documents are updated if possible.
+    /// See `PreparedDocument::changed_paragraphs` for the documents that can be updated by paragraph.
+    ///
+    /// # Arguments
+    ///
+    /// * `paragraph_updates`: Whether to update documents by paragraph.
+    ///
+    /// returns: SyncPipeline<'a>
+    pub(super) fn with_paragraph_updates(mut self, paragraph_updates: bool) -> Self {
+        self.paragraph_updates = paragraph_updates;
+        self
+    }
+
+    /// Set whether the results of the sync steps are printed.
+    /// If set, the results are only collected into the sync summary.
+    ///
+    /// # Arguments
+    ///
+    /// * `summary_only`: Whether to hide the results of the sync steps.
+    ///
+    /// returns: SyncPipeline<'a>
+    pub(super) fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
+    }
+
+    /// Print the result of a sync step, unless only the summary is printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `message`: The message to print.
+    fn print_step_result(&self, message: &str) {
+        if !self.summary_only {
+            self.progress.suspend(|| info!("{}", message));
+        }
+    }
+
+    /// Save the caches filled while rendering documents to the project cache folder.
+    /// Failing to save a cache only affects later runs, so the errors are logged as warnings.
+    pub(super) fn save_caches(&self) {
+        let result = self
+            .project
+            .cache()
+            .file(IMAGE_DIMENSIONS_CACHE_FILE)
+            .and_then(|cache_file| save_dimensions_cache(&cache_file));
+        if let Err(e) = result {
+            self.progress
+                .suspend(|| warn!("Could not save the image dimensions cache: {:#}", e));
+        }
+    }
+
+    /// Run futures concurrently with at most `max_concurrency` futures in flight.
+    /// Like `join_all`, waits for all futures and returns every result, including the errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `futures`: The futures to run.
+    ///
+    /// returns: Vec<Result<T, Error>> The results in the order of completion.
+    async fn join_limited<T>(
+        &self,
+        futures: impl IntoIterator<Item = impl Future<Output = Result<T>>>,
+    ) -> Vec<Result<T>> {
+        stream::iter(futures)
+            .buffer_unordered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Run futures concurrently with at most `max_concurrency` futures in flight.
+    /// Like `try_join_all`, fails on the first error.
+    /// With `max_concurrency` of 1, the futures are run one at a time in the given order.
+    ///
+    /// # Arguments
+    ///
+    /// * `futures`: The futures to run.
+    ///
+    /// returns: Result<Vec<T>, Error> The results in the order of completion.
+    async fn try_join_limited<T>(
+        &self,
+        futures: impl IntoIterator<Item = impl Future<Output = Result<T>>>,
+    ) -> Result<Vec<T>> {
+        stream::iter(futures)
+            .buffer_unordered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
     /// Step 1: Collect all files in the project and add them to the relevant processors.
     fn collect_tim_documents(&mut self) -> Result<()> {
+        match self.try_collect_tim_documents()?.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Collect all files in the project and add them to the relevant processors.
+    /// Unlike `collect_tim_documents`, errors in adding single files do not stop the collection.
+    ///
+    /// returns: Result<Vec<Error>, Error> The errors that occurred while adding files.
+    pub(super) fn try_collect_tim_documents(&mut self) -> Result<Vec<Error>> {
         let progress = self.progress.add(ProgressBar::new_spinner());
         progress.set_message("Collecting files");
         progress.enable_steady_tick(Duration::from_millis(100));
@@ -139,11 +458,18 @@ fn collect_tim_documents(&mut self) -> Result<()> {
             .filter(|e| e.is_file())
             .filter_map(|e| ProjectFile::try_from(e).ok());
 
+        let mut errors = Vec::new();
         for file in project_files {
+            debug!("Found project file {}", file.path().display());
+            let file_path = file.path().display().to_string();
             let processor_type = file.processor_type();
             let processor = self.processors.get_mut(&processor_type);
             match processor {
-                Some(processor) => processor.add_file(file)?,
+                Some(processor) => {
+                    if let Err(e) = processor.add_file(file) {
+                        errors.push(e.context(format!("Could not process file {}", file_path)));
+                    }
+                }
                 None => {}
             }
         }
@@ -151,14 +477,35 @@ fn collect_tim_documents(&mut self) -> Result<()> {
         progress.finish_and_clear();
         self.progress.remove(&progress);
 
-        Ok(())
+        Ok(errors)
     }
 
     /// Step 3: Collect all documents from the processors.
-    fn get_tim_documents(&self) -> Vec<TIMDocument> {
-        self.processors
+    pub(super) fn get_tim_documents(&self) -> Vec<TIMDocument> {
+        let mut documents: Vec<TIMDocument> = self
+            .processors
             .values()
             .flat_map(|processor| processor.get_tim_documents())
+            .collect();
+        // Sort by path so that the documents are processed in the same order on every run
+        documents.sort_by(|a, b| a.path.cmp(b.path));
+        debug!("Collected {} TIM documents", documents.len());
+        documents
+    }
+
+    /// Assign placeholder IDs to the documents in place of real TIM document IDs.
+    /// Used when rendering the documents without creating them in TIM.
+    /// The IDs are numbered from 1 in document path order, so they are the same on every run.
+    ///
+    /// returns: Vec<TIMDocument>
+    pub(super) fn with_placeholder_ids(documents: Vec<TIMDocument<'a>>) -> Vec<TIMDocument<'a>> {
+        documents
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut doc)| {
+                doc.id = Some(i as u64 + 1);
+                doc
+            })
             .collect()
     }
 
@@ -167,9 +514,13 @@ fn get_tim_documents(&self) -> Vec<TIMDocument> {
     /// The items are created in the correct order, i.e. folders are created before documents.
     /// This is done to prevent any concurrency errors and to provide sanity checking.
     /// At the same time, the item IDs are collected so that they can be used in templates.
+    /// Because the templates need the IDs, the documents are created empty before rendering
+    /// and their contents are uploaded in step 7.
+    /// Documents created from the template document of the sync target are recorded,
+    /// so that the template contents are not mistaken for edits made in TIM.
     async fn create_tim_documents(
         &self,
-        client: &TimClient,
+        client: &impl TimApi,
         documents: Vec<TIMDocument<'a>>,
     ) -> Result<Vec<TIMDocument<'a>>> {
         let progress = self.progress.add(ProgressBar::new_spinner());
@@ -207,17 +558,34 @@ async fn create_tim_documents(
 
         async fn create_item(
             progress_bar: &ProgressBar,
-            client: &TimClient,
+            client: &impl TimApi,
             item_type: ItemType,
             path: String,
             title: &str,
-        ) -> Result<(String, u64)> {
+            lang: Option<String>,
+            sync_target: &SyncTarget,
+        ) -> Result<(String, u64, bool)> {
             progress_bar.set_message(format!("Creating item: {}", path));
-            let item_info = client
-                .create_or_update_item(item_type, &path, title)
+            debug!("Creating or updating {} {}", item_type, path);
+            let (item_info, created) = client
+                .create_or_update_item(
+                    item_type,
+                    &path,
+                    title,
+                    sync_target.owner_group.as_deref(),
+                    sync_target.template_doc.as_deref(),
+                )
                 .await?;
+            // Only update the language if it changed to avoid unnecessary requests
+            if let Some(lang) = lang.filter(|lang| item_info.lang_id.as_ref() != Some(lang)) {
+                debug!("Setting language of {} to {}", path, lang);
+                client.set_item_language(&path, &lang).await?;
+            }
+            // Only new documents are created from the template
+            let from_template =
+                created && item_type == ItemType::Document && sync_target.template_doc.is_some();
             progress_bar.inc(1);
-            Ok((path, item_info.id))
+            Ok((path, item_info.id, from_template))
         }
 
         while let Some((current_path, documents_with_paths)) = process_stack.pop_front() {
@@ -235,9 +603,19 @@ async fn create_item(
                 .collect::<Vec<_>>();
 
             let mut futures = Vec::new();
+            let mut folders = Vec::new();
 
             // Sort by base to bring together items with the same base path
-            split_documents_paths.sort_unstable_by_key(|de| de.path_base);
+            split_documents_paths.sort_by_key(|de| de.path_base);
+
+            // Give a clear error instead of TIM's generic one if the user cannot create items in the folder.
+            // The rights are checked once per folder. If the folder cannot be checked, let TIM decide.
+            if let Some(first) = split_documents_paths.first() {
+                if let Ok(false) = client.can_edit(&current_path).await {
+                    let item_path = format!("{}/{}", current_path, first.path_base);
+                    return Err(TimClientErrors::NoEditRights(item_path, current_path).into());
+                }
+            }
 
             // Chunk (i.e. group) by path base
             for (base, chunk) in &split_documents_paths
@@ -258,7 +636,19 @@ async fn create_item(
 
                     // 1. If there are multiple documents and no folders, user is trying to create multiple documents with the same name in the same folder
                     if document_count > 1 && folder_count == 0 {
-                        return Err(SyncError::ItemNameConflict(base.to_string()).into());
+                        let sources = items
+                            .iter()
+                            .map(|de| {
+                                de.doc.get_local_file_path().unwrap_or_else(|| {
+                                    format!("generated document {}", de.doc.path)
+                                })
+                            })
+                            .join(", ");
+                        return Err(SyncError::ItemNameConflict(
+                            format!("{}/{}", current_path, base),
+                            sources,
+                        )
+                        .into());
                     }
 
                     // 2. If there are folders and documents, user is trying to create a folder and a document with the same path
@@ -286,6 +676,7 @@ async fn create_item(
                 match item_entries {
                     ItemEntries::Document(doc_entry) => {
                         let doc_path = format!("{}/{}", current_path, base);
+                        let lang = doc_entry.doc.general_metadata()?.lang;
 
                         futures.push(create_item(
                             &progress_bar,
@@ -293,6 +684,8 @@ async fn create_item(
                             ItemType::Document,
                             doc_path,
                             doc_entry.doc.title,
+                            lang,
+                            sync_target,
                         ));
 
                         result.push(doc_entry);
@@ -306,18 +699,29 @@ async fn create_item(
                             ItemType::Folder,
                             folder_path.clone(),
                             base,
+                            None,
+                            sync_target,
                         ));
 
-                        process_stack.push_front((folder_path, folder_entries));
+                        folders.push((folder_path, folder_entries));
                     }
                 }
             }
 
+            // Process the subfolders next in sorted order
+            for folder in folders.into_iter().rev() {
+                process_stack.push_front(folder);
+            }
+
             // Before going deeper, evaluate all futures (create items for the current level)
             // and collect the resulting IDs to be merged with the documents
-            let item_create_results = try_join_all(futures).await?;
+            let item_create_results = self.try_join_limited(futures).await?;
 
-            for (path, item_id) in item_create_results {
+            for (path, item_id, from_template) in item_create_results {
+                if from_template {
+                    debug!("Created {} from the template document", path);
+                    self.created_from_template.borrow_mut().insert(path.clone());
+                }
                 // Convert full path back to item_path that can be used for item ID lookup
                 let item_path = path[tim_folder_root_length + 1..].to_string();
                 item_id_hashmap.insert(item_path, item_id);
@@ -337,9 +741,93 @@ async fn create_item(
             .collect())
     }
 
+    /// Step 3.5: Register the document aliases declared in the front matter.
+    ///
+    /// Alias paths are relative to the target folder root.
+    /// If `prune` is set, any aliases within the target folder that are no longer declared
+    /// in the front matter are removed.
+    async fn sync_aliases(
+        &self,
+        client: &impl TimApi,
+        documents: &[TIMDocument<'a>],
+        prune: bool,
+    ) -> Result<()> {
+        let sync_target = self.project.config.get_target(self.sync_target).unwrap();
+        let tim_folder_root = &sync_target.folder_root;
+
+        let mut documents_with_aliases = Vec::new();
+        for doc in documents {
+            let aliases = doc
+                .general_metadata()?
+                .aliases
+                .into_iter()
+                .map(|alias| {
+                    format!(
+                        "{}/{}",
+                        tim_folder_root,
+                        alias.trim_matches('/').to_lowercase()
+                    )
+                })
+                .collect::<HashSet<_>>();
+            if !aliases.is_empty() || prune {
+                documents_with_aliases.push((doc, aliases));
+            }
+        }
+
+        if documents_with_aliases.is_empty() {
+            return Ok(());
+        }
+
+        let progress = self.progress.add(ProgressBar::new_spinner());
+        progress.set_message("Updating document aliases");
+        progress.enable_steady_tick(Duration::from_millis(100));
+
+        self.try_join_limited(
+            documents_with_aliases
+                .iter()
+                .map(|(doc, aliases)| async move {
+                    // SAFETY: The documents are created in TIM before this step
+                    let doc_id = doc.id.unwrap();
+                    let doc_path = format!("{}/{}", tim_folder_root, doc.path);
+                    let existing_aliases = client
+                        .get_aliases(doc_id)
+                        .await?
+                        .into_iter()
+                        .map(|a| a.name)
+                        .collect::<HashSet<_>>();
+
+                    for alias in aliases.difference(&existing_aliases) {
+                        debug!("Adding alias {} to {}", alias, doc_path);
+                        client.add_alias(doc_id, alias).await?;
+                    }
+
+                    if prune {
+                        let folder_prefix = format!("{}/", tim_folder_root);
+                        for alias in existing_aliases.difference(aliases) {
+                            if *alias == doc_path || !alias.starts_with(&folder_prefix) {
+                                continue;
+                            }
+                            debug!("Removing alias {} from {}", alias, doc_path);
+                            client.remove_alias(alias).await?;
+                        }
+                    }
+
+                    Ok::<(), Error>(())
+                }),
+        )
+        .await
+        .context("Could not update document aliases")?;
+
+        progress.finish_and_clear();
+        self.progress.remove(&progress);
+
+        Ok(())
+    }
+
     /// Step 4: Update project context to include a full list of documents with their IDs.
-    fn update_project_context(&self, documents: &Vec<TIMDocument<'a>>) -> Result<()> {
+    pub(super) fn update_project_context(&self, documents: &Vec<TIMDocument<'a>>) -> Result<()> {
         let mut uid_to_info_map = Map::new();
+        let mut uid_to_sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
         let mut all_documents_infos = Vec::new();
 
         for doc in documents {
@@ -353,13 +841,34 @@ fn update_project_context(&self, documents: &Vec<TIMDocument<'a>>) -> Result<()>
                 "local_file_path": doc.get_local_file_path(),
             }));
 
+            // The statistics are estimated from the source, because the documents are not rendered yet
+            if let Some(source) = doc.source_text()? {
+                let word_count = count_words(&source);
+                doc_meta_json.merge(&json!({
+                    "word_count": word_count,
+                    "reading_minutes": reading_minutes(word_count),
+                }));
+            }
+
             if let Some(doc_uid) = general_meta.uid {
+                uid_to_sources.entry(doc_uid.clone()).or_default().push(
+                    doc.get_local_file_path()
+                        .unwrap_or_else(|| format!("generated document {}", doc.path)),
+                );
                 uid_to_info_map.insert(doc_uid, doc_meta_json.clone());
             }
 
             all_documents_infos.push(doc_meta_json.clone());
         }
 
+        // A later document would silently replace an earlier one in `site.doc`
+        if let Some((doc_uid, sources)) = uid_to_sources
+            .into_iter()
+            .find(|(_, sources)| sources.len() > 1)
+        {
+            return Err(SyncError::DuplicateDocumentUid(doc_uid, sources.join(", ")).into());
+        }
+
         let mut global_context = self.project.global_context()?;
         global_context.insert("doc", Value::Object(uid_to_info_map));
         global_context.insert("docs", Value::Array(all_documents_infos));
@@ -372,6 +881,12 @@ fn update_project_context(&self, documents: &Vec<TIMDocument<'a>>) -> Result<()>
             Value::String(self.project.get_root_path().display().to_string()),
         );
         global_context.insert("sync_target", Value::String(self.sync_target.to_string()));
+        if sync_target.shared_files {
+            global_context.insert(
+                SHARED_FILES_DOC_KEY,
+                Value::String(SHARED_FILES_DOCPATH.to_string()),
+            );
+        }
 
         for (_, processor) in &self.processors {
             if let Some(context) = processor.get_processor_context() {
@@ -379,6 +894,8 @@ fn update_project_context(&self, documents: &Vec<TIMDocument<'a>>) -> Result<()>
             }
         }
 
+        debug!("Project context updated with {} documents", documents.len());
+
         self.global_context
             .set(global_context)
             .expect("Global context was already set, this should not happen");
@@ -386,18 +903,144 @@ fn update_project_context(&self, documents: &Vec<TIMDocument<'a>>) -> Result<()>
         Ok(())
     }
 
-    /// Step 5: Generate documents content and sync them with TIM.
-    async fn sync_tim_documents_contents(
+    /// Step 5: Generate the contents of the documents.
+    /// Returns the full TIM paths of the documents with the prepared contents.
+    fn render_tim_documents(
         &self,
-        client: &TimClient,
         documents: Vec<TIMDocument<'a>>,
+    ) -> Result<Vec<(String, PreparedDocument)>> {
+        let progress = self.progress.add(ProgressBar::new_spinner());
+        progress.set_message("Rendering documents");
+        progress.enable_steady_tick(Duration::from_millis(100));
+
+        let sync_target = self.project.config.get_target(self.sync_target).unwrap();
+        let tim_folder_root = sync_target.folder_root.clone();
+
+        let result = documents
+            .iter()
+            .map(|doc| {
+                let doc_path = format!("{}/{}", tim_folder_root, doc.path);
+                debug!("Rendering document {}", doc_path);
+                let mut prepared_doc = doc
+                    .render_contents()
+                    .with_context(|| format!("Could not render document {}", doc_path))?;
+                prepared_doc.markdown =
+                    postprocess_markdown(&self.project.config.postprocess, prepared_doc.markdown);
+                if self.project.config.embed_source_path {
+                    prepared_doc.source_path = doc.get_local_file_path();
+                }
+
+                let duplicate_par_ids = prepared_doc.duplicate_par_ids();
+                if !duplicate_par_ids.is_empty() {
+                    return Err(
+                        SyncError::DuplicateParIds(doc_path, duplicate_par_ids.join(", ")).into(),
+                    );
+                }
+                Ok((doc_path, prepared_doc))
+            })
+            .collect::<Result<Vec<_>>>();
+
+        progress.finish_and_clear();
+        self.progress.remove(&progress);
+        self.save_caches();
+
+        result
+    }
+
+    /// Step 5.5: Resolve references to TIM documents outside the project.
+    ///
+    /// The `tim_ref` helper references documents by their path in TIM, because the templates
+    /// are rendered offline. The paths are resolved to document IDs here.
+    async fn resolve_tim_refs(
+        &self,
+        client: &impl TimApi,
+        mut documents: Vec<(String, PreparedDocument)>,
+    ) -> Result<Vec<(String, PreparedDocument)>> {
+        let ref_paths = documents
+            .iter()
+            .flat_map(|(_, prepared_doc)| prepared_doc.tim_ref_paths())
+            .unique()
+            .collect::<Vec<_>>();
+
+        if ref_paths.is_empty() {
+            return Ok(documents);
+        }
+
+        let doc_ids = self
+            .try_join_limited(ref_paths.into_iter().map(|path| async move {
+                let item = client.get_item_info(&path).await.with_context(|| {
+                    format!("Could not find the referenced TIM document '{}'", path)
+                })?;
+                if item.item_type != ItemType::Document {
+                    return Err(anyhow::anyhow!(
+                        "The referenced TIM item '{}' is not a document",
+                        path
+                    ));
+                }
+                Ok::<(String, u64), Error>((path, item.id))
+            }))
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        for (doc_path, prepared_doc) in documents.iter_mut() {
+            prepared_doc.resolve_tim_refs(&doc_ids).with_context(|| {
+                format!("Could not resolve references in document {}", doc_path)
+            })?;
+        }
+
+        Ok(documents)
+    }
+
+    /// Step 6: Upload the files referenced by the documents to TIM.
+    ///
+    /// Files are uploaded to the documents that reference them.
+    /// Files that are already present in the document are not re-uploaded.
+    async fn sync_files(
+        &self,
+        client: &impl TimApi,
+        documents: &[(String, PreparedDocument)],
+        summary: &mut SyncSummary,
     ) -> Result<()> {
+        let sync_target = self.project.config.get_target(self.sync_target).unwrap();
+        let shared_files_doc_path = format!("{}/{}", sync_target.folder_root, SHARED_FILES_DOCPATH);
+
+        // Deduplicate the files by the target TIM filename within each document.
+        // With shared files enabled, all files are uploaded once to the shared document.
+        let mut files_by_document: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+        for (doc_path, prepared_doc) in documents {
+            let doc_path = if sync_target.shared_files {
+                &shared_files_doc_path
+            } else {
+                doc_path
+            };
+            for (file_path, tim_file_name) in prepared_doc.upload_files.iter() {
+                files_by_document
+                    .entry(doc_path)
+                    .or_default()
+                    .entry(tim_file_name)
+                    .or_insert(file_path);
+            }
+        }
+
+        let file_count = files_by_document.values().map(|f| f.len()).sum::<usize>();
+        if file_count == 0 {
+            return Ok(());
+        }
+
         let progress = self.progress.add(ProgressBar::new_spinner());
-        progress.set_message("Uploading document contents to TIM");
+        progress.set_message("Uploading files to TIM");
         progress.enable_steady_tick(Duration::from_millis(100));
 
+        if sync_target.shared_files {
+            client
+                .get_or_create_document(&shared_files_doc_path, SHARED_FILES_TITLE)
+                .await
+                .context("Could not create the shared files document")?;
+        }
+
         let progress_bar = self.progress.add(
-            ProgressBar::new(documents.len() as u64).with_style(
+            ProgressBar::new(file_count as u64).with_style(
                 ProgressStyle::default_bar()
                     .template("{msg} [{wide_bar}] {pos:>3}/{len:3}")
                     .unwrap()
@@ -405,92 +1048,358 @@ async fn sync_tim_documents_contents(
             ),
         );
 
-        let sync_target = self.project.config.get_target(self.sync_target).unwrap();
-        let tim_folder_root = sync_target.folder_root.clone();
+        let results = self
+            .try_join_limited(files_by_document.iter().map(|(doc_path, files)| {
+                let progress_bar = &progress_bar;
+                async move {
+                    let existing_files = client
+                        .get_document_uploads(doc_path)
+                        .await?
+                        .into_iter()
+                        .map(|f| f.filename)
+                        .collect::<HashSet<_>>();
+
+                    let mut uploaded = 0usize;
+                    let mut skipped = 0usize;
+                    for (tim_file_name, file_path) in files.iter() {
+                        // Don't re-upload files that already exist
+                        if existing_files.contains(*tim_file_name) {
+                            debug!(
+                                "File {} already exists in {}, skipping",
+                                tim_file_name, doc_path
+                            );
+                            skipped += 1;
+                        } else {
+                            progress_bar.set_message(format!("Uploading file: {}", file_path));
+                            client
+                                .upload_file(doc_path, Path::new(file_path), tim_file_name)
+                                .await?;
+                            uploaded += 1;
+                        }
+                        progress_bar.inc(1);
+                    }
 
-        try_join_all(documents.iter().map(|doc| async {
-            let doc_path = format!("{}/{}", tim_folder_root, doc.path);
+                    Ok::<(usize, usize), Error>((uploaded, skipped))
+                }
+            }))
+            .await
+            .context("Could not upload files")?;
 
-            progress_bar.set_message(format!("Uploading document: {}", doc_path));
+        progress_bar.finish_and_clear();
+        progress.finish_and_clear();
+        self.progress.remove(&progress_bar);
+        self.progress.remove(&progress);
 
-            let prepared_doc = doc.render_contents()?;
+        let (uploaded, skipped) = results
+            .into_iter()
+            .fold((0, 0), |(u, s), (du, ds)| (u + du, s + ds));
+        self.print_step_result(&format!(
+            "Uploaded {} files, skipped {} files already present in TIM",
+            uploaded, skipped
+        ));
+        summary.uploaded_files += uploaded;
+        summary.skipped_files += skipped;
 
-            // Upload files
-            if !prepared_doc.upload_files.is_empty() {
-                let existing_files = client.get_document_uploads(&doc_path).await?;
-                let existing_files = existing_files
-                    .into_iter()
-                    .map(|f| f.filename)
-                    .collect::<HashSet<_>>();
+        Ok(())
+    }
 
-                // TODO: Parallelize file uploads
-                for (file_path, tim_file_name) in prepared_doc.upload_files.iter() {
-                    // Don't re-upload files that already exist
-                    if existing_files.contains(tim_file_name) {
-                        continue;
+    /// Step 7: Sync the document contents with TIM.
+    /// Unless `force` is set, documents that were modified in TIM since the last sync are not overwritten.
+    /// If `force_upload` is set, documents are uploaded even if their contents have not changed.
+    ///
+    /// If `record_previous_versions` is set, the current version of each uploaded document is
+    /// requested before the upload so that the upload can be reverted.
+    ///
+    /// returns: Result<Vec<SyncReportDocument>, Error> The uploaded documents.
+    async fn sync_tim_documents_contents(
+        &self,
+        client: &impl TimApi,
+        documents: Vec<(String, PreparedDocument)>,
+        force: bool,
+        force_upload: bool,
+        record_previous_versions: bool,
+        summary: &mut SyncSummary,
+    ) -> Result<(Vec<SyncReportDocument>, Vec<Error>)> {
+        let progress = self.progress.add(ProgressBar::new_spinner());
+        progress.set_message("Uploading document contents to TIM");
+        progress.enable_steady_tick(Duration::from_millis(100));
+
+        let progress_bar = self.progress.add(
+            ProgressBar::new(documents.len() as u64).with_style(
+                ProgressStyle::default_bar()
+                    .template("{msg} [{wide_bar}] {pos:>3}/{len:3}")
+                    .unwrap()
+                    .progress_chars("##-"),
+            ),
+        );
+
+        let uploads = documents.into_iter().map(|(doc_path, prepared_doc)| {
+            let progress_bar = &progress_bar;
+            let source_path = doc_path.clone();
+            let upload = async move {
+                progress_bar.set_message(format!("Uploading document: {}", doc_path));
+
+                // The documents are created before uploading, but may have been removed in between
+                let current_doc_markdown = client
+                    .try_download_markdown(&doc_path)
+                    .await?
+                    .ok_or_else(|| SyncError::DocumentMissing(doc_path.clone()))?;
+
+                let uploaded = if !force_upload
+                    && prepared_doc.timestamp_equals(&current_doc_markdown)
+                {
+                    debug!("Document {} is up to date, skipping upload", doc_path);
+                    progress_bar.set_message(format!("Skipped unchanged document: {}", doc_path));
+                    None
+                } else {
+                    // Only check for conflicts if the document should not be overwritten.
+                    // Documents created from the template only contain the template contents.
+                    // A document with the same contents only needs a fresh settings block.
+                    // The paragraphs are matched by their IDs, which TIM only includes on request,
+                    // so IDs written in the source are only found in the markdown with IDs.
+                    let markdown_with_ids = if self.paragraph_updates {
+                        Some(client.download_markdown_with_ids(&doc_path).await?)
+                    } else {
+                        None
+                    };
+                    let from_template = self.created_from_template.borrow().contains(&doc_path);
+                    let original = if force || from_template {
+                        None
+                    } else if !prepared_doc.body_equals(&current_doc_markdown)
+                        && PreparedDocument::is_modified_remotely(&current_doc_markdown)
+                        && markdown_with_ids
+                            .as_deref()
+                            .is_none_or(PreparedDocument::is_modified_remotely)
+                    {
+                        return Err(SyncError::RemoteModified(doc_path).into());
+                    } else {
+                        Some(current_doc_markdown.as_str())
+                    };
+                    let previous_version = if record_previous_versions {
+                        client
+                            .get_document_versions(&doc_path, 1)
+                            .await?
+                            .into_iter()
+                            .next()
+                            .map(|info| info.version)
+                    } else {
+                        None
+                    };
+                    let created = !PreparedDocument::has_timestamp(&current_doc_markdown);
+                    let doc_markdown = prepared_doc.with_timestamp();
+                    let paragraph_updates = markdown_with_ids
+                        .and_then(|markdown| doc_markdown.changed_paragraphs(&markdown));
+                    let version = match paragraph_updates {
+                        Some(updates) => {
+                            let doc_id = client.get_item_info(&doc_path).await?.id;
+                            debug!(
+                                "Updating {} changed paragraphs of {}",
+                                updates.len(),
+                                doc_path
+                            );
+                            for update in updates.iter() {
+                                client
+                                    .update_paragraph(doc_id, &update.par_id, &update.markdown)
+                                    .await?;
+                            }
+                            None
+                        }
+                        None => {
+                            client
+                                .upload_markdown(&doc_path, &doc_markdown.markdown, original)
+                                .await?
+                        }
+                    };
+                    match version {
+                        Some(version) => debug!("Uploaded {} as version {}", doc_path, version),
+                        None => debug!("Uploaded {}", doc_path),
                     }
-                    client
-                        .upload_file(&doc_path, file_path, tim_file_name)
-                        .await?;
-                }
-            }
+                    if let Some(tim_settings) = &doc_markdown.tim_settings {
+                        let doc_id = client.get_item_info(&doc_path).await?.id;
+                        debug!("Setting the document settings of {}", doc_path);
+                        client.set_document_settings(doc_id, tim_settings).await?;
+                    }
+                    let report_doc = SyncReportDocument {
+                        path: doc_path,
+                        previous_version,
+                        version,
+                    };
+                    Some((report_doc, created))
+                };
 
-            let current_doc_markdown = client.download_markdown(&doc_path).await?;
+                progress_bar.inc(1);
 
-            if !prepared_doc.timestamp_equals(&current_doc_markdown) {
-                let doc_markdown = prepared_doc.with_timestamp();
-                client
-                    .upload_markdown(&doc_path, &doc_markdown.markdown)
-                    .await?;
+                Ok::<Option<(SyncReportDocument, bool)>, Error>(uploaded)
+            };
+            async move {
+                upload
+                    .await
+                    .with_context(|| format!("Could not sync document {}", source_path))
             }
+        });
 
-            progress_bar.inc(1);
+        let (results, errors) = if self.keep_going {
+            self.join_limited(uploads)
+                .await
+                .into_iter()
+                .partition_result()
+        } else {
+            let results = self
+                .try_join_limited(uploads)
+                .await
+                .context("Could not sync documents")?;
+            (results, Vec::new())
+        };
+
+        progress_bar.finish_and_clear();
+        progress.finish_and_clear();
+        self.progress.remove(&progress_bar);
+        self.progress.remove(&progress);
 
-            Ok::<(), Error>(())
-        }))
-        .await
-        .context("Could not sync documents")?;
+        let skipped = results.iter().filter(|result| result.is_none()).count();
+        let (created, uploaded): (Vec<_>, Vec<_>) = results
+            .into_iter()
+            .flatten()
+            .partition(|(_, created)| *created);
+        self.print_step_result(&format!(
+            "Uploaded {} documents, skipped {} unchanged documents",
+            created.len() + uploaded.len(),
+            skipped
+        ));
+        summary.created_documents += created.len();
+        summary.updated_documents += uploaded.len();
+        summary.skipped_documents += skipped;
+        let uploaded = created
+            .into_iter()
+            .chain(uploaded)
+            .map(|(report_doc, _)| report_doc)
+            .collect::<Vec<_>>();
 
-        Ok(())
+        Ok((uploaded, errors))
     }
 }
 
-/// Synchronize the project with a remote TIM target.
+/// Create a client for the TIM instance of a sync target.
+/// The host is checked to be a TIM instance, but the client is not logged in.
 ///
 /// # Arguments
 ///
-/// * `opts`: Synchronization options
+/// * `target_info`: The sync target to connect to.
+/// * `progress`: The progress bars to suspend while printing.
 ///
-/// returns: Result<(), Error>
-pub async fn sync_target(opts: SyncOpts) -> Result<()> {
-    let current_dir = std::env::current_dir()?;
-    let project =
-        Project::resolve_from_directory(&current_dir).context("Could not resolve project")?;
+/// returns: Result<TimClient, Error>
+pub(super) async fn build_client(
+    target_info: &SyncTarget,
+    progress: &MultiProgress,
+) -> Result<TimClient> {
+    let mut client_builder = TimClientBuilder::new()
+        .tim_host(&target_info.host)
+        .danger_accept_invalid_certs(target_info.danger_accept_invalid_certs);
+    if let Some(timeout) = target_info.timeout {
+        client_builder = client_builder.timeout(Duration::from_secs(timeout));
+    }
+    if let Some(connect_timeout) = target_info.connect_timeout {
+        client_builder = client_builder.connect_timeout(Duration::from_secs(connect_timeout));
+    }
+    if target_info.danger_accept_invalid_certs {
+        progress.suspend(|| {
+            warn!("TLS certificate verification is disabled for the sync target, do not use this for production instances")
+        });
+    }
 
-    let target_info = project.config.get_target(&opts.target).context(format!(
-        "Could not find sync target {}. Use `timsync target add` to add the target.",
-        opts.target
-    ))?;
+    client_builder
+        .build()
+        .await
+        .context("Could not connect to TIM")
+}
 
-    info!("Syncing to {} ({})...", opts.target, target_info.host);
+/// Log in to TIM with the API token or the credentials of a sync target.
+/// The API token is preferred if both the token and the credentials are set.
+///
+/// # Arguments
+///
+/// * `client`: The client to log in with.
+/// * `target_info`: The sync target to log in to.
+///
+/// returns: Result<(), Error>
+pub(super) async fn log_in(client: &mut TimClient, target_info: &SyncTarget) -> Result<()> {
+    match &target_info.token {
+        Some(token) => client
+            .login_token(token)
+            .await
+            .context("Could not log in to TIM with the API token"),
+        None => client
+            .login_basic(&target_info.username, &target_info.password)
+            .await
+            .context("Could not log in to TIM"),
+    }
+}
 
-    let multi_progress = MultiProgress::new();
+/// Connect and log in to the TIM instance of a sync target.
+/// The logged in user is printed to help catch accidental use of a wrong account.
+///
+/// # Arguments
+///
+/// * `target_info`: The sync target to connect to.
+/// * `progress`: The progress bars to suspend while printing.
+///
+/// returns: Result<TimClient, Error>
+pub(super) async fn connect_to_target(
+    target_info: &SyncTarget,
+    progress: &MultiProgress,
+) -> Result<TimClient> {
+    let mut client = build_client(target_info, progress).await?;
+    log_in(&mut client, target_info).await?;
+
+    // Print the account in use to catch accidental use of a wrong account
+    let current_user = client.current_user().await;
+    progress.suspend(|| match current_user {
+        Ok(user) => info!(
+            "Logged in as {} ({})",
+            user.name,
+            user.real_name.as_deref().unwrap_or("no name set")
+        ),
+        Err(e) => warn!("Could not verify the logged in user: {:#}", e),
+    });
+
+    Ok(client)
+}
+
+/// Synchronize a project with a remote TIM target.
+///
+/// The placeholders in the folder root of the target must be resolved before calling this,
+/// e.g. with `SyncTarget::resolve_placeholders()`.
+/// Locally, only the caches in the project cache folder (`.timsync/cache`) are written;
+/// use the returned report to record the uploaded versions.
+/// Progress bars are drawn to the terminal only if `show_progress` is set in the options.
+///
+/// # Arguments
+///
+/// * `project`: The project to sync
+/// * `target`: The name of the sync target to send the documents to
+/// * `options`: Synchronization options
+///
+/// returns: Result<SyncReport, Error> Report of the uploaded documents.
+/// If `keep_going` is set, the documents that failed to upload are listed in the `errors` of the report.
+pub async fn sync_project(
+    project: &Project,
+    target: &str,
+    options: SyncOptions,
+) -> Result<SyncReport> {
+    let target_info = project.config.require_target(target)?;
+
+    let multi_progress = if options.show_progress && !options.summary_only {
+        MultiProgress::new()
+    } else {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    };
 
     let tick_progress = multi_progress.add(ProgressBar::new_spinner());
 
     tick_progress.set_message("Logging in");
     tick_progress.enable_steady_tick(Duration::from_millis(100));
 
-    let client = TimClientBuilder::new()
-        .tim_host(&target_info.host)
-        .build()
-        .await
-        .context("Could not connect to TIM")?;
-
-    client
-        .login_basic(&target_info.username, &target_info.password)
-        .await
-        .context("Could not log in to TIM")?;
+    let client = connect_to_target(target_info, &multi_progress).await?;
 
     let folder_root_info = match client.get_item_info(&target_info.folder_root).await {
         Ok(info) => info,
@@ -511,25 +1420,1247 @@ pub async fn sync_target(opts: SyncOpts) -> Result<()> {
             return Err(SyncError::SyncTargetNotAFolder(tim_url).into());
         }
     }
+    // Fail early if the user cannot create items in the folder
+    // instead of failing halfway through the sync
+    if !client.can_edit(&target_info.folder_root).await? {
+        let tim_url = format!("{}/{}", target_info.host, target_info.folder_root);
+        return Err(SyncError::SyncTargetNotWritable(tim_url).into());
+    }
+
+    // Prefetch the folder contents to avoid a separate request for each item.
+    // If it fails, item information is requested separately for each item as needed.
+    tick_progress.set_message("Fetching folder contents");
+    match client.prefetch_folder_items(&target_info.folder_root).await {
+        Ok(count) => debug!("Prefetched information for {} items", count),
+        Err(e) => debug!(
+            "Could not prefetch folder contents, falling back to per-item requests: {:#}",
+            e
+        ),
+    }
 
     tick_progress.disable_steady_tick();
     tick_progress.set_message("Uploading project");
 
-    let mut pipeline = SyncPipeline::new(&project, &opts.target, multi_progress)?;
+    let mut pipeline = SyncPipeline::new(project, target, multi_progress)?
+        .with_max_concurrency(options.max_concurrency)
+        .with_keep_going(options.keep_going)
+        .with_paragraph_updates(options.paragraph_updates)
+        .with_summary_only(options.summary_only);
+    let mut summary = SyncSummary::default();
     pipeline.collect_tim_documents()?;
     let documents = pipeline.get_tim_documents();
     let documents = pipeline.create_tim_documents(&client, documents).await?;
+    pipeline
+        .sync_aliases(&client, &documents, options.prune)
+        .await?;
     pipeline.update_project_context(&documents)?;
+    let prepared_documents = pipeline.render_tim_documents(documents)?;
+    let prepared_documents = pipeline
+        .resolve_tim_refs(&client, prepared_documents)
+        .await?;
     pipeline
-        .sync_tim_documents_contents(&client, documents)
+        .sync_files(&client, &prepared_documents, &mut summary)
+        .await?;
+    let (documents, errors) = pipeline
+        .sync_tim_documents_contents(
+            &client,
+            prepared_documents,
+            options.force,
+            options.force_upload,
+            options.record_previous_versions,
+            &mut summary,
+        )
         .await?;
 
+    Ok(SyncReport {
+        target: target.to_string(),
+        documents,
+        summary,
+        errors,
+    })
+}
+
+/// Synchronize the project with a remote TIM target.
+///
+/// # Arguments
+///
+/// * `opts`: Synchronization options
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub async fn sync_target(opts: SyncOpts, project_dir: &Path) -> Result<()> {
+    let mut project = Project::resolve_from_directory(project_dir, opts.env.as_deref())
+        .context("Could not resolve project")?;
+    project.config.strict_templating |= opts.strict_templating;
+    project.config.strict_links |= opts.strict_links;
+
+    if let Some(output_dir) = &opts.output_dir {
+        return render_to_directory(&mut project, &opts, output_dir);
+    }
+
+    project
+        .config
+        .require_target_mut(&opts.target)?
+        .resolve_placeholders(&opts.vars.iter().cloned().collect())?;
+
+    // The target was checked to exist above
+    let target_info = project.config.get_target(&opts.target).unwrap();
+
+    if !opts.summary_only {
+        info!("Syncing to {} ({})...", opts.target, target_info.host);
+    }
+
+    let options = SyncOptions {
+        prune: opts.prune,
+        force: opts.force,
+        force_upload: opts.force_upload,
+        keep_going: opts.keep_going,
+        record_previous_versions: opts.report.is_some(),
+        paragraph_updates: opts.paragraph_updates,
+        summary_only: opts.summary_only,
+        show_progress: true,
+        max_concurrency: opts.max_concurrency,
+    };
+    let report = sync_project(&project, &opts.target, options).await?;
+
+    if let Some(report_path) = &opts.report {
+        let report_json =
+            serde_json::to_string_pretty(&report).context("Could not serialize the sync report")?;
+        std::fs::write(report_path, report_json).with_context(|| {
+            format!(
+                "Could not write the sync report to {}",
+                report_path.display()
+            )
+        })?;
+        info!(
+            "Wrote a report of {} uploaded documents to {}",
+            report.documents.len(),
+            report_path.display()
+        );
+    }
+
+    if !report.errors.is_empty() {
+        for e in report.errors.iter() {
+            error!("<red>{}</> {:#}", LogIcon::Cross, e);
+        }
+        return Err(anyhow::anyhow!(
+            "Could not sync {} documents",
+            report.errors.len()
+        ));
+    }
+
+    if opts.summary_only {
+        let summary = &report.summary;
+        info!(
+            "{} Synced to {}: {} created, {} updated and {} unchanged documents, {} uploaded and {} unchanged files",
+            LogIcon::Tick,
+            opts.target,
+            summary.created_documents,
+            summary.updated_documents,
+            summary.skipped_documents,
+            summary.uploaded_files,
+            summary.skipped_files
+        );
+    } else {
+        info!(
+            "{} Syncing complete! View the documents at {}/view/{}",
+            LogIcon::Tick,
+            target_info.host,
+            target_info.folder_root
+        );
+    }
+
+    Ok(())
+}
+
+/// Render the project documents into Markdown files in a local directory instead of uploading them.
+///
+/// Each document is written to `<output_dir>/<document path>.md`.
+/// TIM is not contacted, so placeholder IDs are used in place of the TIM document IDs,
+/// and references to documents outside the project made with `tim_ref` keep the TIM path of the document.
+/// If the sync target is not configured, placeholder target values are used.
+///
+/// # Arguments
+///
+/// * `project`: The project to render
+/// * `opts`: Synchronization options
+/// * `output_dir`: The directory to write the documents to
+///
+/// returns: Result<(), Error>
+fn render_to_directory(project: &mut Project, opts: &SyncOpts, output_dir: &Path) -> Result<()> {
+    match project.config.get_target_mut(&opts.target) {
+        Some(target) => target.resolve_placeholders(&opts.vars.iter().cloned().collect())?,
+        None => project
+            .config
+            .set_target(&opts.target, placeholder_target()),
+    }
+
+    info!("Rendering documents to {}...", output_dir.display());
+
+    let mut pipeline = SyncPipeline::new(project, &opts.target, MultiProgress::new())?;
+    pipeline.collect_tim_documents()?;
+    let documents = SyncPipeline::with_placeholder_ids(pipeline.get_tim_documents());
+    pipeline.update_project_context(&documents)?;
+    let mut prepared_documents = pipeline.render_tim_documents(documents)?;
+    for (_, prepared_doc) in prepared_documents.iter_mut() {
+        prepared_doc.write_tim_ref_paths();
+    }
+
+    let folder_root = &project.config.get_target(&opts.target).unwrap().folder_root;
+    for (doc_path, prepared_doc) in prepared_documents.iter() {
+        let relative_path = doc_path
+            .strip_prefix(folder_root.as_str())
+            .unwrap_or(doc_path)
+            .trim_start_matches('/');
+        let file_path = output_dir.join(format!("{}.md", relative_path));
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create folder {}", parent.display()))?;
+        }
+        std::fs::write(&file_path, &prepared_doc.markdown)
+            .with_context(|| format!("Could not write document {}", file_path.display()))?;
+        debug!("Wrote {}", file_path.display());
+    }
+
+    warn!(
+        "The documents were not uploaded to TIM. Document IDs are placeholders numbered from 1, references made with tim_ref contain TIM paths instead of document IDs, and links point to /view/{} in TIM. Attached files are not copied.",
+        folder_root
+    );
     info!(
-        "{} Syncing complete! View the documents at {}/view/{}",
+        "{} Rendered {} documents to {}",
         LogIcon::Tick,
-        target_info.host,
-        target_info.folder_root
+        prepared_documents.len(),
+        output_dir.display()
     );
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use crate::processing::task_processor::TASKS_DOCPATH;
+    use crate::util::fake_tim::FakeTim;
+    use crate::util::mock_server::{MockResponse, MockServer};
+    use crate::util::test_project::{self, write_file};
+
+    use super::*;
+
+    const FOLDER_ROOT: &str = "kurssit/ohj1";
+
+    /// Create a project with a default sync target to `kurssit/ohj1` and the given files.
+    fn project(files: &[(&str, &str)]) -> (TempDir, Project) {
+        project_with_target_config("", files)
+    }
+
+    /// Create a project with a default sync target to `kurssit/ohj1` and the given files.
+    /// The target config is appended after the default sync target, so it can also add other sections.
+    fn project_with_target_config(
+        target_config: &str,
+        files: &[(&str, &str)],
+    ) -> (TempDir, Project) {
+        test_project::project(
+            &format!(
+                "[targets.default]\nhost = \"https://tim.example.com\"\nfolder_root = \"{}\"\n{}",
+                FOLDER_ROOT, target_config
+            ),
+            files,
+        )
+    }
+
+    fn pipeline(project: &Project) -> SyncPipeline<'_> {
+        SyncPipeline::new(
+            project,
+            "default",
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+        )
+        .unwrap()
+    }
+
+    /// Run the sync steps of `sync_project` after logging in against the fake TIM instance.
+    async fn sync(project: &Project, tim: &FakeTim, force: bool) -> Result<SyncSummary> {
+        sync_with(pipeline(project), tim, force).await
+    }
+
+    /// Run the sync steps of `sync_project` with the given pipeline against a TIM instance.
+    async fn sync_with(
+        mut pipeline: SyncPipeline<'_>,
+        tim: &impl TimApi,
+        force: bool,
+    ) -> Result<SyncSummary> {
+        let mut summary = SyncSummary::default();
+        pipeline.collect_tim_documents()?;
+        let documents = pipeline.get_tim_documents();
+        let documents = pipeline.create_tim_documents(tim, documents).await?;
+        pipeline.update_project_context(&documents)?;
+        let prepared_documents = pipeline.render_tim_documents(documents)?;
+        let prepared_documents = pipeline.resolve_tim_refs(tim, prepared_documents).await?;
+        pipeline
+            .sync_files(tim, &prepared_documents, &mut summary)
+            .await?;
+        pipeline
+            .sync_tim_documents_contents(tim, prepared_documents, force, false, false, &mut summary)
+            .await?;
+        Ok(summary)
+    }
+
+    fn tim_path(path: &str) -> String {
+        format!("{}/{}", FOLDER_ROOT, path)
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_creates_folders_before_documents() {
+        let (_dir, project) = project(&[
+            ("intro.md", "Intro"),
+            ("week1/lecture.md", "Lecture"),
+            ("week1/exercises/task.md", "Task"),
+        ]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let documents = pipeline
+            .create_tim_documents(&tim, documents)
+            .await
+            .unwrap();
+
+        let calls = tim.calls();
+        let position = |call: String| calls.iter().position(|c| *c == call).unwrap();
+        assert!(
+            position(format!("create folder {}", tim_path("week1")))
+                < position(format!("create document {}", tim_path("week1/lecture"))),
+            "{:?}",
+            calls
+        );
+        assert!(
+            position(format!("create folder {}", tim_path("week1/exercises")))
+                < position(format!(
+                    "create document {}",
+                    tim_path("week1/exercises/task")
+                )),
+            "{:?}",
+            calls
+        );
+        // The tasks document of the project is created along with the documents
+        assert!(calls.contains(&format!("create document {}", tim_path(TASKS_DOCPATH))));
+        assert_eq!(tim.count("create "), 6, "{:?}", calls);
+
+        assert_eq!(documents.len(), 4);
+        for doc in documents.iter() {
+            let item = tim.item(&tim_path(doc.path)).unwrap();
+            assert_eq!(doc.id, Some(item.id), "{}", doc.path);
+        }
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_keeps_existing_items() {
+        let (_dir, project) = project(&[("week1/lecture.md", "Lecture")]);
+        let tim = FakeTim::new()
+            .with_folder(FOLDER_ROOT)
+            .with_folder(&tim_path("week1"))
+            .with_document(&tim_path("week1/lecture"), "lecture", "");
+        let id = tim.item(&tim_path("week1/lecture")).unwrap().id;
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let documents = pipeline
+            .create_tim_documents(&tim, documents)
+            .await
+            .unwrap();
+
+        let lecture = documents
+            .iter()
+            .find(|doc| doc.path == "week1/lecture")
+            .unwrap();
+        assert_eq!(lecture.id, Some(id));
+        assert_eq!(
+            tim.calls(),
+            vec![format!("create document {}", tim_path(TASKS_DOCPATH))]
+        );
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_rejects_document_and_folder_with_same_path() {
+        let (_dir, project) = project(&[("week1.md", "Week"), ("week1/lecture.md", "Lecture")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let Err(err) = pipeline.create_tim_documents(&tim, documents).await else {
+            panic!("Expected an item type conflict");
+        };
+
+        assert!(
+            matches!(err.downcast_ref(), Some(SyncError::ItemTypeConflict(_))),
+            "{:#}",
+            err
+        );
+        assert!(tim.calls().is_empty(), "{:?}", tim.calls());
+    }
+
+    #[tokio::test]
+    async fn folder_tim_path_creates_index_document_in_the_folder() {
+        let (_dir, project) = project(&[
+            ("week1.md", "---\ntim_path: week1/\n---\nWeek 1"),
+            ("week1/intro.md", "Intro"),
+        ]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let documents = pipeline
+            .create_tim_documents(&tim, documents)
+            .await
+            .unwrap();
+
+        let calls = tim.calls();
+        for call in [
+            format!("create folder {}", tim_path("week1")),
+            format!("create document {}", tim_path("week1/index")),
+            format!("create document {}", tim_path("week1/intro")),
+        ] {
+            assert!(calls.contains(&call), "{:?}", calls);
+        }
+        let index = documents
+            .iter()
+            .find(|doc| doc.path == "week1/index")
+            .unwrap();
+        assert_eq!(
+            index.id,
+            Some(tim.item(&tim_path("week1/index")).unwrap().id)
+        );
+    }
+
+    #[test]
+    fn collect_tim_documents_rejects_colliding_tim_paths() {
+        let (_dir, project) = project(&[
+            ("week1/intro.md", "---\ntim_path: intro\n---\nWeek 1"),
+            ("intro.md", "Intro"),
+        ]);
+        let mut pipeline = pipeline(&project);
+        let Err(err) = pipeline.collect_tim_documents() else {
+            panic!("Expected a path conflict");
+        };
+
+        let message = format!("{:#}", err);
+        assert!(message.contains(" intro.md"), "{}", message);
+        assert!(message.contains("week1/intro.md"), "{}", message);
+        assert!(message.contains("'intro'"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn update_project_context_rejects_duplicate_uids() {
+        let (_dir, project) = project(&[
+            ("a.md", "---\nuid: x\n---\nA"),
+            ("week1/b.md", "---\nuid: x\n---\nB"),
+            ("c.md", "---\nuid: y\n---\nC"),
+        ]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let documents = pipeline
+            .create_tim_documents(&tim, documents)
+            .await
+            .unwrap();
+        let err = pipeline.update_project_context(&documents).unwrap_err();
+
+        assert!(
+            matches!(
+                err.downcast_ref::<SyncError>(),
+                Some(SyncError::DuplicateDocumentUid(uid, _)) if uid == "x"
+            ),
+            "{:#}",
+            err
+        );
+        let message = format!("{:#}", err);
+        assert!(message.contains("a.md"), "{}", message);
+        assert!(message.contains("week1/b.md"), "{}", message);
+        assert!(!message.contains("c.md"), "{}", message);
+    }
+
+    /// Start a mock TIM server where all items of the sync target exist.
+    /// The documents have the language `lang_id`.
+    async fn existing_items_server(lang_id: &'static str) -> MockServer {
+        MockServer::start(move |request| {
+            let Some(path) = request.path.strip_prefix("/itemInfo/") else {
+                return MockResponse::json(json!({}));
+            };
+            let (location, short_name) = path.rsplit_once('/').unwrap_or(("", path));
+            let item_type = if path.starts_with(FOLDER_ROOT) && path != FOLDER_ROOT {
+                "document"
+            } else {
+                "folder"
+            };
+            MockResponse::json(json!({
+                "id": path.len(),
+                "type": item_type,
+                "title": short_name,
+                "location": location,
+                "short_name": short_name,
+                "lang_id": lang_id,
+            }))
+        })
+        .await
+    }
+
+    /// Run `create_tim_documents` for a project with a single document in language `fi`.
+    async fn create_document_in_finnish(server: &MockServer) {
+        let (_dir, project) = project(&[("intro.md", "---\ntitle: intro\nlang: fi\n---\nIntro")]);
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        pipeline
+            .create_tim_documents(&server.client(), documents)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_skips_unchanged_language() {
+        let server = existing_items_server("fi").await;
+        create_document_in_finnish(&server).await;
+        assert_eq!(server.count("POST", "/translation/"), 0);
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_sets_changed_language() {
+        let server = existing_items_server("en").await;
+        create_document_in_finnish(&server).await;
+        let requests = server.requests();
+        let translations = requests
+            .iter()
+            .filter(|request| request.path.starts_with("/translation/"))
+            .collect::<Vec<_>>();
+        assert_eq!(translations.len(), 1, "{:?}", requests);
+        assert_eq!(translations[0].json()["new_langid"], "fi");
+    }
+
+    const TEMPLATE_DOC: &str = "kurssit/pohja";
+
+    fn tim_with_template() -> FakeTim {
+        FakeTim::new()
+            .with_folder("kurssit")
+            .with_folder(FOLDER_ROOT)
+            .with_document(TEMPLATE_DOC, "Template", "Template contents\n")
+    }
+
+    #[tokio::test]
+    async fn sync_overwrites_template_contents_of_created_documents() {
+        let (_dir, project) = project_with_target_config(
+            &format!("template_doc = \"{}\"\n", TEMPLATE_DOC),
+            &[("intro.md", "Intro")],
+        );
+        let tim = tim_with_template();
+
+        let summary = sync(&project, &tim, false).await.unwrap();
+
+        assert!(summary.created_documents > 0, "{:?}", summary);
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        assert!(
+            markdown.contains("Intro") && !markdown.contains("Template contents"),
+            "{}",
+            markdown
+        );
+        let summary = sync(&project, &tim, false).await.unwrap();
+        assert_eq!(summary.updated_documents, 0, "{:?}", summary);
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_checks_existing_documents_once_with_template() {
+        let server = existing_items_server("fi").await;
+        let (_dir, project) = project_with_target_config(
+            &format!("template_doc = \"{}\"\n", TEMPLATE_DOC),
+            &[("intro.md", "Intro")],
+        );
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        pipeline
+            .create_tim_documents(&server.client(), documents)
+            .await
+            .unwrap();
+
+        let item_info_path = format!("/itemInfo/{}", tim_path("intro"));
+        assert_eq!(
+            server.count("GET", &item_info_path),
+            1,
+            "{:?}",
+            server
+                .requests()
+                .iter()
+                .map(|r| &r.path)
+                .collect::<Vec<_>>()
+        );
+        assert!(pipeline.created_from_template.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_rejects_folders_without_edit_rights() {
+        let (_dir, project) = project(&[
+            ("intro.md", "Intro"),
+            ("week1/a.md", "A"),
+            ("week1/b.md", "B"),
+        ]);
+        let tim = FakeTim::new()
+            .with_folder(FOLDER_ROOT)
+            .with_read_only_folder(&tim_path("week1"));
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let Err(err) = pipeline.create_tim_documents(&tim, documents).await else {
+            panic!("Expected missing edit rights");
+        };
+
+        assert!(
+            matches!(
+                err.downcast_ref(),
+                Some(TimClientErrors::NoEditRights(item, folder))
+                    if *item == tim_path("week1/a") && *folder == tim_path("week1")
+            ),
+            "{:#}",
+            err
+        );
+        // The documents of the editable folders are created before the read-only folder is reached
+        assert!(
+            tim.calls()
+                .contains(&format!("create document {}", tim_path("intro"))),
+            "{:?}",
+            tim.calls()
+        );
+        assert_eq!(
+            tim.count(&format!("create document {}", tim_path("week1"))),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn create_tim_documents_checks_edit_rights_once_per_folder() {
+        let server = existing_items_server("fi").await;
+        let (_dir, project) = project(&[("a.md", "A"), ("b.md", "B"), ("c.md", "C")]);
+        let mut pipeline = pipeline(&project);
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        pipeline
+            .create_tim_documents(&server.client(), documents)
+            .await
+            .unwrap();
+
+        let folder_info_path = format!("/itemInfo/{}", FOLDER_ROOT);
+        let folder_requests = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.method == "GET" && request.path == folder_info_path)
+            .count();
+        assert_eq!(folder_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn sync_project_stops_if_folder_root_is_not_editable() {
+        let server = MockServer::start(|request| {
+            let path = request.path.as_str();
+            if path == "/" {
+                return MockResponse::status(200)
+                    .with_header("Set-Cookie", "XSRF-TOKEN=abc; Path=/");
+            }
+            if path == "/ping" {
+                return MockResponse::json(json!({"status": "ok"}));
+            }
+            if path == "/user/current" {
+                return MockResponse::json(json!({"id": 1, "name": "teacher"}));
+            }
+            let folder = json!({
+                "id": 1,
+                "type": "folder",
+                "title": "ohj1",
+                "location": "kurssit",
+                "short_name": "ohj1",
+            });
+            if path == format!("/itemInfo/{}", FOLDER_ROOT) {
+                return MockResponse::json(folder);
+            }
+            if path.starts_with("/getItems?folder=kurssit&") {
+                let mut folder = folder;
+                folder["rights"] = json!({"editable": false, "manage": false, "owner": false});
+                return MockResponse::json(json!([folder]));
+            }
+            MockResponse::json(json!({}))
+        })
+        .await;
+        let (_dir, project) = test_project::project(
+            &format!(
+                "[targets.default]\nhost = \"{}\"\nfolder_root = \"{}\"\ntoken = \"secret\"\n",
+                server.url(),
+                FOLDER_ROOT
+            ),
+            &[("intro.md", "Intro")],
+        );
+
+        let err = sync_project(&project, "default", SyncOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                err.downcast_ref(),
+                Some(SyncError::SyncTargetNotWritable(_))
+            ),
+            "{:#}",
+            err
+        );
+        // Nothing is created or uploaded
+        assert!(
+            server
+                .requests()
+                .iter()
+                .all(|request| request.method == "GET"),
+            "{:?}",
+            server
+                .requests()
+                .iter()
+                .map(|r| &r.path)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_downloads_each_uploaded_document_once() {
+        // All documents of the sync target exist and are empty
+        let server = MockServer::start(|request| {
+            let path = request.path.as_str();
+            if let Some(item_path) = path.strip_prefix("/itemInfo/") {
+                let (location, short_name) = item_path.rsplit_once('/').unwrap();
+                let (id, item_type) = match short_name {
+                    "ohj1" => (1, "folder"),
+                    "a" => (2, "document"),
+                    "b" => (3, "document"),
+                    _ => (4, "document"),
+                };
+                return MockResponse::json(json!({
+                    "id": id,
+                    "type": item_type,
+                    "title": short_name,
+                    "location": location,
+                    "short_name": short_name,
+                }));
+            }
+            if path.starts_with("/download/") {
+                return MockResponse::text(200, "");
+            }
+            MockResponse::json(json!({}))
+        })
+        .await;
+        let (_dir, project) = project(&[("a.md", "A"), ("b.md", "B")]);
+
+        let summary = sync_with(pipeline(&project), &server.client(), false)
+            .await
+            .unwrap();
+
+        // The empty tasks document of the project is uploaded as well to write its settings block
+        assert_eq!(summary.created_documents, 3, "{:?}", summary);
+        assert_eq!(server.count("POST", "/update/"), 3);
+        assert_eq!(server.count("GET", "/download/"), 3);
+    }
+
+    #[tokio::test]
+    async fn sync_rejects_existing_documents_with_template_contents() {
+        let (_dir, project) = project_with_target_config(
+            &format!("template_doc = \"{}\"\n", TEMPLATE_DOC),
+            &[("intro.md", "Intro")],
+        );
+        // The document was created earlier, so the contents may be edits made in TIM
+        let tim =
+            tim_with_template().with_document(&tim_path("intro"), "intro", "Template contents\n");
+
+        let err = sync(&project, &tim, false).await.unwrap_err();
+
+        assert!(
+            err.chain()
+                .any(|e| matches!(e.downcast_ref(), Some(SyncError::RemoteModified(_)))),
+            "{:#}",
+            err
+        );
+    }
+
+    const LECTURE_WITH_SETTINGS: &str = "---\ntim_settings:\n  css: lecture.css\n---\nLecture\n";
+
+    #[tokio::test]
+    async fn sync_emits_settings_block_by_default() {
+        let (_dir, project) = project(&[("lecture.md", LECTURE_WITH_SETTINGS)]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+
+        sync(&project, &tim, false).await.unwrap();
+
+        let lecture = tim.item(&tim_path("lecture")).unwrap();
+        assert!(
+            lecture.markdown.contains("css: lecture.css"),
+            "{}",
+            lecture.markdown
+        );
+        assert!(lecture.settings.is_empty());
+        assert_eq!(tim.count("settings "), 0, "{:?}", tim.calls());
+    }
+
+    #[tokio::test]
+    async fn sync_sets_settings_with_api() {
+        let (dir, project) = project_with_target_config(
+            "[markdown]\nsettings_mode = \"api\"\n",
+            &[("lecture.md", LECTURE_WITH_SETTINGS)],
+        );
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+
+        sync(&project, &tim, false).await.unwrap();
+
+        let lecture = tim.item(&tim_path("lecture")).unwrap();
+        assert!(!lecture.markdown.contains("css:"), "{}", lecture.markdown);
+        assert_eq!(lecture.settings.get("css"), Some(&json!("lecture.css")));
+
+        // Unchanged settings are not set again
+        let summary = sync(&project, &tim, false).await.unwrap();
+        assert_eq!(summary.updated_documents, 0, "{:?}", summary);
+        assert_eq!(tim.count("settings "), 1, "{:?}", tim.calls());
+
+        // Changing only the settings uploads the document again
+        write_file(
+            &dir,
+            "lecture.md",
+            &LECTURE_WITH_SETTINGS.replace("lecture.css", "dark.css"),
+        );
+        let summary = sync(&project, &tim, false).await.unwrap();
+        assert_eq!(summary.updated_documents, 1, "{:?}", summary);
+        let lecture = tim.item(&tim_path("lecture")).unwrap();
+        assert_eq!(lecture.settings.get("css"), Some(&json!("dark.css")));
+    }
+
+    #[tokio::test]
+    async fn sync_skips_unchanged_documents() {
+        let (_dir, project) = project(&[("intro.md", "Intro")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+
+        // The empty tasks document of the project is uploaded as well to write its settings block
+        let summary = sync(&project, &tim, false).await.unwrap();
+        assert_eq!(summary.created_documents, 2, "{:?}", summary);
+        assert_eq!(summary.skipped_documents, 0, "{:?}", summary);
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        assert!(markdown.contains("Intro"), "{}", markdown);
+
+        let summary = sync(&project, &tim, false).await.unwrap();
+        assert_eq!(summary.skipped_documents, 2, "{:?}", summary);
+        assert_eq!(summary.updated_documents, 0);
+        assert_eq!(tim.count("upload "), 2, "{:?}", tim.calls());
+    }
+
+    /// Logger that records the messages logged on each test thread,
+    /// so that tests can check what the pipeline prints.
+    struct StepLogger(std::sync::Mutex<Vec<(Option<String>, String)>>);
+
+    impl log::Log for StepLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let thread = std::thread::current().name().map(str::to_string);
+            self.0
+                .lock()
+                .unwrap()
+                .push((thread, record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static STEP_LOGGER: StepLogger = StepLogger(std::sync::Mutex::new(Vec::new()));
+
+    /// Install `STEP_LOGGER` as the logger, unless another test already installed it.
+    fn install_step_logger() {
+        if log::set_logger(&STEP_LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Info);
+        }
+    }
+
+    /// Messages logged on the current thread since the start of the test.
+    fn logged_messages() -> Vec<String> {
+        let thread = std::thread::current().name().map(str::to_string);
+        STEP_LOGGER
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(t, _)| *t == thread)
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn summary_only_hides_step_results_and_fills_summary() {
+        install_step_logger();
+        let (_dir, project) = project(&[("a.md", "A"), ("b.md", "B")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+
+        let summary = sync_with(pipeline(&project).with_summary_only(true), &tim, false)
+            .await
+            .unwrap();
+
+        let messages = logged_messages();
+        assert!(
+            !messages.iter().any(|message| message.contains("Uploaded")),
+            "{:?}",
+            messages
+        );
+        // The empty tasks document of the project is uploaded as well to write its settings block
+        assert_eq!(summary.created_documents, 3, "{:?}", summary);
+        assert_eq!(summary.updated_documents, 0, "{:?}", summary);
+    }
+
+    #[tokio::test]
+    async fn step_results_are_printed_without_summary_only() {
+        install_step_logger();
+        let (_dir, project) = project(&[("a.md", "A"), ("b.md", "B")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+
+        sync(&project, &tim, false).await.unwrap();
+
+        let messages = logged_messages();
+        assert!(
+            messages
+                .iter()
+                .any(|message| message.contains("Uploaded 3 documents")),
+            "{:?}",
+            messages
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_uploads_locally_changed_documents() {
+        let (dir, project) = project(&[("intro.md", "Intro")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+
+        write_file(&dir, "intro.md", "Changed intro");
+        let summary = sync(&project, &tim, false).await.unwrap();
+
+        assert_eq!(summary.updated_documents, 1);
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        assert!(markdown.contains("Changed intro"), "{}", markdown);
+    }
+
+    #[tokio::test]
+    async fn sync_uploads_paragraph_splits() {
+        let (dir, project) = project(&[("intro.md", "First\nSecond\n")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+
+        write_file(&dir, "intro.md", "First\n\nSecond\n");
+        let summary = sync(&project, &tim, false).await.unwrap();
+
+        assert_eq!(summary.updated_documents, 1, "{:?}", summary);
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        assert!(markdown.contains("First\n\nSecond"), "{}", markdown);
+    }
+
+    #[tokio::test]
+    async fn sync_writes_settings_block_to_cleaned_document() {
+        let (dir, project) = project(&[("intro.md", "Intro\n")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+        // The document was turned back into a regular TIM document with `timsync clean`
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        let cleaned = PreparedDocument::without_timsync_settings(&markdown).unwrap();
+        tim.edit_markdown(&tim_path("intro"), &cleaned);
+
+        sync(&project, &tim, false).await.unwrap();
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        assert!(PreparedDocument::has_timestamp(&markdown), "{}", markdown);
+
+        // Later local edits are uploaded without --force
+        write_file(&dir, "intro.md", "Changed intro\n");
+        let summary = sync(&project, &tim, false).await.unwrap();
+        assert_eq!(summary.updated_documents, 1, "{:?}", summary);
+    }
+
+    #[tokio::test]
+    async fn sync_keeps_remote_edits_of_unchanged_documents() {
+        let (_dir, project) = project(&[("intro.md", "Intro")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+
+        let edited = tim.item(&tim_path("intro")).unwrap().markdown + "\nEdited in TIM\n";
+        tim.edit_markdown(&tim_path("intro"), &edited);
+        let summary = sync(&project, &tim, false).await.unwrap();
+
+        assert_eq!(summary.skipped_documents, 2, "{:?}", summary);
+        assert_eq!(tim.item(&tim_path("intro")).unwrap().markdown, edited);
+    }
+
+    #[tokio::test]
+    async fn sync_rejects_local_changes_to_remotely_modified_documents() {
+        let (dir, project) = project(&[("intro.md", "Intro")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+
+        let edited = tim.item(&tim_path("intro")).unwrap().markdown + "\nEdited in TIM\n";
+        tim.edit_markdown(&tim_path("intro"), &edited);
+        write_file(&dir, "intro.md", "Changed intro");
+        let err = sync(&project, &tim, false).await.unwrap_err();
+
+        assert!(
+            err.chain()
+                .any(|e| matches!(e.downcast_ref(), Some(SyncError::RemoteModified(_)))),
+            "{:#}",
+            err
+        );
+        assert_eq!(tim.item(&tim_path("intro")).unwrap().markdown, edited);
+
+        let summary = sync(&project, &tim, true).await.unwrap();
+        assert_eq!(summary.updated_documents, 1);
+        let markdown = tim.item(&tim_path("intro")).unwrap().markdown;
+        assert!(
+            markdown.contains("Changed intro") && !markdown.contains("Edited in TIM"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[tokio::test]
+    async fn keep_going_reports_every_failed_upload() {
+        let (dir, project) = project(&[("a.md", "A"), ("b.md", "B"), ("c.md", "C")]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+
+        for name in ["a", "c"] {
+            let edited = tim.item(&tim_path(name)).unwrap().markdown + "\nEdited in TIM\n";
+            tim.edit_markdown(&tim_path(name), &edited);
+        }
+        for (name, contents) in [
+            ("a.md", "Changed A"),
+            ("b.md", "Changed B"),
+            ("c.md", "Changed C"),
+        ] {
+            write_file(&dir, name, contents);
+        }
+
+        let mut pipeline = pipeline(&project).with_keep_going(true);
+        let mut summary = SyncSummary::default();
+        pipeline.collect_tim_documents().unwrap();
+        let documents = pipeline.get_tim_documents();
+        let documents = pipeline
+            .create_tim_documents(&tim, documents)
+            .await
+            .unwrap();
+        pipeline.update_project_context(&documents).unwrap();
+        let prepared_documents = pipeline.render_tim_documents(documents).unwrap();
+        let (uploaded, errors) = pipeline
+            .sync_tim_documents_contents(
+                &tim,
+                prepared_documents,
+                false,
+                false,
+                false,
+                &mut summary,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            uploaded
+                .iter()
+                .map(|doc| doc.path.as_str())
+                .collect::<Vec<_>>(),
+            vec![tim_path("b")]
+        );
+        assert!(tim
+            .item(&tim_path("b"))
+            .unwrap()
+            .markdown
+            .contains("Changed B"));
+        let messages = errors
+            .iter()
+            .map(|err| format!("{:#}", err))
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].contains(&tim_path("a")), "{}", messages[0]);
+        assert!(messages[1].contains(&tim_path("c")), "{}", messages[1]);
+        for err in errors {
+            assert!(
+                err.chain()
+                    .any(|e| matches!(e.downcast_ref(), Some(SyncError::RemoteModified(_)))),
+                "{:#}",
+                err
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn paragraph_updates_send_only_the_changed_paragraph() {
+        let markdown = "#- {id=\"aaaaaaaaaaaa\"}\nFirst\n\n#- {id=\"bbbbbbbbbbbb\"}\nSecond\n";
+        let (dir, project) = project(&[("doc.md", markdown)]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+
+        // TIM adds an ID to the settings block when the document is uploaded
+        let stored = tim.item(&tim_path("doc")).unwrap().markdown.replacen(
+            "``` {",
+            "``` {id=\"settingsPar\" ",
+            1,
+        );
+        tim.edit_markdown(&tim_path("doc"), &stored);
+        write_file(&dir, "doc.md", &markdown.replace("Second", "Changed"));
+        let calls_before = tim.calls().len();
+
+        sync_with(pipeline(&project).with_paragraph_updates(true), &tim, false)
+            .await
+            .unwrap();
+
+        // The settings block is updated with the new hash, but the document is not uploaded
+        assert_eq!(
+            tim.calls()[calls_before..],
+            [
+                format!("update paragraph {} settingsPar", tim_path("doc")),
+                format!("update paragraph {} bbbbbbbbbbbb", tim_path("doc")),
+            ]
+        );
+        assert!(tim
+            .item(&tim_path("doc"))
+            .unwrap()
+            .markdown
+            .contains("#- {id=\"bbbbbbbbbbbb\"}\nChanged\n"));
+    }
+
+    #[tokio::test]
+    async fn paragraph_updates_request_the_markdown_with_ids() {
+        let markdown = "#- {id=\"aaaaaaaaaaaa\"}\nFirst\n\n#- {id=\"bbbbbbbbbbbb\"}\nSecond\n";
+        let (dir, project) = project(&[("doc.md", markdown)]);
+        let tim = FakeTim::new().with_folder(FOLDER_ROOT);
+        sync(&project, &tim, false).await.unwrap();
+        // TIM adds an ID to the settings block when the document is uploaded
+        let with_ids = tim.item(&tim_path("doc")).unwrap().markdown.replacen(
+            "``` {",
+            "``` {id=\"settingsPar\" ",
+            1,
+        );
+        // Without the IDs requested, TIM leaves them out of the downloaded markdown
+        let without_ids = with_ids
+            .replace("id=\"settingsPar\" ", "")
+            .replace(" {id=\"aaaaaaaaaaaa\"}", "")
+            .replace(" {id=\"bbbbbbbbbbbb\"}", "");
+        let doc_info_path = format!("/itemInfo/{}", tim_path("doc"));
+        let server = MockServer::start(move |request| {
+            let path = request.path.as_str();
+            if let Some(item_path) = path.strip_prefix("/itemInfo/") {
+                let (location, short_name) = item_path.rsplit_once('/').unwrap();
+                let (id, item_type) = match path {
+                    _ if path == doc_info_path => (2, "document"),
+                    _ if item_path == FOLDER_ROOT => (1, "folder"),
+                    _ => (3, "document"),
+                };
+                return MockResponse::json(json!({
+                    "id": id,
+                    "type": item_type,
+                    "title": short_name,
+                    "location": location,
+                    "short_name": short_name,
+                }));
+            }
+            match path {
+                "/download/2?with_ids=true" => MockResponse::text(200, &with_ids),
+                "/download/2" => MockResponse::text(200, &without_ids),
+                _ if path.starts_with("/download/") => MockResponse::text(200, ""),
+                _ => MockResponse::json(json!({})),
+            }
+        })
+        .await;
+        write_file(&dir, "doc.md", &markdown.replace("Second", "Changed"));
+
+        sync_with(
+            pipeline(&project).with_paragraph_updates(true),
+            &server.client(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(server.count("GET", "/download/2?with_ids=true"), 1);
+        assert_eq!(server.count("POST", "/update/2"), 0);
+        let requests = server.requests();
+        let updates = requests
+            .iter()
+            .filter(|request| request.path == "/postParagraph/")
+            .map(|request| request.json())
+            .collect::<Vec<_>>();
+        assert_eq!(updates.len(), 2, "{:?}", updates);
+        assert_eq!(updates[0]["docId"], 2);
+        assert_eq!(updates[0]["par"], "settingsPar");
+        assert!(updates[0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("``` {id=\"settingsPar\" settings=\"timsync\"}"));
+        assert_eq!(
+            updates[1],
+            json!({
+                "docId": 2,
+                "par": "bbbbbbbbbbbb",
+                "text": "#- {id=\"bbbbbbbbbbbb\"}\nChanged\n",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_var_splits_at_first_equals_sign() {
+        assert_eq!(
+            parse_var("year=2025"),
+            Ok(("year".to_string(), "2025".to_string()))
+        );
+        assert_eq!(
+            parse_var("query=a=b"),
+            Ok(("query".to_string(), "a=b".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_var_trims_name_but_not_value() {
+        assert_eq!(
+            parse_var(" year = 2025"),
+            Ok(("year".to_string(), " 2025".to_string()))
+        );
+        assert_eq!(
+            parse_var("empty="),
+            Ok(("empty".to_string(), String::new()))
+        );
+    }
+
+    #[test]
+    fn parse_var_requires_equals_sign() {
+        assert!(parse_var("year").is_err());
+    }
+
+    /// Parse the sync options from command line arguments.
+    fn sync_opts(args: &[&str]) -> SyncOpts {
+        #[derive(clap::Parser)]
+        struct Cli {
+            #[command(flatten)]
+            opts: SyncOpts,
+        }
+        <Cli as clap::Parser>::parse_from(std::iter::once("sync").chain(args.iter().copied())).opts
+    }
+
+    #[test]
+    fn render_to_directory_writes_tim_ref_paths() {
+        let (dir, mut project) = project(&[(
+            "week1/intro.md",
+            "{{tim_ref \"kurssit/tie/materiaali\" \"example\"}}",
+        )]);
+        let output_dir = dir.path().join("out");
+        let opts = sync_opts(&["--output-dir", &output_dir.to_string_lossy()]);
+        render_to_directory(&mut project, &opts, &output_dir).unwrap();
+
+        let markdown = std::fs::read_to_string(output_dir.join("week1/intro.md")).unwrap();
+        assert!(
+            markdown.contains("#- {rd=\"kurssit/tie/materiaali\" ra=\"example\"}"),
+            "{}",
+            markdown
+        );
+    }
+}
@@ -1,14 +1,184 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
 use dialoguer::{Input, Password};
 use indicatif::ProgressBar;
 use simplelog::__private::paris::LogIcon;
 use simplelog::{error, info};
 
-use crate::project::config::{SyncTarget, DEFAULT_SYNC_TARGET_HOST};
+use crate::project::config::{SyncConfig, SyncTarget, DEFAULT_SYNC_TARGET_HOST};
+use crate::project::project::Project;
 use crate::util::tim_client::TimClientBuilder;
 
+#[derive(Debug, Args)]
+pub struct TargetOpts {
+    #[command(subcommand)]
+    command: TargetCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum TargetCommand {
+    #[command(name = "add")]
+    /// Add a sync target to the project config
+    Add(TargetAddOpts),
+}
+
+#[derive(Debug, Args)]
+struct TargetAddOpts {
+    #[arg(default_value = "default")]
+    /// The name of the sync target to add. Defaults to "default".
+    name: String,
+
+    #[arg(long)]
+    /// TIM host to which to sync the files. Defaults to https://tim.jyu.fi.
+    /// If none of the target details are given, they are asked interactively.
+    host: Option<String>,
+
+    #[arg(long)]
+    /// Path to the TIM folder to which to sync the files, e.g. `kurssit/tie/kurssi`.
+    folder_root: Option<String>,
+
+    #[arg(long)]
+    /// The username to use when authenticating to TIM.
+    username: Option<String>,
+
+    #[arg(long, conflicts_with = "password_stdin")]
+    /// The password to use when authenticating to TIM.
+    /// Prefer `--password-stdin`, as command line arguments are visible to other users in process listings.
+    password: Option<String>,
+
+    #[arg(long)]
+    /// Read the password from the standard input. A single trailing newline is removed.
+    password_stdin: bool,
+
+    #[arg(short, long)]
+    /// Replace the sync target if a target with the same name already exists.
+    force: bool,
+}
+
+/// Run a `timsync target` subcommand.
+///
+/// # Arguments
+///
+/// * `opts`: Target command options
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub async fn run_target_command(opts: TargetOpts, project_dir: &Path) -> Result<()> {
+    match opts.command {
+        TargetCommand::Add(opts) => add_target(opts, project_dir).await,
+    }
+}
+
+/// Add a sync target to the base config file of the project.
+/// The target details are asked interactively if none of them are given as options.
+///
+/// # Arguments
+///
+/// * `opts`: Options of the target to add
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+async fn add_target(opts: TargetAddOpts, project_dir: &Path) -> Result<()> {
+    let project =
+        Project::resolve_from_directory(project_dir, None).context("Could not resolve project")?;
+    if project.config.get_target(&opts.name).is_some() && !opts.force {
+        return Err(anyhow::anyhow!(
+            "Sync target {} already exists. Use --force to replace it.",
+            opts.name
+        ));
+    }
+
+    let interactive = opts.host.is_none()
+        && opts.folder_root.is_none()
+        && opts.username.is_none()
+        && opts.password.is_none()
+        && !opts.password_stdin;
+    let target = if interactive {
+        match prompt_user_details_interactive().await? {
+            Some(target) => target,
+            None => return Ok(()),
+        }
+    } else {
+        let (Some(folder_root), Some(username)) = (opts.folder_root, opts.username) else {
+            return Err(anyhow::anyhow!(
+                "Both --folder-root and --username must be given to add a target without prompts"
+            ));
+        };
+        let password = match opts.password {
+            Some(password) => password,
+            None if opts.password_stdin => read_password(std::io::stdin())?,
+            None => Password::new()
+                .with_prompt("Password")
+                .interact()
+                .context("No password given")?,
+        };
+        SyncTarget {
+            host: opts
+                .host
+                .unwrap_or_else(|| DEFAULT_SYNC_TARGET_HOST.to_string()),
+            folder_root,
+            username,
+            password,
+            shared_files: false,
+            vars: HashMap::new(),
+            timeout: None,
+            connect_timeout: None,
+            danger_accept_invalid_certs: false,
+            token: None,
+            owner_group: None,
+            template_doc: None,
+        }
+    };
+
+    // Only the base config is modified, so that the overrides are not copied into it
+    let config_path = project.config_file_path();
+    let mut config = SyncConfig::read_file(&config_path)?;
+    config.set_target(&opts.name, target);
+    config.write_file(&config_path)?;
+
+    info!(
+        "{} Added sync target {}. Use `timsync doctor {}` to check the connection.",
+        LogIcon::Tick,
+        opts.name,
+        opts.name
+    );
+
+    Ok(())
+}
+
+/// Read a password from a reader, e.g. the standard input.
+/// A single trailing newline (`\n` or `\r\n`) is removed, but other whitespace is kept,
+/// since it may be a part of the password.
+///
+/// # Arguments
+///
+/// * `reader`: The reader to read the password from.
+///
+/// returns: Result<String, Error>
+fn read_password(mut reader: impl Read) -> Result<String> {
+    let mut password = String::new();
+    reader
+        .read_to_string(&mut password)
+        .context("Could not read the password from the standard input")?;
+    if password.ends_with('\n') {
+        password.pop();
+        if password.ends_with('\r') {
+            password.pop();
+        }
+    }
+    if password.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The password read from the standard input is empty"
+        ));
+    }
+    Ok(password)
+}
+
 /// Create a new sync target by asking the user for details.
 pub async fn prompt_user_details_interactive() -> Result<Option<SyncTarget>> {
     loop {
@@ -73,6 +243,38 @@ pub async fn prompt_user_details_interactive() -> Result<Option<SyncTarget>> {
             folder_root,
             username,
             password,
+            shared_files: false,
+            vars: HashMap::new(),
+            timeout: None,
+            connect_timeout: None,
+            danger_accept_invalid_certs: false,
+            token: None,
+            owner_group: None,
+            template_doc: None,
         }));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_password_removes_single_trailing_newline() {
+        assert_eq!(read_password("secret\n".as_bytes()).unwrap(), "secret");
+        assert_eq!(read_password("secret\r\n".as_bytes()).unwrap(), "secret");
+        assert_eq!(read_password("secret".as_bytes()).unwrap(), "secret");
+    }
+
+    #[test]
+    fn read_password_keeps_other_whitespace() {
+        assert_eq!(read_password("sec ret \n".as_bytes()).unwrap(), "sec ret ");
+        assert_eq!(read_password("secret\n\n".as_bytes()).unwrap(), "secret\n");
+    }
+
+    #[test]
+    fn read_password_rejects_empty_input() {
+        assert!(read_password("".as_bytes()).is_err());
+        assert!(read_password("\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use indicatif::MultiProgress;
+use simplelog::__private::paris::LogIcon;
+use simplelog::{error, info};
+
+use crate::commands::sync::{parse_var, SyncPipeline};
+use crate::project::config::{SyncTarget, DEFAULT_SYNC_TARGET_HOST};
+use crate::project::project::Project;
+
+/// Folder root used for validation if the sync target is not configured.
+const PLACEHOLDER_FOLDER_ROOT: &str = "timsync/validate";
+
+/// Create a sync target with placeholder values for rendering the project without a configured target.
+///
+/// returns: SyncTarget
+pub(super) fn placeholder_target() -> SyncTarget {
+    SyncTarget {
+        host: DEFAULT_SYNC_TARGET_HOST.to_string(),
+        folder_root: PLACEHOLDER_FOLDER_ROOT.to_string(),
+        username: String::new(),
+        password: String::new(),
+        shared_files: false,
+        vars: HashMap::new(),
+        timeout: None,
+        connect_timeout: None,
+        danger_accept_invalid_certs: false,
+        token: None,
+        owner_group: None,
+        template_doc: None,
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateOpts {
+    #[arg(default_value = "default")]
+    /// The name of the sync target to validate against. Defaults to "default".
+    /// The target does not need to be configured; if it is missing, placeholder values are used.
+    target: String,
+
+    #[arg(short, long)]
+    /// The config environment to use. Applies overrides from `.timsync/config.<env>.toml`.
+    env: Option<String>,
+
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    /// Set the value of a `{NAME}` placeholder in the folder root of the sync target.
+    /// Overrides the value set in the sync target config. Can be given multiple times.
+    vars: Vec<(String, String)>,
+
+    #[arg(long)]
+    /// Fail on references to undefined template variables instead of rendering them as empty.
+    /// Same as setting `strict_templating = true` in the config.
+    strict_templating: bool,
+
+    #[arg(long)]
+    /// Fail on links to local files that do not exist instead of only warning about them.
+    /// Same as setting `strict_links = true` in the config.
+    strict_links: bool,
+}
+
+/// Validate the project without connecting to TIM.
+///
+/// All project files are collected and rendered using placeholder document IDs.
+/// Any errors in front matter or templates are reported.
+///
+/// # Arguments
+///
+/// * `opts`: Validation options
+/// * `project_dir`: The project directory, or any directory inside the project.
+///
+/// returns: Result<(), Error>
+pub async fn validate_project(opts: ValidateOpts, project_dir: &Path) -> Result<()> {
+    let mut project = Project::resolve_from_directory(project_dir, opts.env.as_deref())
+        .context("Could not resolve project")?;
+    project.config.strict_templating |= opts.strict_templating;
+    project.config.strict_links |= opts.strict_links;
+
+    if let Some(target) = project.config.get_target_mut(&opts.target) {
+        target.resolve_placeholders(&opts.vars.iter().cloned().collect())?;
+    } else {
+        project
+            .config
+            .set_target(&opts.target, placeholder_target());
+    }
+
+    info!(
+        "Validating project {}...",
+        project.get_root_path().display()
+    );
+
+    let mut pipeline = SyncPipeline::new(&project, &opts.target, MultiProgress::new())?;
+    let mut errors = pipeline.try_collect_tim_documents()?;
+
+    let documents = SyncPipeline::with_placeholder_ids(pipeline.get_tim_documents());
+    pipeline.update_project_context(&documents)?;
+
+    for doc in documents.iter() {
+        if let Err(e) = doc.render_contents() {
+            let source = doc.get_local_file_path().unwrap_or(doc.path.to_string());
+            errors.push(e.context(format!("Could not render document {}", source)));
+        }
+    }
+    pipeline.save_caches();
+
+    for e in errors.iter() {
+        error!("<red>{}</> {:#}", LogIcon::Cross, e);
+    }
+
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Validation failed with {} errors",
+            errors.len()
+        ));
+    }
+
+    info!(
+        "{} Validated {} documents, no errors found",
+        LogIcon::Tick,
+        documents.len()
+    );
+
+    Ok(())
+}
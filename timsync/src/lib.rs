@@ -0,0 +1,47 @@
+//! TIMSync is a preprocessor and synchronizer for TIM documents.
+//!
+//! Besides the `timsync` command line tool, the sync engine can be used as a library:
+//!
+//! ```no_run
+//! use timsync::{sync_project, Project, SyncOptions};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let project = Project::resolve_from_directory(std::path::Path::new("."), None)?;
+//! let report = sync_project(&project, "default", SyncOptions::default()).await?;
+//! println!("Uploaded {} documents", report.documents.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The public API consists of:
+//!
+//! * [`Project`]: a TIMSync project and its configuration ([`SyncConfig`], [`SyncTarget`]),
+//! * [`sync_project`]: syncs a project with a configured sync target using [`SyncOptions`]
+//!   and returns a [`SyncReport`] of the uploaded documents.
+//!
+//! The library does not initialize logging. Diagnostics are emitted through the `log` crate,
+//! so the embedding application decides whether and where they are shown.
+//! Progress bars are drawn to the terminal only if [`SyncOptions::show_progress`] is set.
+//! Besides TIM, the sync writes the caches in the project cache folder (`.timsync/cache`).
+
+/// The entry points of the `timsync` command line tool. Not part of the library API.
+#[doc(hidden)]
+pub mod cli {
+    pub use crate::commands::{
+        clean_cache, clean_target, init_repo, rollback_sync, run_doctor, run_target_command,
+        sync_target, validate_project,
+    };
+    pub use crate::commands::{
+        CleanOpts, DoctorOpts, InitOptions, RollbackOpts, SyncOpts, TargetOpts, ValidateOpts,
+    };
+}
+mod commands;
+mod processing;
+mod project;
+mod templating;
+mod util;
+
+pub use commands::{sync_project, SyncOptions, SyncReport, SyncReportDocument, SyncSummary};
+pub use project::config::{SyncConfig, SyncTarget};
+pub use project::project::Project;
+pub use util::tim_client::DocVersion;
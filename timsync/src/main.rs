@@ -1,20 +1,18 @@
+use std::fs::File;
+use std::path::PathBuf;
 use std::process::ExitCode;
 
-use anyhow::Result;
-use clap::{command, Parser, Subcommand};
+use anyhow::{Context, Result};
+use clap::{command, ArgAction, Parser, Subcommand};
+use path_absolutize::Absolutize;
 use shadow_rs::shadow;
 use simplelog::__private::paris::LogIcon;
 use simplelog::*;
 
-use commands::InitOptions;
-
-use crate::commands::SyncOpts;
-
-mod commands;
-mod processing;
-mod project;
-mod util;
-mod templating;
+use timsync::cli;
+use timsync::cli::{
+    CleanOpts, DoctorOpts, InitOptions, RollbackOpts, SyncOpts, TargetOpts, ValidateOpts,
+};
 
 shadow!(build);
 
@@ -31,6 +29,90 @@
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    /// Increase logging verbosity. Use -v for debug output and -vv for trace output.
+    verbose: u8,
+
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    /// Only show warnings and errors.
+    quiet: bool,
+
+    #[arg(long, global = true, value_name = "PATH")]
+    /// Write debug logs to the given file in addition to the terminal.
+    /// The file is recreated on every run.
+    log_file: Option<PathBuf>,
+
+    #[arg(long, global = true, value_name = "DIR")]
+    /// Run the command in the given project directory instead of the current directory.
+    /// Relative paths in other options are still resolved from the current directory.
+    project: Option<PathBuf>,
+
+    #[arg(long, global = true)]
+    /// Remove the project cache folder (`.timsync/cache`) before running the command.
+    /// Ignored by `init`, as there is no project to clean yet.
+    clean_cache: bool,
+}
+
+impl Cli {
+    /// Get the terminal log level based on the verbosity flags.
+    ///
+    /// returns: LevelFilter
+    fn log_level(&self) -> LevelFilter {
+        if self.quiet {
+            return LevelFilter::Warn;
+        }
+        match self.verbose {
+            0 => LevelFilter::Info,
+            1 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    /// Get the project directory given with `--project`, or the current directory.
+    /// A relative project directory is resolved from the current directory.
+    ///
+    /// returns: Result<PathBuf, Error>
+    fn project_dir(&self) -> Result<PathBuf> {
+        match &self.project {
+            Some(dir) => Ok(dir
+                .absolutize()
+                .with_context(|| {
+                    format!("Could not resolve the project directory {}", dir.display())
+                })?
+                .to_path_buf()),
+            None => std::env::current_dir().context("Could not get the current directory"),
+        }
+    }
+
+    /// Initialize the terminal logger and the optional file logger.
+    ///
+    /// returns: Result<(), Error>
+    fn init_logger(&self) -> Result<()> {
+        let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
+            self.log_level(),
+            Config::default(),
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        )];
+
+        if let Some(log_file) = &self.log_file {
+            if let Some(parent) = log_file.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent).with_context(|| {
+                    format!("Could not create log file directory {}", parent.display())
+                })?;
+            }
+            let file = File::create(log_file)
+                .with_context(|| format!("Could not create log file {}", log_file.display()))?;
+            loggers.push(WriteLogger::new(
+                LevelFilter::Debug,
+                Config::default(),
+                file,
+            ));
+        }
+
+        CombinedLogger::init(loggers).context("Could not initialize logging")
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -42,23 +124,61 @@ enum Command {
     #[command(name = "sync")]
     /// Synchronize the project with TIM
     Sync(SyncOpts),
-    // TODO: target command to modify upload targets
+
+    #[command(name = "validate")]
+    /// Check the project templates and front matter without connecting to TIM
+    Validate(ValidateOpts),
+
+    #[command(name = "clean")]
+    /// Remove the TIMSync settings blocks from the documents in TIM
+    Clean(CleanOpts),
+
+    #[command(name = "rollback")]
+    /// Restore the documents uploaded in a sync to their earlier versions
+    Rollback(RollbackOpts),
+
+    #[command(name = "doctor")]
+    /// Check the project and the sync targets for common setup issues without syncing
+    Doctor(DoctorOpts),
+
+    #[command(name = "target")]
+    /// Manage the sync targets of the project
+    Target(TargetOpts),
 }
 
 #[tokio::main]
 async fn main() -> ExitCode {
-    CombinedLogger::init(vec![TermLogger::new(
-        LevelFilter::Info,
-        Config::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )])
-    .unwrap();
-
     let cli = Cli::parse();
+
+    if let Err(e) = cli.init_logger() {
+        eprintln!("{:#}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let project_dir = match cli.project_dir() {
+        Ok(project_dir) => project_dir,
+        Err(e) => {
+            error!("<red>{}</> {:#}", LogIcon::Warning, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // A new project has no cache yet, and resolving the project would fail
+    if cli.clean_cache && !matches!(cli.command, Command::Init(_)) {
+        if let Err(e) = cli::clean_cache(&project_dir) {
+            error!("<red>{}</> {:#}", LogIcon::Warning, e);
+            return ExitCode::FAILURE;
+        }
+    }
+
     let cmd_resul: Result<()> = match cli.command {
-        Command::Init(opts) => commands::init_repo(opts).await,
-        Command::Sync(opts) => commands::sync_target(opts).await,
+        Command::Init(opts) => cli::init_repo(opts, &project_dir).await,
+        Command::Sync(opts) => cli::sync_target(opts, &project_dir).await,
+        Command::Validate(opts) => cli::validate_project(opts, &project_dir).await,
+        Command::Clean(opts) => cli::clean_target(opts, &project_dir).await,
+        Command::Rollback(opts) => cli::rollback_sync(opts, &project_dir).await,
+        Command::Doctor(opts) => cli::run_doctor(opts, &project_dir).await,
+        Command::Target(opts) => cli::run_target_command(opts, &project_dir).await,
     };
 
     match cmd_resul {
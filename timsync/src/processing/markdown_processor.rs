@@ -9,24 +9,33 @@
 use markdown::{Constructs, ParseOptions};
 use serde::Deserialize;
 use serde_json::{json, Map, Value};
+use simplelog::warn;
 use url::{ParseError, Url};
 
 use crate::processing::prepared_document::PreparedDocument;
 use crate::processing::processors::{FileProcessorAPI, FileProcessorInternalAPI};
 use crate::processing::tim_document::TIMDocument;
+use crate::project::config::{LinkStyle, SettingsMode, SHARED_FILES_DOCPATH};
 use crate::project::files::project_files::{ProjectFile, ProjectFileAPI};
 use crate::project::global_ctx::GlobalContext;
 use crate::project::project::Project;
+use crate::templating::document_context::{build_document_context, DocumentContextInfo};
 use crate::templating::ext_context::ContextExtension;
 use crate::templating::ext_render_with_context::RendererExtension;
-use crate::templating::tim_handlebars::{TimRendererExt, FILE_MAP_ATTRIBUTE};
+use crate::templating::tim_handlebars::{TimRendererExt, FILE_MAP_ATTRIBUTE, TEMPLATE_FOLDER};
+use crate::util::date::{file_modified_date, git_last_commit_date};
 use crate::util::path::{generate_hashed_filename, RelativizeExtension, WithSetExtension};
+use crate::util::text::{humanize_title, sha1_hex};
 
 /// Helper struct to store metadata about a document and a reference to the
 /// file in the project folder.
 struct TIMDocInfo {
     path: Rc<str>,
     title: Rc<str>,
+    tim_settings: Option<Map<String, Value>>,
+    redirect_to: Option<String>,
+    layout: Option<String>,
+    modified: Option<String>,
     proj_file: ProjectFile,
 }
 
@@ -36,13 +45,82 @@ struct TIMDocInfo {
 pub struct DocumentSettings {
     /// The human-readable title of the document
     /// The title is displayed in the navigation bar of TIM
-    pub title: Option<String>,
+    pub title: Option<PerTarget<String>>,
 
     /// The path of the document in TIM
     /// If not specified, the path of the file will be used
-    pub tim_path: Option<String>,
+    /// A path ending with `/` (e.g. `week1/`) is the index document of the folder (`week1/index`)
+    pub tim_path: Option<PerTarget<String>>,
+
+    /// Additional TIM document settings (e.g. `css`, `macros`, `globalmacros`)
+    /// The settings are emitted as a settings block at the top of the document
+    pub tim_settings: Option<Map<String, Value>>,
+
+    /// Print settings of the document used when exporting the document (e.g. as PDF)
+    /// The settings are emitted as the `print` setting in the settings block of the document
+    pub print: Option<Map<String, Value>>,
+
+    /// UID or path of the project document that replaces this document
+    /// If specified, the document only contains a link to the target document
+    pub redirect_to: Option<String>,
+
+    /// Name of the layout template in `_templates` to render the document into
+    /// The rendered document is available in the layout as `content`
+    /// Overrides `markdown.layout` of the config; `none` disables the layout
+    pub layout: Option<String>,
+}
+
+/// A front matter value that may differ between sync targets
+///
+/// The value is either given as-is, or as a map from sync target names to values:
+///
+/// ```yaml
+/// title:
+///   staging: DRAFT - Lecture 1
+///   default: Lecture 1
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PerTarget<T> {
+    /// The same value for all targets
+    Value(T),
+    /// Values by sync target name. The `default` key is used for targets without a value.
+    ByTarget(HashMap<String, T>),
 }
 
+impl<T> PerTarget<T> {
+    /// Get the value for a sync target.
+    ///
+    /// # Arguments
+    ///
+    /// * `sync_target`: The name of the sync target.
+    ///
+    /// returns: Option<T> `None` if the value is a map without the target or a `default` key.
+    pub fn resolve(self, sync_target: &str) -> Option<T> {
+        match self {
+            PerTarget::Value(value) => Some(value),
+            PerTarget::ByTarget(mut values) => values
+                .remove(sync_target)
+                .or_else(|| values.remove(PER_TARGET_DEFAULT_KEY)),
+        }
+    }
+}
+
+/// Key of the fallback value in per-target front matter values
+const PER_TARGET_DEFAULT_KEY: &str = "default";
+
+/// Prefixes of absolute TIM URLs that are not links to local files, e.g. `/files/root/doc/image.png`
+const TIM_ROUTE_PREFIXES: [&str; 2] = ["/files/", "/view/"];
+
+/// Name of the document that a `tim_path` ending with `/` refers to
+const INDEX_DOCUMENT_NAME: &str = "index";
+
+/// Layout name that disables the default layout for a document
+const NO_LAYOUT: &str = "none";
+
+/// Name of the context variable that contains the rendered document in a layout template
+const LAYOUT_CONTENT_VARIABLE: &str = "content";
+
 /// Processor for markdown files.
 /// The processor generates a TIM document for each project file added to the processor.
 /// The contents of the file are passed to the templating engine and the result is stored in the TIM document.
@@ -60,6 +138,9 @@ pub struct MarkdownProcessor<'a> {
     /// Handlebars renderer to render the Markdown files.
     renderer: Handlebars<'a>,
 
+    /// Whether to render the templates of `markdown.prepend` and `markdown.append` around the documents.
+    use_configured_templates: bool,
+
     /// Reference to the shared global context of the project.
     global_context: Rc<OnceCell<GlobalContext>>,
 }
@@ -81,20 +162,57 @@ pub fn new(
         sync_target: &str,
         global_context: Rc<OnceCell<GlobalContext>>,
     ) -> Result<Self> {
-        let renderer = Handlebars::new()
+        let mut renderer = Handlebars::new()
             .with_tim_doc_helpers()
             .with_project_templates(project)?
             .with_project_helpers(project)?;
+        renderer.set_strict_mode(project.config.strict_templating);
+
+        let markdown_config = &project.config.markdown;
+        for (option, name) in markdown_config
+            .prepend
+            .iter()
+            .map(|name| ("prepend", name))
+            .chain(markdown_config.append.iter().map(|name| ("append", name)))
+        {
+            if !renderer.has_template(name) {
+                return Err(anyhow::anyhow!(
+                    "Template '{}' in `markdown.{}` of the config was not found in the {} folder",
+                    name,
+                    option,
+                    TEMPLATE_FOLDER
+                ));
+            }
+        }
+        if let Some(layout) = &markdown_config.layout {
+            if layout != NO_LAYOUT && find_layout_template(&renderer, layout).is_none() {
+                return Err(anyhow::anyhow!(
+                    "Layout '{}' in `markdown.layout` of the config was not found in the {} folder",
+                    layout,
+                    TEMPLATE_FOLDER
+                ));
+            }
+        }
 
         Ok(Self {
             files: HashMap::new(),
             project,
             sync_target: sync_target.to_string(),
             renderer,
+            use_configured_templates: true,
             global_context,
         })
     }
 
+    /// Do not render the templates configured in `markdown.prepend` and `markdown.append`
+    /// around the documents, e.g. for processors that render other file types via this processor.
+    ///
+    /// returns: MarkdownProcessor
+    pub fn without_configured_templates(mut self) -> Self {
+        self.use_configured_templates = false;
+        self
+    }
+
     /// Parse the Markdown document into an AST.
     ///
     /// # Arguments
@@ -175,27 +293,46 @@ fn find_impl(result: &mut Vec<DocumentLink>, children: &Vec<Node>) {
 
     /// Rewrite relative URLs in the Markdown document into absolute TIM URLs.
     ///
+    /// Links to documents and files keep their query and fragment, e.g. `other.md#section`.
+    /// Absolute URLs (e.g. `https:`, `mailto:` and `tel:` links), scheme-relative URLs (`//host/path`)
+    /// and fragment-only links (`#section`) are left as-is.
+    /// Links to local files that do not exist are also left as-is with a warning,
+    /// or fail the rendering if `strict_links` is enabled.
+    ///
     /// # Arguments
     ///
     /// * `contents` - The contents of the Markdown document.
     /// * `project_dir` - The root directory of the project.
     /// * `proj_file_path` - The path of the Markdown file.
     /// * `root_url` - The root URL of the target in TIM.
-    /// * `md_file` - Information about the Markdown file to process.
-    /// * `upload_files_map` - Map of files to upload to TIM and their uploaded filenames.
+    /// * `doc_path` - The path of the TIM document being processed relative to the root URL.
+    /// * `tim_path` - The path of the TIM document to which the referenced files are uploaded.
+    ///
+    /// Returns: Result<HashMap<String, String>> Map of files to upload to TIM and their uploaded filenames.
     fn resolve_relative_urls(
         &self,
         contents: &mut String,
         project_dir: &Path,
         proj_file_path: &PathBuf,
         root_url: &String,
+        doc_path: &str,
         tim_path: &str,
-    ) -> HashMap<String, String> {
+    ) -> Result<HashMap<String, String>> {
         let links = self.find_links(contents);
         let mut start_offset = 0isize;
         let mut upload_files_map = HashMap::new();
 
         for DocumentLink(start, end, url) in links {
+            // Links written with Windows path separators, e.g. `images\diagram.png`,
+            // are resolved the same way as links with forward slashes
+            let url = url.replace('\\', "/");
+
+            // Fragment-only links point within the same document,
+            // and scheme-relative links (`//host/path`) point to another host
+            if url.starts_with('#') || url.starts_with("//") {
+                continue;
+            }
+
             let parse_result = Url::parse(&url);
             let project_url_str = Url::from_directory_path(project_dir).unwrap().to_string();
 
@@ -209,6 +346,8 @@ fn resolve_relative_urls(
                     };
                     let mut full_url = base_url.join(path_part).unwrap();
                     let path_part = full_url.path().to_string();
+                    // Keep the query and the fragment, e.g. `other.md?x=1#heading`
+                    let suffix = full_url[url::Position::AfterPath..].to_string();
 
                     // TODO: This may not be enough, because we do not know if the
                     //   .md file is being processed as a TIM document or not.
@@ -218,21 +357,49 @@ fn resolve_relative_urls(
                     //   as an upload file.
                     let final_url = if path_part.ends_with(".md") {
                         full_url.set_path(&path_part[..path_part.len() - 3]);
-                        let final_url = full_url.to_string().replace(&project_url_str, "");
-                        format!("/view/{}/{}", root_url, final_url)
+                        let doc_url =
+                            full_url[..url::Position::AfterPath].replace(&project_url_str, "");
+                        let final_url = format!("{}{}", doc_url, suffix);
+                        match self.project.config.link_style {
+                            LinkStyle::Absolute => format!("/view/{}/{}", root_url, final_url),
+                            LinkStyle::Relative => relative_doc_url(doc_path, &final_url),
+                        }
                     } else {
                         // Safety: The URL is guaranteed to be a file path, and other
                         // requirements are met for to_file_path to be safe.
                         let full_path = full_url.to_file_path().unwrap();
-                        // Try to find and hash the file, otherwise silently skip it
+                        // Try to find and hash the file, otherwise leave the link as-is
                         let Ok(tim_file_name) = generate_hashed_filename(&full_path) else {
+                            // Links without an extension usually point to other TIM documents,
+                            // and links to TIM routes are generated by the helpers
+                            if full_path.extension().is_none()
+                                || TIM_ROUTE_PREFIXES.iter().any(|p| url.starts_with(p))
+                            {
+                                continue;
+                            }
+                            let missing_path = full_path.relativize(project_dir);
+                            if self.project.config.strict_links {
+                                return Err(anyhow::anyhow!(
+                                    "Document {} links to a file that does not exist: {}",
+                                    proj_file_path.relativize(project_dir).display(),
+                                    missing_path.display()
+                                ));
+                            }
+                            warn!(
+                                "Document {} links to a file that does not exist: {}",
+                                proj_file_path.relativize(project_dir).display(),
+                                missing_path.display()
+                            );
                             continue;
                         };
                         upload_files_map.insert(
                             full_path.to_string_lossy().to_string(),
                             tim_file_name.clone(),
                         );
-                        format!("/files/{}/{}/{}", root_url, tim_path, tim_file_name)
+                        format!(
+                            "/files/{}/{}/{}{}",
+                            root_url, tim_path, tim_file_name, suffix
+                        )
                     };
 
                     // Replace the url in the markdown from the start to the end position
@@ -249,8 +416,78 @@ fn resolve_relative_urls(
             }
         }
 
-        upload_files_map
+        Ok(upload_files_map)
+    }
+}
+
+/// Get the URL of a project document relative to another project document.
+/// Both paths are relative to the root URL of the sync target.
+///
+/// # Arguments
+///
+/// * `from_doc_path` - The path of the linking document, e.g. `folder/doc`.
+/// * `target_url` - The path of the linked document, optionally with a query or fragment,
+///   e.g. `other/doc#section`.
+///
+/// returns: String
+fn relative_doc_url(from_doc_path: &str, target_url: &str) -> String {
+    let (target_path, suffix) =
+        target_url.split_at(target_url.find(['?', '#']).unwrap_or(target_url.len()));
+
+    // Relative URLs are resolved against the folder that contains the linking document
+    let mut from_dirs = from_doc_path.split('/').collect::<Vec<_>>();
+    from_dirs.pop();
+    let target_parts = target_path.split('/').collect::<Vec<_>>();
+
+    let common = from_dirs
+        .iter()
+        .zip(target_parts.iter())
+        .take_while(|(a, b)| a == b)
+        .count()
+        // The document name itself is never a common folder
+        .min(target_parts.len() - 1);
+
+    let mut parts = vec![".."; from_dirs.len() - common];
+    parts.extend(&target_parts[common..]);
+    format!("{}{}", parts.join("/"), suffix)
+}
+
+/// Get a date declared in the front matter, e.g. `created: 2024-05-01`.
+///
+/// # Arguments
+///
+/// * `front_matter` - The front matter of the document.
+/// * `key` - The name of the date field.
+///
+/// returns: Option<String> The date as a string, or `None` if the field is not set.
+fn front_matter_date(front_matter: &Value, key: &str) -> Option<String> {
+    match front_matter.get(key)? {
+        Value::Null => None,
+        Value::String(date) => Some(date.clone()),
+        date => Some(date.to_string()),
+    }
+}
+
+/// Find the name of the layout template in `_templates`.
+/// The layout may be given with or without the file extension of the template,
+/// e.g. `lecture` matches the template `lecture.hbs`.
+///
+/// # Arguments
+///
+/// * `renderer` - The renderer with the project templates registered.
+/// * `layout` - The name of the layout.
+///
+/// returns: Option<String> The name of the registered template, or `None` if the layout was not found.
+fn find_layout_template(renderer: &Handlebars, layout: &str) -> Option<String> {
+    if renderer.has_template(layout) {
+        return Some(layout.to_string());
     }
+    renderer
+        .get_templates()
+        .keys()
+        .filter(|name| Path::new(name).with_extension("") == Path::new(layout))
+        .min()
+        .cloned()
 }
 
 impl<'a> FileProcessorAPI for MarkdownProcessor<'a> {
@@ -258,35 +495,57 @@ fn add_file(&mut self, file: ProjectFile) -> Result<()> {
         let root_path = self.project.get_root_path();
 
         let document_settings = match file.front_matter() {
-            Ok(front_matter) => serde_yaml::from_str::<DocumentSettings>(front_matter)
-                .with_context(|| {
-                    format!(
-                        "Could not parse front matter of file: {}",
-                        file.path().display()
-                    )
-                })?,
+            Ok(_) => file.parse_front_matter::<DocumentSettings>()?,
             _ => DocumentSettings {
                 title: None,
                 tim_path: None,
+                tim_settings: None,
+                print: None,
+                redirect_to: None,
+                layout: None,
             },
         };
 
-        let title = match document_settings.title {
+        let front_matter = file.front_matter_json()?;
+        let schema_errors = self.project.config.validate_front_matter(&front_matter);
+        if !schema_errors.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Front matter of {} does not match the schema of document type '{}': {}",
+                file.path().relativize(root_path).display(),
+                front_matter["type"].as_str().unwrap_or_default(),
+                schema_errors.join(", ")
+            ));
+        }
+
+        let title = match document_settings
+            .title
+            .and_then(|title| title.resolve(&self.sync_target))
+        {
             Some(title) => title,
-            None => file
-                .path()
-                .file_stem()
-                .ok_or_else(|| {
-                    anyhow::anyhow!(format!(
-                        "Could not get file name from path: {}",
-                        file.path().display()
-                    ))
-                })?
-                .to_string_lossy()
-                .to_string(),
+            None => {
+                let file_stem = file
+                    .path()
+                    .file_stem()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(format!(
+                            "Could not get file name from path: {}",
+                            file.path().display()
+                        ))
+                    })?
+                    .to_string_lossy()
+                    .to_string();
+                if self.project.config.markdown.humanize_titles {
+                    humanize_title(&file_stem)
+                } else {
+                    file_stem
+                }
+            }
         };
 
-        let path = match document_settings.tim_path {
+        let mut path = match document_settings
+            .tim_path
+            .and_then(|tim_path| tim_path.resolve(&self.sync_target))
+        {
             Some(path) => path,
             None => file
                 .path()
@@ -297,6 +556,57 @@ fn add_file(&mut self, file: ProjectFile) -> Result<()> {
         }
         .replace("\\", "/")
         .to_lowercase();
+        // A path to a folder refers to the index document of the folder
+        if path.ends_with('/') {
+            path.push_str(INDEX_DOCUMENT_NAME);
+        }
+
+        let layout = match document_settings
+            .layout
+            .or_else(|| self.project.config.markdown.layout.clone())
+        {
+            Some(layout) if layout == NO_LAYOUT => None,
+            Some(layout) => Some(find_layout_template(&self.renderer, &layout).ok_or_else(
+                || {
+                    anyhow::anyhow!(
+                        "Layout '{}' of {} was not found in the {} folder",
+                        layout,
+                        file.path().relativize(root_path).display(),
+                        TEMPLATE_FOLDER
+                    )
+                },
+            )?),
+            None => None,
+        };
+
+        let created = front_matter_date(&front_matter, "created");
+        let modified = front_matter_date(&front_matter, "modified")
+            .or_else(|| self.source_modified_date(file.path()));
+
+        let mut tim_settings = document_settings.tim_settings;
+        if let Some(print) = document_settings.print {
+            tim_settings
+                .get_or_insert_with(Map::new)
+                .insert("print".to_string(), Value::Object(print));
+        }
+        if self.project.config.markdown.dates_in_settings {
+            for (key, date) in [("created", &created), ("modified", &modified)] {
+                if let Some(date) = date {
+                    tim_settings
+                        .get_or_insert_with(Map::new)
+                        .insert(key.to_string(), Value::from(date.as_str()));
+                }
+            }
+        }
+
+        if let Some(existing) = self.files.get(path.as_str()) {
+            return Err(anyhow::anyhow!(
+                "Files {} and {} map to the same TIM document path '{}'. Make sure there are no duplicate paths in the project.",
+                existing.proj_file.path().relativize(root_path).display(),
+                file.path().relativize(root_path).display(),
+                path
+            ));
+        }
 
         let title: Rc<str> = Rc::from(title);
         let path: Rc<str> = Rc::from(path);
@@ -306,6 +616,10 @@ fn add_file(&mut self, file: ProjectFile) -> Result<()> {
             TIMDocInfo {
                 path,
                 title,
+                tim_settings,
+                redirect_to: document_settings.redirect_to,
+                layout,
+                modified,
                 proj_file: file,
             },
         );
@@ -330,36 +644,93 @@ fn get_tim_documents(&self) -> Vec<TIMDocument> {
     }
 }
 
-impl<'a> FileProcessorInternalAPI for MarkdownProcessor<'a> {
-    fn render_tim_document(&self, tim_document: &TIMDocument) -> Result<PreparedDocument> {
+impl<'a> MarkdownProcessor<'a> {
+    /// Get the modification date of a source file.
+    /// If `markdown.modified_from_git` is set, the date of the last git commit that changed the file is preferred.
+    /// Otherwise, or if the file has no commits, the modification time of the file is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the source file.
+    ///
+    /// Returns: Option<String>
+    fn source_modified_date(&self, path: &Path) -> Option<String> {
+        let git_date = if self.project.config.markdown.modified_from_git {
+            git_last_commit_date(self.project.get_root_path(), path)
+        } else {
+            None
+        };
+        git_date.or_else(|| file_modified_date(path))
+    }
+
+    /// Get the TIM document settings declared in the front matter of the document.
+    ///
+    /// # Arguments
+    ///
+    /// * `tim_document` - The TIM document to get the settings for.
+    ///
+    /// Returns: Option<&Map<String, Value>>
+    pub(in crate::processing) fn get_tim_settings(
+        &self,
+        tim_document: &TIMDocument,
+    ) -> Option<&Map<String, Value>> {
+        self.files
+            .get(tim_document.path)
+            .and_then(|info| info.tim_settings.as_ref())
+    }
+
+    /// Render the contents of the document without the document settings block.
+    ///
+    /// # Arguments
+    ///
+    /// * `tim_document` - The TIM document to render.
+    ///
+    /// Returns: Result<PreparedDocument>
+    pub(in crate::processing) fn render_document_contents(
+        &self,
+        tim_document: &TIMDocument,
+    ) -> Result<PreparedDocument> {
         // This unwrap is safe because the file was added to the processor
         // Because internal API is only called by TIMDocument, the file should always exist
         let info = self.files.get(tim_document.path).unwrap();
 
-        let contents = info.proj_file.contents_without_front_matter()?.to_string();
+        if let Some(redirect_to) = &info.redirect_to {
+            return self.render_redirect_document(tim_document, redirect_to);
+        }
+
+        let contents =
+            self.wrap_with_configured_templates(info.proj_file.contents_without_front_matter()?);
         let project_dir = self.project.get_root_path();
         let proj_file_path = info.proj_file.path();
-        let root_url = &self
+        let sync_target = self
             .project
             .config
             .get_target(&self.sync_target)
-            .ok_or_else(|| anyhow::anyhow!("Could not find target: {}", self.sync_target))?
-            .folder_root;
-
-        let mut ctx = self
-            .global_context
-            .get()
-            .expect("Global context was not initialized")
-            .handlebars_context();
-        ctx.extend_with_json(&info.proj_file.front_matter_json()?);
-        ctx.extend_with_json(&json!({
-            "title": tim_document.title,
-            "path": tim_document.path,
-            "doc_id": tim_document.id.unwrap_or(0),
-            "local_file_path": tim_document.get_local_file_path(),
-        }));
-
-        let res = self
+            .ok_or_else(|| anyhow::anyhow!("Could not find target: {}", self.sync_target))?;
+        let root_url = &sync_target.folder_root;
+        // With shared files enabled, attachments are uploaded to a single shared document
+        let files_doc_path = if sync_target.shared_files {
+            SHARED_FILES_DOCPATH
+        } else {
+            tim_document.path
+        };
+
+        let ctx = build_document_context(
+            self.global_context
+                .get()
+                .expect("Global context was not initialized"),
+            &info.proj_file.front_matter_json()?,
+            &DocumentContextInfo {
+                title: Some(tim_document.title),
+                path: tim_document.path,
+                doc_id: tim_document.id,
+                local_file_path: tim_document.get_local_file_path(),
+                source_hash: Some(sha1_hex(info.proj_file.contents()?)),
+                modified: info.modified.as_deref(),
+            },
+        );
+
+        let mut res = self
             .renderer
             .render_template_with_context_return_new_context(&contents, &ctx)
             .with_context(|| {
@@ -369,6 +740,26 @@ fn render_tim_document(&self, tim_document: &TIMDocument) -> Result<PreparedDocu
                 )
             })?;
 
+        if let Some(layout) = &info.layout {
+            // Render the layout with the context of the rendered document
+            // so that the files registered by the document are kept
+            let mut layout_ctx = res.modified_context.unwrap_or(ctx);
+            layout_ctx.extend_with_json(&json!({ LAYOUT_CONTENT_VARIABLE: res.rendered }));
+            res = self
+                .renderer
+                .render_template_with_context_return_new_context(
+                    &format!("{{{{> {}}}}}", layout),
+                    &layout_ctx,
+                )
+                .with_context(|| {
+                    format!(
+                        "Could not render layout '{}' of markdown document: {}",
+                        layout,
+                        proj_file_path.display()
+                    )
+                })?;
+        }
+
         // TODO: Make a general context extension for this
         let mut upload_files_map = res
             .modified_context
@@ -392,13 +783,134 @@ fn render_tim_document(&self, tim_document: &TIMDocument) -> Result<PreparedDocu
                 proj_file_path,
                 root_url,
                 tim_document.path,
-            );
+                files_doc_path,
+            )?;
             upload_files_map.extend(additional_upload_files);
         }
 
         Ok(PreparedDocument {
             markdown: contents,
             upload_files: upload_files_map,
+            source_path: None,
+            tim_settings: None,
+        })
+    }
+}
+
+impl<'a> MarkdownProcessor<'a> {
+    /// Add the partial calls of the templates configured in `markdown.prepend` and `markdown.append`
+    /// around the document contents, so that the templates are rendered with the document context.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents`: The document contents without the front matter.
+    ///
+    /// returns: String
+    fn wrap_with_configured_templates(&self, contents: &str) -> String {
+        let markdown_config = &self.project.config.markdown;
+        if !self.use_configured_templates || markdown_config.is_default() {
+            return contents.to_string();
+        }
+        let partial_call = |name: &String| format!("{{{{> {}}}}}\n", name);
+
+        let mut result = String::new();
+        result.extend(markdown_config.prepend.iter().map(partial_call));
+        result.push_str(contents);
+        if !markdown_config.append.is_empty() {
+            if !result.ends_with('\n') {
+                result.push('\n');
+            }
+            result.extend(markdown_config.append.iter().map(partial_call));
+        }
+        result
+    }
+
+    /// Render a stub document that links to the document given in `redirect_to`.
+    /// The target is first looked up by its TIM path and then by its UID.
+    ///
+    /// # Arguments
+    ///
+    /// * `tim_document` - The TIM document to render.
+    /// * `redirect_to` - The path or UID of the target document.
+    ///
+    /// Returns: Result<PreparedDocument>
+    fn render_redirect_document(
+        &self,
+        tim_document: &TIMDocument,
+        redirect_to: &str,
+    ) -> Result<PreparedDocument> {
+        let target_path = redirect_to.trim_matches('/').to_lowercase();
+        let target = match self.files.get(target_path.as_str()) {
+            Some(target) => target,
+            None => self
+                .files
+                .values()
+                .find(|info| {
+                    info.proj_file
+                        .read_general_metadata()
+                        .is_ok_and(|meta| meta.uid.as_deref() == Some(redirect_to))
+                })
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Redirect target '{}' of document {} is not a path or UID of a document in the project",
+                        redirect_to,
+                        tim_document.path
+                    )
+                })?,
+        };
+
+        if target.path.as_ref() == tim_document.path {
+            return Err(anyhow::anyhow!(
+                "Document {} cannot redirect to itself",
+                tim_document.path
+            ));
+        }
+
+        let sync_target = self
+            .project
+            .config
+            .get_target(&self.sync_target)
+            .ok_or_else(|| anyhow::anyhow!("Could not find target: {}", self.sync_target))?;
+        let url = match self.project.config.link_style {
+            LinkStyle::Absolute => format!("/view/{}/{}", sync_target.folder_root, target.path),
+            LinkStyle::Relative => relative_doc_url(tim_document.path, &target.path),
+        };
+
+        Ok(PreparedDocument {
+            markdown: format!("This page has moved to [{}]({}).\n", target.title, url),
+            upload_files: HashMap::new(),
+            source_path: None,
+            tim_settings: None,
+        })
+    }
+}
+
+impl<'a> FileProcessorInternalAPI for MarkdownProcessor<'a> {
+    fn render_tim_document(&self, tim_document: &TIMDocument) -> Result<PreparedDocument> {
+        let prepared_doc = self.render_document_contents(tim_document)?;
+
+        let Some(tim_settings) = self.get_tim_settings(tim_document) else {
+            return Ok(prepared_doc);
+        };
+
+        if self.project.config.markdown.settings_mode == SettingsMode::Api {
+            return Ok(PreparedDocument {
+                tim_settings: Some(tim_settings.clone()),
+                ..prepared_doc
+            });
+        }
+
+        let settings_yaml = serde_yaml::to_string(tim_settings)
+            .context("Could not serialize TIM settings of the document")?;
+
+        Ok(PreparedDocument {
+            markdown: format!(
+                "``` {{settings=\"\"}}\n{}```\n\n{}",
+                settings_yaml, prepared_doc.markdown
+            ),
+            upload_files: prepared_doc.upload_files,
+            source_path: prepared_doc.source_path,
+            tim_settings: None,
         })
     }
 
@@ -421,4 +933,571 @@ fn get_project_file_local_path(&self, tim_document: &TIMDocument) -> Option<Stri
                 .to_string(),
         )
     }
+
+    fn get_project_file_source_text(&self, tim_document: &TIMDocument) -> Result<Option<String>> {
+        // This unwrap is safe because the file was added to the processor
+        let info = self.files.get(tim_document.path).unwrap();
+        Ok(Some(
+            info.proj_file.contents_without_front_matter()?.to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_project;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Create a project with the given config in a temporary directory.
+    fn project(config: &str) -> (TempDir, Project) {
+        test_project::project(config, &[])
+    }
+
+    const TARGET_CONFIG: &str =
+        "[targets.default]\nhost = \"https://tim.example.com\"\nfolder_root = \"kurssit/ohj1\"\n";
+
+    /// Render a document of the project for the sync target `default`.
+    fn render(project: &Project, path: &str) -> Result<PreparedDocument> {
+        render_with_files(project, &[path], 0)
+    }
+
+    /// Add the given files of the project to the processor and render the document of the file at `index`.
+    fn render_with_files(
+        project: &Project,
+        files: &[&str],
+        index: usize,
+    ) -> Result<PreparedDocument> {
+        let global_context = Rc::new(OnceCell::new());
+        let mut processor = MarkdownProcessor::new(project, "default", global_context.clone())?;
+        for file in files {
+            processor.add_file(ProjectFile::try_from(project.get_root_path().join(file))?)?;
+        }
+        let _ = global_context.set(GlobalContext::new());
+        let path = files[index].trim_end_matches(".md");
+        let documents = processor.get_tim_documents();
+        documents
+            .iter()
+            .find(|document| document.path == path)
+            .unwrap()
+            .render_contents()
+    }
+
+    /// Resolve the links of the document `week1/intro.md` synced into the TIM folder `kurssit/ohj1`.
+    fn resolve_links(project: &Project, markdown: &str) -> String {
+        try_resolve_links(project, markdown).unwrap()
+    }
+
+    /// Like `resolve_links`, but returns the error of the link resolution.
+    fn try_resolve_links(project: &Project, markdown: &str) -> Result<String> {
+        let processor = MarkdownProcessor::new(project, "default", Rc::new(OnceCell::new()))?;
+        let project_dir = project.get_root_path();
+        let mut contents = markdown.to_string();
+        processor.resolve_relative_urls(
+            &mut contents,
+            project_dir,
+            &project_dir.join("week1").join("intro.md"),
+            &"kurssit/ohj1".to_string(),
+            "week1/intro",
+            "week1/intro",
+        )?;
+        Ok(contents)
+    }
+
+    #[test]
+    fn relative_doc_url_of_sibling() {
+        assert_eq!(relative_doc_url("week1/intro", "week1/other"), "other");
+        assert_eq!(relative_doc_url("intro", "other"), "other");
+    }
+
+    #[test]
+    fn relative_doc_url_of_nested_target() {
+        assert_eq!(
+            relative_doc_url("week1/intro", "week1/extra/notes"),
+            "extra/notes"
+        );
+        assert_eq!(relative_doc_url("intro", "week1/notes"), "week1/notes");
+    }
+
+    #[test]
+    fn relative_doc_url_of_target_in_other_folder() {
+        assert_eq!(
+            relative_doc_url("week1/intro", "week2/intro"),
+            "../week2/intro"
+        );
+        assert_eq!(
+            relative_doc_url("week1/extra/notes", "index"),
+            "../../index"
+        );
+        // A document with the same name as a folder of the linking document
+        assert_eq!(relative_doc_url("week1/intro", "week1"), "../week1");
+    }
+
+    #[test]
+    fn relative_doc_url_keeps_query_and_fragment() {
+        assert_eq!(
+            relative_doc_url("week1/intro", "week2/intro?x=1#tasks"),
+            "../week2/intro?x=1#tasks"
+        );
+    }
+
+    #[test]
+    fn absolute_links_to_documents() {
+        let (_dir, project) = project("[targets]\n");
+        assert_eq!(
+            resolve_links(&project, "[a](other.md) [b](extra/notes.md) [c](/index.md)"),
+            "[a](/view/kurssit/ohj1/week1/other) \
+             [b](/view/kurssit/ohj1/week1/extra/notes) \
+             [c](/view/kurssit/ohj1/index)"
+        );
+    }
+
+    #[test]
+    fn relative_links_to_documents() {
+        let (_dir, project) = project("link_style = \"relative\"\n\n[targets]\n");
+        assert_eq!(
+            resolve_links(
+                &project,
+                "[a](other.md) [b](extra/notes.md) [c](../week2/intro.md) [d](/index.md)"
+            ),
+            "[a](other) [b](extra/notes) [c](../week2/intro) [d](../index)"
+        );
+    }
+
+    #[test]
+    fn document_links_keep_fragment_and_query() {
+        let (_dir, project) = project("[targets]\n");
+        assert_eq!(
+            resolve_links(
+                &project,
+                "[a](other.md#frag) [b](other.md?x=1) [c](other.md?x=1&y=2#frag)"
+            ),
+            "[a](/view/kurssit/ohj1/week1/other#frag) \
+             [b](/view/kurssit/ohj1/week1/other?x=1) \
+             [c](/view/kurssit/ohj1/week1/other?x=1&y=2#frag)"
+        );
+    }
+
+    #[test]
+    fn relative_document_links_keep_fragment_and_query() {
+        let (_dir, project) = project("link_style = \"relative\"\n\n[targets]\n");
+        assert_eq!(
+            resolve_links(&project, "[a](../week2/intro.md?x=1#frag)"),
+            "[a](../week2/intro?x=1#frag)"
+        );
+    }
+
+    #[test]
+    fn fragment_only_links_are_kept() {
+        let (_dir, project) = project("[targets]\n");
+        assert_eq!(resolve_links(&project, "[a](#frag)"), "[a](#frag)");
+    }
+
+    #[test]
+    fn links_with_schemes_or_hosts_are_kept() {
+        let (_dir, project) = project("[targets]\n");
+        let markdown = "[a](mailto:teacher@example.com) [b](tel:+358401234567) [c](//example.com/page.md) [d](https://example.com/page.md)";
+        assert_eq!(resolve_links(&project, markdown), markdown);
+    }
+
+    #[test]
+    fn links_to_missing_files_are_kept() {
+        let (_dir, project) = project("[targets]\n");
+        let markdown = "![chart](images/missing.png)";
+        assert_eq!(resolve_links(&project, markdown), markdown);
+    }
+
+    #[test]
+    fn links_to_missing_files_are_errors_with_strict_links() {
+        let (_dir, project) = project("strict_links = true\n[targets]\n");
+        let Err(err) = try_resolve_links(&project, "![chart](images/missing.png)") else {
+            panic!("Expected a missing file error");
+        };
+        let message = err.to_string();
+        assert!(message.contains("week1/intro.md"), "{}", message);
+        assert!(message.contains("week1/images/missing.png"), "{}", message);
+    }
+
+    #[test]
+    fn extensionless_and_tim_route_links_are_not_checked() {
+        let (_dir, project) = project("strict_links = true\n[targets]\n");
+        let markdown = "[a](other-document) [b](/files/kurssit/ohj1/intro/image.png) [c](/view/kurssit/ohj1/intro)";
+        assert_eq!(try_resolve_links(&project, markdown).unwrap(), markdown);
+    }
+
+    #[test]
+    fn file_links_keep_fragment_and_query() {
+        let (dir, project) = project("[targets]\n");
+        fs::create_dir_all(dir.path().join("week1")).unwrap();
+        fs::write(dir.path().join("week1").join("slides.pdf"), "slides").unwrap();
+        let resolved = resolve_links(&project, "[a](slides.pdf?v=2#page=3)");
+        assert!(
+            resolved.starts_with("[a](/files/kurssit/ohj1/week1/intro/")
+                && resolved.ends_with(".pdf?v=2#page=3)"),
+            "{}",
+            resolved
+        );
+    }
+
+    #[test]
+    fn links_with_backslashes_are_resolved() {
+        let (dir, project) = project("[targets]\n");
+        fs::create_dir_all(dir.path().join("week1").join("images")).unwrap();
+        fs::write(
+            dir.path().join("week1").join("images").join("chart.png"),
+            "png",
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_links(&project, r"[a](..\week2\intro.md) [b](extra\notes.md#frag)"),
+            "[a](/view/kurssit/ohj1/week2/intro) [b](/view/kurssit/ohj1/week1/extra/notes#frag)"
+        );
+        let resolved = resolve_links(&project, r"![chart](images\chart.png)");
+        assert!(
+            resolved.starts_with("![chart](/files/kurssit/ohj1/week1/intro/")
+                && resolved.ends_with(".png)"),
+            "{}",
+            resolved
+        );
+    }
+
+    #[test]
+    fn tim_settings_are_rendered_as_settings_block() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[(
+                "intro.md",
+                "---\ntim_settings:\n  css: foo.css\n---\nIntro\n",
+            )],
+        );
+        let prepared = render(&project, "intro.md").unwrap();
+        assert_eq!(
+            prepared.markdown,
+            "``` {settings=\"\"}\ncss: foo.css\n```\n\n\nIntro\n"
+        );
+
+        // The TIMSync settings block is added before the document settings
+        let markdown = prepared.with_timestamp().markdown;
+        let timsync_settings = markdown.find("{settings=\"timsync\"}").unwrap();
+        let document_settings = markdown.find("css: foo.css").unwrap();
+        assert!(timsync_settings < document_settings, "{}", markdown);
+    }
+
+    #[test]
+    fn print_settings_are_added_only_if_given() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[
+                (
+                    "print.md",
+                    "---\nprint:\n  paper: a4\n  landscape: true\n---\nBody\n",
+                ),
+                ("plain.md", "---\ntitle: Plain\n---\nBody\n"),
+            ],
+        );
+        let prepared = render(&project, "print.md").unwrap();
+        assert_eq!(
+            prepared.markdown,
+            "``` {settings=\"\"}\nprint:\n  landscape: true\n  paper: a4\n```\n\n\nBody\n"
+        );
+
+        let prepared = render(&project, "plain.md").unwrap();
+        assert_eq!(prepared.markdown, "\nBody\n");
+    }
+
+    #[test]
+    fn redirect_document_links_to_target_by_path_or_uid() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[
+                ("old.md", "---\nredirect_to: week1/new\n---\nOld contents\n"),
+                (
+                    "old_uid.md",
+                    "---\nredirect_to: new-intro\n---\nOld contents\n",
+                ),
+                (
+                    "week1/new.md",
+                    "---\ntitle: New intro\nuid: new-intro\n---\nNew contents\n",
+                ),
+            ],
+        );
+        let files = ["old.md", "old_uid.md", "week1/new.md"];
+        for index in [0, 1] {
+            let prepared = render_with_files(&project, &files, index).unwrap();
+            assert_eq!(
+                prepared.markdown,
+                "This page has moved to [New intro](/view/kurssit/ohj1/week1/new).\n"
+            );
+        }
+    }
+
+    #[test]
+    fn redirect_to_unknown_document_is_an_error() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[("old.md", "---\nredirect_to: missing\n---\nOld contents\n")],
+        );
+        let Err(err) = render(&project, "old.md") else {
+            panic!("Expected an unknown redirect target");
+        };
+        let message = err.to_string();
+        assert!(message.contains("'missing'"), "{}", message);
+    }
+
+    #[test]
+    fn documents_not_matching_the_schema_are_rejected() {
+        let (_dir, project) = test_project::project(
+            &format!(
+                "{}[schema.assignment]\npoints = \"number\"\n",
+                TARGET_CONFIG
+            ),
+            &[("week1/task.md", "---\ntype: assignment\n---\nTask\n")],
+        );
+        let Err(err) = render(&project, "week1/task.md") else {
+            panic!("Expected a schema error");
+        };
+        let message = err.to_string();
+        assert!(message.contains("week1/task.md"), "{}", message);
+        assert!(message.contains("missing field `points`"), "{}", message);
+    }
+
+    #[test]
+    fn source_hash_changes_only_when_the_source_changes() {
+        let source = "---\ntitle: Intro\n---\nHash {{ doc.source_hash }}\n";
+        let (dir, project) = test_project::project(TARGET_CONFIG, &[("intro.md", source)]);
+        let hash = |project: &Project| render(project, "intro.md").unwrap().markdown;
+
+        let first = hash(&project);
+        assert_eq!(first, format!("\nHash {}\n", sha1_hex(source)));
+        assert_eq!(hash(&project), first);
+
+        test_project::write_file(&dir, "intro.md", &source.replace("Hash", "Edited hash"));
+        let edited = hash(&project);
+        assert_ne!(edited, first.replace("Hash", "Edited hash"));
+    }
+
+    #[test]
+    fn undefined_variables_render_empty_by_default() {
+        let (_dir, project) =
+            test_project::project(TARGET_CONFIG, &[("intro.md", "Value: {{ missing }}\n")]);
+        assert_eq!(render(&project, "intro.md").unwrap().markdown, "Value: \n");
+    }
+
+    #[test]
+    fn undefined_variables_are_errors_in_strict_mode() {
+        let (_dir, project) = test_project::project(
+            &format!("strict_templating = true\n{}", TARGET_CONFIG),
+            &[("intro.md", "Value: {{ missing }}\n")],
+        );
+        let Err(err) = render(&project, "intro.md") else {
+            panic!("Expected an undefined variable error");
+        };
+        let message = format!("{:#}", err);
+        assert!(message.contains("missing"), "{}", message);
+    }
+
+    #[test]
+    fn configured_templates_wrap_the_body_in_order() {
+        let (_dir, project) = test_project::project(
+            &format!(
+                "{}[markdown]\nprepend = [\"header.md\", \"note.md\"]\nappend = [\"footer.md\"]\n",
+                TARGET_CONFIG
+            ),
+            &[
+                ("_templates/header.md", "# {{ title }}\n"),
+                ("_templates/note.md", "Note\n"),
+                ("_templates/footer.md", "Footer\n"),
+                ("intro.md", "---\ntitle: Intro\n---\nBody"),
+            ],
+        );
+        assert_eq!(
+            render(&project, "intro.md").unwrap().markdown,
+            "# Intro\nNote\n\nBody\nFooter\n"
+        );
+    }
+
+    #[test]
+    fn missing_configured_template_is_an_error() {
+        let (_dir, project) = test_project::project(
+            &format!("{}[markdown]\nappend = [\"footer.md\"]\n", TARGET_CONFIG),
+            &[],
+        );
+        let Err(err) = MarkdownProcessor::new(&project, "default", Rc::new(OnceCell::new())) else {
+            panic!("Expected a missing template error");
+        };
+        let message = err.to_string();
+        assert!(message.contains("`markdown.append`"), "{}", message);
+    }
+
+    #[test]
+    fn per_target_value_resolves_by_target_name() {
+        let value: PerTarget<String> =
+            serde_json::from_value(json!({"staging": "DRAFT - Intro", "default": "Intro"}))
+                .unwrap();
+        assert_eq!(value.resolve("staging").as_deref(), Some("DRAFT - Intro"));
+
+        let value: PerTarget<String> =
+            serde_json::from_value(json!({"staging": "DRAFT - Intro", "default": "Intro"}))
+                .unwrap();
+        assert_eq!(value.resolve("production").as_deref(), Some("Intro"));
+
+        let value: PerTarget<String> =
+            serde_json::from_value(json!({"staging": "DRAFT - Intro"})).unwrap();
+        assert_eq!(value.resolve("production"), None);
+
+        let value: PerTarget<String> = serde_json::from_value(json!("Intro")).unwrap();
+        assert_eq!(value.resolve("staging").as_deref(), Some("Intro"));
+    }
+
+    #[test]
+    fn per_target_title_and_path_are_resolved_for_the_sync_target() {
+        let (_dir, project) = test_project::project(
+            "[targets]\n",
+            &[(
+                "week1/intro.md",
+                "---\ntitle:\n  staging: DRAFT - Intro\n  default: Intro\ntim_path:\n  staging: drafts/intro\n---\nIntro\n",
+            )],
+        );
+        let documents = |sync_target: &str| {
+            let mut processor =
+                MarkdownProcessor::new(&project, sync_target, Rc::new(OnceCell::new())).unwrap();
+            processor
+                .add_file(
+                    ProjectFile::try_from(project.get_root_path().join("week1/intro.md")).unwrap(),
+                )
+                .unwrap();
+            processor
+                .get_tim_documents()
+                .iter()
+                .map(|doc| (doc.title.to_string(), doc.path.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            documents("staging"),
+            vec![("DRAFT - Intro".to_string(), "drafts/intro".to_string())]
+        );
+        assert_eq!(
+            documents("production"),
+            vec![("Intro".to_string(), "week1/intro".to_string())]
+        );
+    }
+
+    const LAYOUT: (&str, &str) = (
+        "_templates/lecture.hbs",
+        "# {{ title }}\n\n{{{ content }}}<!-- end -->\n",
+    );
+
+    #[test]
+    fn document_is_rendered_into_its_layout() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[
+                LAYOUT,
+                (
+                    "intro.md",
+                    "---\ntitle: Intro\nlayout: lecture\n---\nBody\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            render(&project, "intro.md").unwrap().markdown,
+            "# Intro\n\n\nBody\n<!-- end -->\n"
+        );
+    }
+
+    #[test]
+    fn configured_layout_is_the_default_and_none_disables_it() {
+        let (_dir, project) = test_project::project(
+            &format!("{}[markdown]\nlayout = \"lecture\"\n", TARGET_CONFIG),
+            &[
+                LAYOUT,
+                ("intro.md", "---\ntitle: Intro\n---\nBody\n"),
+                ("plain.md", "---\ntitle: Plain\nlayout: none\n---\nBody\n"),
+            ],
+        );
+        assert_eq!(
+            render(&project, "intro.md").unwrap().markdown,
+            "# Intro\n\n\nBody\n<!-- end -->\n"
+        );
+        assert_eq!(render(&project, "plain.md").unwrap().markdown, "\nBody\n");
+    }
+
+    #[test]
+    fn documents_without_layout_are_rendered_as_is() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[LAYOUT, ("intro.md", "---\ntitle: Intro\n---\nBody\n")],
+        );
+        assert_eq!(render(&project, "intro.md").unwrap().markdown, "\nBody\n");
+    }
+
+    #[test]
+    fn unknown_layout_is_an_error() {
+        let (_dir, project) = test_project::project(
+            TARGET_CONFIG,
+            &[("week1/intro.md", "---\nlayout: missing\n---\nBody\n")],
+        );
+        let Err(err) = render(&project, "week1/intro.md") else {
+            panic!("Expected a missing layout error");
+        };
+        let message = err.to_string();
+        assert!(message.contains("Layout 'missing'"), "{}", message);
+        assert!(message.contains("week1/intro.md"), "{}", message);
+    }
+
+    const GIT_DATES_CONFIG: &str = "[markdown]\nmodified_from_git = true\n";
+
+    #[test]
+    fn front_matter_modified_date_is_preferred() {
+        let (_dir, project) = test_project::project(
+            &format!("{}{}", TARGET_CONFIG, GIT_DATES_CONFIG),
+            &[(
+                "intro.md",
+                "---\nmodified: 2020-01-02\n---\nModified {{ modified }}\n",
+            )],
+        );
+        assert_eq!(
+            render(&project, "intro.md").unwrap().markdown,
+            "\nModified 2020-01-02\n"
+        );
+    }
+
+    #[test]
+    fn uncommitted_files_use_the_file_modification_date() {
+        let (dir, project) = test_project::project(
+            &format!("{}{}", TARGET_CONFIG, GIT_DATES_CONFIG),
+            &[("intro.md", "Modified {{ modified }}\n")],
+        );
+        // The file is in a repository, but has no commits
+        let _ = std::process::Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(dir.path())
+            .status();
+        let file_date = file_modified_date(&dir.path().join("intro.md")).unwrap();
+        assert_eq!(
+            render(&project, "intro.md").unwrap().markdown,
+            format!("Modified {}\n", file_date)
+        );
+    }
+
+    #[test]
+    fn dates_are_added_to_the_settings() {
+        let (_dir, project) = test_project::project(
+            &format!("{}[markdown]\ndates_in_settings = true\n", TARGET_CONFIG),
+            &[(
+                "intro.md",
+                "---\ncreated: 2020-01-01\nmodified: 2020-01-02\n---\nBody\n",
+            )],
+        );
+        let markdown = render(&project, "intro.md").unwrap().markdown;
+        assert!(
+            markdown.starts_with("``` {settings=\"\"}\n"),
+            "{}",
+            markdown
+        );
+        assert!(markdown.contains("created: 2020-01-01\n"), "{}", markdown);
+        assert!(markdown.contains("modified: 2020-01-02\n"), "{}", markdown);
+    }
 }
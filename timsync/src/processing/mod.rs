@@ -1,4 +1,5 @@
 pub mod markdown_processor;
+pub mod postprocess;
 pub mod prepared_document;
 pub mod processors;
 pub mod style_theme_processor;
@@ -0,0 +1,245 @@
+use crate::project::config::PostProcessConfig;
+
+/// A post-processing transform applied to the final markdown of a document.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Transform {
+    /// Remove trailing whitespace from all lines, keeping Markdown hard line breaks.
+    TrimTrailingWhitespace,
+    /// Convert straight quotes into curly quotes outside of code, math, HTML tags and attribute blocks.
+    SmartQuotes,
+}
+
+impl Transform {
+    /// Apply the transform to the markdown.
+    ///
+    /// # Arguments
+    ///
+    /// * `markdown`: The markdown to transform.
+    ///
+    /// returns: String
+    pub fn apply(&self, markdown: &str) -> String {
+        match self {
+            Transform::TrimTrailingWhitespace => trim_trailing_whitespace(markdown),
+            Transform::SmartQuotes => smart_quotes(markdown),
+        }
+    }
+}
+
+/// Get the list of enabled transforms in the order they are applied.
+///
+/// # Arguments
+///
+/// * `config`: The post-processing settings of the project.
+///
+/// returns: Vec<Transform>
+pub fn enabled_transforms(config: &PostProcessConfig) -> Vec<Transform> {
+    let mut transforms = Vec::new();
+    if config.trim_trailing_whitespace {
+        transforms.push(Transform::TrimTrailingWhitespace);
+    }
+    if config.smart_quotes {
+        transforms.push(Transform::SmartQuotes);
+    }
+    transforms
+}
+
+/// Apply all enabled transforms to the markdown.
+///
+/// # Arguments
+///
+/// * `config`: The post-processing settings of the project.
+/// * `markdown`: The markdown to transform.
+///
+/// returns: String
+pub fn postprocess_markdown(config: &PostProcessConfig, markdown: String) -> String {
+    enabled_transforms(config)
+        .iter()
+        .fold(markdown, |md, transform| transform.apply(&md))
+}
+
+fn trim_trailing_whitespace(markdown: &str) -> String {
+    markdown
+        .split_inclusive('\n')
+        .map(|line| {
+            let (content, newline) = match line.strip_suffix('\n') {
+                Some(content) => (content.strip_suffix('\r').unwrap_or(content), "\n"),
+                None => (line, ""),
+            };
+            let trimmed = content.trim_end();
+            // Keep hard line breaks (two or more trailing spaces after text)
+            let hard_break = !trimmed.is_empty()
+                && content[trimmed.len()..].starts_with("  ")
+                && !newline.is_empty();
+            format!(
+                "{}{}{}",
+                trimmed,
+                if hard_break { "  " } else { "" },
+                newline
+            )
+        })
+        .collect()
+}
+
+fn smart_quotes(markdown: &str) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+    let mut in_display_math = false;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            result.push_str(line);
+            continue;
+        }
+        // Skip code blocks and TIM paragraph attribute lines
+        if in_code_block || (!in_display_math && trimmed.starts_with("#-")) {
+            result.push_str(line);
+            continue;
+        }
+
+        // Code spans, math (e.g. `$f'(x)$`), HTML tags and attribute blocks
+        // (e.g. `{.note title="Note"}`) are kept as-is.
+        // Display math in `$$...$$` may continue on the following lines.
+        let mut skip_until: Option<&str> = in_display_math.then_some("$$");
+        let mut prev: Option<char> = None;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            let replaced = match (skip_until, c) {
+                (Some("$$"), _) => {
+                    if c == '$' && chars.next_if_eq(&'$').is_some() {
+                        result.push('$');
+                        skip_until = None;
+                    }
+                    c
+                }
+                (Some(end), _) => {
+                    if end.starts_with(c) {
+                        skip_until = None;
+                    }
+                    c
+                }
+                (None, '`') => {
+                    skip_until = Some("`");
+                    c
+                }
+                (None, '$') if prev != Some('\\') => {
+                    if chars.next_if_eq(&'$').is_some() {
+                        result.push('$');
+                        skip_until = Some("$$");
+                    } else if chars.peek().is_some_and(|next| !next.is_whitespace()) {
+                        // A dollar sign followed by a space is not math, e.g. `costs 5 $ each`
+                        skip_until = Some("$");
+                    }
+                    c
+                }
+                (None, '<') if chars.peek().is_some_and(|&next| starts_html_tag(next)) => {
+                    skip_until = Some(">");
+                    c
+                }
+                (None, '{') => {
+                    skip_until = Some("}");
+                    c
+                }
+                (None, '"') => {
+                    if is_opening_position(prev) {
+                        '“'
+                    } else {
+                        '”'
+                    }
+                }
+                (None, '\'') => {
+                    if is_opening_position(prev) {
+                        '‘'
+                    } else {
+                        '’'
+                    }
+                }
+                _ => c,
+            };
+            result.push(replaced);
+            prev = Some(c);
+        }
+        in_display_math = skip_until == Some("$$");
+    }
+
+    result
+}
+
+/// Check whether a quote after the given character opens a quotation.
+fn is_opening_position(prev: Option<char>) -> bool {
+    match prev {
+        None => true,
+        Some(c) => c.is_whitespace() || matches!(c, '(' | '[' | '>' | '—' | '–' | '-'),
+    }
+}
+
+/// Check whether a `<` followed by the given character starts an HTML tag, e.g. `<a`, `</a` or `<!--`.
+/// Other uses of `<`, such as `a < b`, are text.
+fn starts_html_tag(next: char) -> bool {
+    next.is_ascii_alphabetic() || matches!(next, '/' | '!')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_quotes_in_prose() {
+        assert_eq!(
+            smart_quotes("She said \"don't\" ('twice').\n"),
+            "She said “don’t” (‘twice’).\n"
+        );
+    }
+
+    #[test]
+    fn keeps_quotes_in_code() {
+        let markdown =
+            "Run `echo \"hi\"` now.\n```\nprint(\"hi\")\n```\n#- {.task plugin=\"csPlugin\"}\n";
+        assert_eq!(smart_quotes(markdown), markdown);
+    }
+
+    #[test]
+    fn keeps_quotes_in_html_tags() {
+        assert_eq!(
+            smart_quotes("<a href=\"x.html\" title='Link'>\"Link\"</a> and 1 < \"2\"\n"),
+            "<a href=\"x.html\" title='Link'>“Link”</a> and 1 < “2”\n"
+        );
+        assert_eq!(smart_quotes("<!-- \"c\" -->\n"), "<!-- \"c\" -->\n");
+    }
+
+    #[test]
+    fn keeps_quotes_in_attribute_blocks() {
+        assert_eq!(
+            smart_quotes(
+                "# \"Title\" {#title .note data-x=\"1\"}\n[Link](x.md){target=\"_blank\"}\n"
+            ),
+            "# “Title” {#title .note data-x=\"1\"}\n[Link](x.md){target=\"_blank\"}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_quotes_in_math() {
+        assert_eq!(
+            smart_quotes("The \"derivative\" $f'(x)$ and $$g''(x)$$.\n"),
+            "The “derivative” $f'(x)$ and $$g''(x)$$.\n"
+        );
+        assert_eq!(
+            smart_quotes("$$\nf'(x) = 2x\n$$\nIt's \"done\".\n"),
+            "$$\nf'(x) = 2x\n$$\nIt’s “done”.\n"
+        );
+        // Escaped dollar signs and dollar signs followed by a space do not start math
+        assert_eq!(
+            smart_quotes("It's \\$5 or 5 $ \"each\".\n"),
+            "It’s \\$5 or 5 $ “each”.\n"
+        );
+    }
+
+    #[test]
+    fn trims_trailing_whitespace_but_keeps_hard_breaks() {
+        assert_eq!(
+            trim_trailing_whitespace("a \nb  \n  \nc\t\r\n"),
+            "a\nb  \n\nc\n"
+        );
+    }
+}
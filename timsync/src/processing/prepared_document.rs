@@ -1,9 +1,13 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Context;
-use lazy_regex::regex;
+use itertools::Itertools;
+use lazy_regex::{regex, Captures, Regex};
 use serde::{Deserialize, Serialize};
-use sha1::{Digest, Sha1};
+use serde_json::{Map, Value};
+
+use crate::util::text::sha1_hex;
 
 /// A Markdown document contents that are ready to be uploaded to TIM.
 pub struct PreparedDocument {
@@ -12,18 +16,102 @@ pub struct PreparedDocument {
     /// Map of files to upload.
     /// Keys are full resolved paths to the files, values are final filenames of the files in TIM
     pub upload_files: HashMap<String, String>,
+    /// Path of the source file relative to the project root.
+    /// If set, the path is recorded in the TIMSync settings block when the timestamp is added.
+    pub source_path: Option<String>,
+    /// TIM settings to set with the document settings API after uploading the document.
+    /// Set instead of a settings block in the markdown if `markdown.settings_mode` is `api`.
+    pub tim_settings: Option<Map<String, Value>>,
+}
+
+/// New contents of a single paragraph of a document in TIM.
+/// Returned by `PreparedDocument::changed_paragraphs`.
+pub struct ParagraphUpdate {
+    /// ID of the paragraph in TIM
+    pub par_id: String,
+    /// New markdown of the paragraph, including the attribute line with the paragraph ID
+    pub markdown: String,
+}
+
+/// A paragraph of document markdown that starts with an attribute line with a paragraph ID.
+struct IdParagraph<'a> {
+    id: &'a str,
+    markdown: &'a str,
 }
 
 impl PreparedDocument {
-    /// Calculates the SHA1 hash of the markdown.
-    /// This is used to check if the markdown has changed.
+    /// Calculates the SHA1 hash of the markdown and the TIM settings set with the settings API.
+    /// Any TIMSync settings blocks are excluded from the hash.
     ///
     /// returns: String
     pub fn sha1(&self) -> String {
-        let mut hasher = Sha1::new();
-        hasher.update(self.markdown.as_bytes());
-        let result = hasher.finalize();
-        format!("{:x}", result)
+        let mut text = strip_timsync_settings(&self.markdown).into_owned();
+        if let Some(tim_settings) = &self.tim_settings {
+            text.push_str(&Value::Object(tim_settings.clone()).to_string());
+        }
+        sha1_hex(&text)
+    }
+
+    /// Find paragraph IDs that are used more than once in the markdown.
+    /// TIM requires that all paragraph IDs are unique within a document.
+    ///
+    /// returns: Vec<String> The duplicate paragraph IDs in the order of their first repetition.
+    pub fn duplicate_par_ids(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        for captures in par_id_regex().captures_iter(&self.markdown) {
+            let id = captures.name("id").unwrap().as_str();
+            if !seen.insert(id) && !duplicates.iter().any(|d| d == id) {
+                duplicates.push(id.to_string());
+            }
+        }
+        duplicates
+    }
+
+    /// Find the paths of TIM documents referenced with the `tim_ref` helper.
+    /// The references are resolved to document IDs with `resolve_tim_refs`.
+    ///
+    /// returns: Vec<String> The unique referenced paths in the order of their first occurrence.
+    pub fn tim_ref_paths(&self) -> Vec<String> {
+        tim_ref_regex()
+            .captures_iter(&self.markdown)
+            .map(|captures| captures.name("path").unwrap().as_str().to_string())
+            .unique()
+            .collect()
+    }
+
+    /// Replace the document references inserted by the `tim_ref` helper with the actual document IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc_ids`: Map of referenced TIM document paths to their document IDs.
+    ///
+    /// returns: Result<(), Error> Fails if a referenced path is missing from `doc_ids`.
+    pub fn resolve_tim_refs(&mut self, doc_ids: &HashMap<String, u64>) -> anyhow::Result<()> {
+        let mut missing = None;
+        let markdown = tim_ref_regex().replace_all(&self.markdown, |captures: &Captures| {
+            let path = captures.name("path").unwrap().as_str();
+            match doc_ids.get(path) {
+                Some(doc_id) => format!("rd=\"{}\"", doc_id),
+                None => {
+                    missing.get_or_insert_with(|| path.to_string());
+                    captures[0].to_string()
+                }
+            }
+        });
+        if let Some(path) = missing {
+            anyhow::bail!("Could not resolve the referenced TIM document '{}'", path);
+        }
+        self.markdown = markdown.into_owned();
+        Ok(())
+    }
+
+    /// Replace the document references inserted by the `tim_ref` helper with the referenced TIM paths.
+    /// Used when the documents are rendered without contacting TIM, so the document IDs are not known.
+    pub fn write_tim_ref_paths(&mut self) {
+        self.markdown = tim_ref_regex()
+            .replace_all(&self.markdown, "rd=\"$path\"")
+            .into_owned();
     }
 
     /// Prepends the timestamp to the markdown.
@@ -32,18 +120,83 @@ pub fn sha1(&self) -> String {
     /// returns: PreparedMarkdown
     pub fn with_timestamp(self) -> PreparedDocument {
         let sha1 = self.sha1();
+        let content_hash = content_hash_of(&strip_timsync_settings(&self.markdown));
         // prepend the timestamp to the markdown
         Self {
             markdown: format!(
                 "{}\n\n{}",
-                TimSyncDocSettings::new(sha1).to_markdown(),
+                TimSyncDocSettings::new(sha1, content_hash, self.source_path.clone()).to_markdown(),
                 self.markdown
             ),
             upload_files: self.upload_files,
+            source_path: self.source_path,
+            tim_settings: self.tim_settings,
         }
     }
 
-    /// Checks if the timestamp in the markdown equals the hash in the given markdown.
+    /// Find the paragraphs that differ between this document and the current markdown of the document in TIM.
+    /// Used to update only the changed paragraphs instead of uploading the whole document.
+    ///
+    /// Only documents where every paragraph has an ID (e.g. task documents) can be updated by paragraph.
+    /// The documents must have the same paragraph IDs in the same order, and the current markdown
+    /// must contain a TIMSync settings block, which is updated to the settings block of this document.
+    /// The paragraphs are compared by their content hashes, see `content_hash_of`.
+    ///
+    /// # Arguments
+    ///
+    /// * `md`: The current markdown of the document in TIM
+    ///
+    /// returns: Option<Vec<ParagraphUpdate>> The changed paragraphs,
+    /// or `None` if the document must be uploaded as a whole.
+    pub fn changed_paragraphs(&self, md: &str) -> Option<Vec<ParagraphUpdate>> {
+        let settings = timsync_settings_regex().find(&self.markdown)?.as_str();
+        let local_pars = id_paragraphs(&strip_timsync_settings(&self.markdown))?
+            .into_iter()
+            .map(|par| (par.id.to_string(), par.markdown.to_string()))
+            .collect::<Vec<_>>();
+        let (remote_settings, remote_pars): (Vec<_>, Vec<_>) = id_paragraphs(md)?
+            .into_iter()
+            .partition(|par| timsync_settings_regex().is_match(par.markdown));
+
+        let [remote_settings] = remote_settings.as_slice() else {
+            return None;
+        };
+        if local_pars.is_empty()
+            || !local_pars
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .eq(remote_pars.iter().map(|par| par.id))
+        {
+            return None;
+        }
+
+        // The settings block of this document has no ID, so it is added to keep the paragraph ID in TIM
+        let settings = settings.replacen("{", &format!("{{id=\"{}\" ", remote_settings.id), 1);
+        let updates = std::iter::once((remote_settings, settings))
+            .chain(
+                remote_pars
+                    .iter()
+                    .zip(local_pars.into_iter().map(|(_, md)| md)),
+            )
+            .filter(|(remote, local)| content_hash_of(remote.markdown) != content_hash_of(local))
+            .map(|(remote, local)| ParagraphUpdate {
+                par_id: remote.id.to_string(),
+                markdown: local,
+            })
+            .collect();
+        Some(updates)
+    }
+
+    /// Checks if the given markdown is up to date with this document, i.e. the upload can be skipped.
+    ///
+    /// The markdown is up to date if the hash stored in its TIMSync settings block equals the hash
+    /// of this document, i.e. this document has not changed since it was last synced.
+    /// Edits made in TIM after the last sync do not affect the result, so an unchanged document
+    /// is skipped and the edits made in TIM are kept.
+    /// Extra content in the settings block, e.g. a paragraph ID added by TIM, is ignored.
+    ///
+    /// If the settings block is missing or its hash is stale, the markdown is not up to date
+    /// even if the contents are identical, so that the upload writes a fresh settings block.
     ///
     /// # Arguments
     ///
@@ -51,29 +204,175 @@ pub fn with_timestamp(self) -> PreparedDocument {
     ///
     /// returns: bool
     pub fn timestamp_equals(&self, md: &str) -> bool {
-        // Try to find the settings in the markdown with regex
-        let re = regex!(r#"```\s*\{\s*?settings="timsync".*?\}\n(?P<settings>(?:.|\s)*?)```"#);
-        let result = re.captures(md);
-        match result {
-            Some(captures) => {
-                let settings_str = captures.name("settings").unwrap().as_str();
-                TimSyncDocSettings::from_yaml(settings_str)
-                    .map(|settings| settings.hash == self.sha1())
-                    .unwrap_or(false)
-            }
-            None => false,
+        TimSyncDocSettings::find(md).is_some_and(|settings| settings.hash == self.sha1())
+    }
+
+    /// Checks if the given markdown has the same contents as this document.
+    /// Only the TIMSync settings blocks and the blank lines around the contents are ignored,
+    /// so e.g. splitting a paragraph or adding a hard line break is a difference.
+    ///
+    /// Used to upload a document whose settings block is missing or stale without treating
+    /// the contents as edited in TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `md`: The markdown to check
+    ///
+    /// returns: bool
+    pub fn body_equals(&self, md: &str) -> bool {
+        strip_timsync_settings(md).trim_matches('\n')
+            == strip_timsync_settings(&self.markdown).trim_matches('\n')
+    }
+
+    /// Checks if the given document markdown was modified in TIM after it was last synced.
+    /// The check compares the document contents against the content hash stored in the timestamp.
+    ///
+    /// Empty documents and documents synced with an older version of TIMSync
+    /// (i.e. without a content hash) are never considered modified.
+    /// Documents that have contents but no timestamp are considered modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `md`: The current markdown of the document in TIM
+    ///
+    /// returns: bool
+    pub fn is_modified_remotely(md: &str) -> bool {
+        match TimSyncDocSettings::find(md) {
+            Some(settings) => match settings.content_hash {
+                Some(content_hash) => content_hash != content_hash_of(&strip_timsync_settings(md)),
+                None => false,
+            },
+            None => !md.trim().is_empty(),
         }
     }
+
+    /// Checks if the given document markdown contains a TIMSync settings block,
+    /// i.e. whether the document has been synced before.
+    ///
+    /// # Arguments
+    ///
+    /// * `md`: The current markdown of the document in TIM
+    ///
+    /// returns: bool
+    pub fn has_timestamp(md: &str) -> bool {
+        timsync_settings_regex().is_match(md)
+    }
+
+    /// Removes the TIMSync settings blocks from the given document markdown.
+    /// Used to turn a synced document back into a regular TIM document.
+    ///
+    /// # Arguments
+    ///
+    /// * `md`: The current markdown of the document in TIM
+    ///
+    /// returns: Option<String> The markdown without the settings blocks,
+    /// or `None` if the markdown has no settings block.
+    pub fn without_timsync_settings(md: &str) -> Option<String> {
+        match strip_timsync_settings(md) {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(stripped) => Some(stripped.trim_start_matches('\n').to_string()),
+        }
+    }
+}
+
+/// Regex matching an attribute line with a paragraph ID, i.e. `#- {id="..."}` or a code block with an `id` attribute.
+fn par_id_regex() -> &'static Regex {
+    regex!(r#"(?m)^(?:#-|```)[^\n]*?\{[^}\n]*?\bid="(?P<id>[^"]+)""#)
+}
+
+/// Split the markdown into paragraphs that start with an attribute line with a paragraph ID.
+/// Each paragraph continues until the next attribute line with an ID.
+///
+/// returns: Option<Vec<IdParagraph>> The paragraphs, or `None` if there is content before the first paragraph ID.
+fn id_paragraphs(markdown: &str) -> Option<Vec<IdParagraph<'_>>> {
+    let starts = par_id_regex()
+        .captures_iter(markdown)
+        .map(|captures| {
+            let start = captures.get(0).unwrap().start();
+            (start, captures.name("id").unwrap().as_str())
+        })
+        .collect::<Vec<_>>();
+    let first_start = starts.first().map_or(markdown.len(), |(start, _)| *start);
+    if !markdown[..first_start].trim().is_empty() {
+        return None;
+    }
+    let ends = starts
+        .iter()
+        .skip(1)
+        .map(|(start, _)| *start)
+        .chain(std::iter::once(markdown.len()));
+    let paragraphs = starts
+        .iter()
+        .zip(ends)
+        .map(|((start, id), end)| IdParagraph {
+            id,
+            markdown: &markdown[*start..end],
+        })
+        .collect();
+    Some(paragraphs)
+}
+
+/// Regex matching the placeholder document IDs inserted by the `tim_ref` helper.
+/// The prefix must match `TIM_PATH_REF_PREFIX`.
+fn tim_ref_regex() -> &'static Regex {
+    regex!(r#"rd="timsync-path:(?P<path>[^"]+)""#)
+}
+
+/// Regex matching a TIMSync settings block.
+/// The attributes of the block may be in any order, as TIM may add its own attributes (e.g. `id`).
+fn timsync_settings_regex() -> &'static Regex {
+    regex!(
+        r#"(?m)^```[^\n]*\{[^}\n]*\bsettings="timsync"[^}\n]*\}[ \t]*\n(?P<settings>(?:.|\n)*?)^```[ \t]*(?:\n|$)"#
+    )
+}
+
+/// Remove all TIMSync settings blocks from the markdown.
+///
+/// returns: Cow<str>
+fn strip_timsync_settings(markdown: &str) -> Cow<'_, str> {
+    timsync_settings_regex().replace_all(markdown, "")
+}
+
+/// Calculates a SHA1 hash of the markdown contents that ignores blank lines and trailing whitespace.
+/// TIM may normalize the whitespace between paragraphs when storing the document,
+/// so the hash is calculated only over the meaningful lines.
+///
+/// returns: String
+fn content_hash_of(markdown: &str) -> String {
+    let text: String = markdown
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty())
+        .map(|l| format!("{}\n", l))
+        .collect();
+    sha1_hex(&text)
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 struct TimSyncDocSettings {
     hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_path: Option<String>,
 }
 
 impl TimSyncDocSettings {
-    fn new(hash: String) -> Self {
-        Self { hash }
+    fn new(hash: String, content_hash: String, source_path: Option<String>) -> Self {
+        Self {
+            hash,
+            content_hash: Some(content_hash),
+            source_path,
+        }
+    }
+
+    /// Find the first TIMSync settings block in the markdown.
+    ///
+    /// returns: Option<TimSyncDocSettings>
+    fn find(md: &str) -> Option<Self> {
+        let captures = timsync_settings_regex().captures(md)?;
+        let settings_str = captures.name("settings").unwrap().as_str();
+        Self::from_yaml(settings_str).ok()
     }
 
     fn from_yaml(yaml: &str) -> anyhow::Result<Self> {
@@ -87,3 +386,254 @@ fn to_markdown(&self) -> String {
         format!("``` {{settings=\"timsync\"}}\n{}```\n", yaml_str)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(markdown: &str) -> PreparedDocument {
+        PreparedDocument {
+            markdown: markdown.to_string(),
+            upload_files: HashMap::new(),
+            source_path: None,
+            tim_settings: None,
+        }
+    }
+
+    /// Markdown of the document as uploaded to TIM by the sync
+    fn synced(markdown: &str) -> String {
+        document(markdown).with_timestamp().markdown
+    }
+
+    #[test]
+    fn without_timsync_settings_restores_the_document() {
+        let markdown = "# Title\n\nText\n";
+        assert_eq!(
+            PreparedDocument::without_timsync_settings(&synced(markdown)).as_deref(),
+            Some(markdown)
+        );
+        assert_eq!(PreparedDocument::without_timsync_settings(markdown), None);
+    }
+
+    #[test]
+    fn timestamp_equals_synced_document() {
+        let remote = synced("# Title\n\nContents\n");
+        assert!(document("# Title\n\nContents\n").timestamp_equals(&remote));
+    }
+
+    #[test]
+    fn timestamp_equals_ignores_extra_settings_content() {
+        // TIM adds a paragraph ID to the block, and the block may have been edited in TIM
+        let remote = synced("# Title\n\nContents\n")
+            .replacen(
+                "``` {settings=\"timsync\"}",
+                "``` {id=\"abc123\" settings=\"timsync\"}",
+                1,
+            )
+            .replacen("hash:", "extra_key: value\nhash:", 1);
+        assert!(document("# Title\n\nContents\n").timestamp_equals(&remote));
+    }
+
+    #[test]
+    fn timestamp_equals_identical_body_with_stale_hash() {
+        let remote = "``` {settings=\"timsync\"}\nhash: outdated\n```\n\n# Title\n\nContents\n";
+        let local = document("# Title\n\nContents\n");
+        // The document is uploaded to write a fresh settings block
+        assert!(!local.timestamp_equals(remote));
+        assert!(local.body_equals(remote));
+    }
+
+    #[test]
+    fn timestamp_equals_identical_body_without_settings() {
+        let local = document("# Title\n\nContents\n");
+        assert!(!local.timestamp_equals("# Title\n\nContents\n"));
+        assert!(local.body_equals("# Title\n\nContents\n"));
+    }
+
+    #[test]
+    fn body_equals_detects_whitespace_changes_within_the_body() {
+        let remote = synced("# Title\n\nFirst\nSecond\n");
+        assert!(document("# Title\n\nFirst\nSecond\n").body_equals(&remote));
+        assert!(!document("# Title\n\nFirst\n\nSecond\n").body_equals(&remote));
+        assert!(!document("# Title\n\nFirst  \nSecond\n").body_equals(&remote));
+    }
+
+    #[test]
+    fn timestamp_equals_skips_unchanged_document_edited_in_tim() {
+        let remote = synced("# Title\n\nContents\n").replace("Contents", "Edited in TIM");
+        assert!(PreparedDocument::is_modified_remotely(&remote));
+        assert!(document("# Title\n\nContents\n").timestamp_equals(&remote));
+    }
+
+    #[test]
+    fn timestamp_equals_detects_local_changes() {
+        let remote = synced("# Title\n\nContents\n");
+        assert!(!document("# Title\n\nNew contents\n").timestamp_equals(&remote));
+        assert!(!document("# Title\n\nNew contents\n").timestamp_equals(""));
+    }
+
+    #[test]
+    fn is_modified_remotely_ignores_settings_block_changes() {
+        let remote = synced("# Title\n\nContents\n").replacen(
+            "``` {settings=\"timsync\"}",
+            "``` {settings=\"timsync\" id=\"abc123\"}",
+            1,
+        );
+        assert!(!PreparedDocument::is_modified_remotely(&remote));
+        assert!(!PreparedDocument::is_modified_remotely(""));
+        assert!(PreparedDocument::is_modified_remotely("Created in TIM"));
+    }
+
+    /// Markdown of a document where every paragraph has an ID
+    fn id_document(paragraphs: &[(&str, &str)]) -> String {
+        paragraphs
+            .iter()
+            .map(|(id, text)| format!("#- {{id=\"{}\"}}\n{}\n\n", id, text))
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_par_ids_of_unique_ids_is_empty() {
+        let markdown = id_document(&[("a", "First"), ("b", "Second")]);
+        assert!(document(&markdown).duplicate_par_ids().is_empty());
+    }
+
+    #[test]
+    fn duplicate_par_ids_are_reported_once_in_order() {
+        let markdown = id_document(&[
+            ("b", "First"),
+            ("a", "Second"),
+            ("a", "Third"),
+            ("b", "Fourth"),
+            ("a", "Fifth"),
+        ]);
+        assert_eq!(document(&markdown).duplicate_par_ids(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn duplicate_par_ids_include_code_block_ids() {
+        let markdown = format!(
+            "{}``` {{id=\"a\" plugin=\"csPlugin\"}}\ntype: cs\n```\n",
+            id_document(&[("a", "First")])
+        );
+        assert_eq!(document(&markdown).duplicate_par_ids(), vec!["a"]);
+    }
+
+    /// Markdown of a synced document as stored by TIM, which adds an ID to the settings block
+    fn stored_in_tim(markdown: &str) -> String {
+        synced(markdown).replacen("``` {", "``` {id=\"settingsPar\" ", 1)
+    }
+
+    fn updated_ids(updates: &[ParagraphUpdate]) -> Vec<&str> {
+        updates
+            .iter()
+            .map(|update| update.par_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn id_paragraphs_splits_at_ids() {
+        let markdown = id_document(&[("a", "First"), ("b", "Second")]);
+        let paragraphs = id_paragraphs(&markdown).unwrap();
+        let ids = paragraphs.iter().map(|par| par.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(paragraphs[0].markdown, "#- {id=\"a\"}\nFirst\n\n");
+        assert_eq!(paragraphs[1].markdown, "#- {id=\"b\"}\nSecond\n\n");
+    }
+
+    #[test]
+    fn id_paragraphs_requires_id_for_first_paragraph() {
+        let markdown = format!("# Title\n\n{}", id_document(&[("a", "First")]));
+        assert!(id_paragraphs(&markdown).is_none());
+    }
+
+    #[test]
+    fn changed_paragraphs_of_unchanged_document_is_empty() {
+        let markdown = id_document(&[("a", "First"), ("b", "Second")]);
+        let remote = stored_in_tim(&markdown);
+        let local = document(&markdown).with_timestamp();
+        let updates = local.changed_paragraphs(&remote).unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[test]
+    fn changed_paragraphs_with_identical_ids() {
+        let remote = stored_in_tim(&id_document(&[("a", "First"), ("b", "Second")]));
+        let local = document(&id_document(&[("a", "First"), ("b", "Changed")])).with_timestamp();
+        let updates = local.changed_paragraphs(&remote).unwrap();
+        // The settings block is updated with the new hash and keeps its ID in TIM
+        assert_eq!(updated_ids(&updates), vec!["settingsPar", "b"]);
+        assert!(updates[0]
+            .markdown
+            .starts_with("``` {id=\"settingsPar\" settings=\"timsync\"}"));
+        assert_eq!(updates[1].markdown, "#- {id=\"b\"}\nChanged\n\n");
+    }
+
+    #[test]
+    fn changed_paragraphs_ignores_whitespace_changes_in_tim() {
+        let remote = stored_in_tim(&id_document(&[("a", "First"), ("b", "Second")]))
+            .replace("First\n\n", "First  \n\n\n");
+        let local = document(&id_document(&[("a", "First"), ("b", "Second")])).with_timestamp();
+        assert!(local.changed_paragraphs(&remote).unwrap().is_empty());
+    }
+
+    #[test]
+    fn changed_paragraphs_of_reordered_paragraphs_is_none() {
+        let remote = stored_in_tim(&id_document(&[("a", "First"), ("b", "Second")]));
+        let local = document(&id_document(&[("b", "Second"), ("a", "First")])).with_timestamp();
+        assert!(local.changed_paragraphs(&remote).is_none());
+    }
+
+    #[test]
+    fn changed_paragraphs_of_inserted_paragraph_is_none() {
+        let remote = stored_in_tim(&id_document(&[("a", "First"), ("b", "Second")]));
+        let local = document(&id_document(&[
+            ("a", "First"),
+            ("c", "New"),
+            ("b", "Second"),
+        ]))
+        .with_timestamp();
+        assert!(local.changed_paragraphs(&remote).is_none());
+    }
+
+    #[test]
+    fn changed_paragraphs_of_deleted_paragraph_is_none() {
+        let remote = stored_in_tim(&id_document(&[("a", "First"), ("b", "Second")]));
+        let local = document(&id_document(&[("a", "First")])).with_timestamp();
+        assert!(local.changed_paragraphs(&remote).is_none());
+    }
+
+    #[test]
+    fn changed_paragraphs_without_remote_settings_is_none() {
+        let remote = id_document(&[("a", "First"), ("b", "Second")]);
+        let local = document(&id_document(&[("a", "First"), ("b", "Changed")])).with_timestamp();
+        assert!(local.changed_paragraphs(&remote).is_none());
+    }
+
+    #[test]
+    fn changed_paragraphs_of_document_without_ids_is_none() {
+        let remote = stored_in_tim("# Title\n\nContents\n");
+        let local = document("# Title\n\nChanged\n").with_timestamp();
+        assert!(local.changed_paragraphs(&remote).is_none());
+    }
+
+    #[test]
+    fn tim_refs_are_resolved_to_document_ids() {
+        let markdown = "#- {rd=\"timsync-path:kurssit/tie/a\" ra=\"x\"}\n\n#-";
+        let mut doc = document(markdown);
+        assert_eq!(doc.tim_ref_paths(), vec!["kurssit/tie/a"]);
+        doc.resolve_tim_refs(&HashMap::from([("kurssit/tie/a".to_string(), 12)]))
+            .unwrap();
+        assert_eq!(doc.markdown, "#- {rd=\"12\" ra=\"x\"}\n\n#-");
+
+        let mut doc = document(markdown);
+        assert!(doc.resolve_tim_refs(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn tim_refs_can_be_written_as_paths() {
+        let mut doc = document("#- {rd=\"timsync-path:kurssit/tie/a\" ra=\"x\"}\n\n#-");
+        doc.write_tim_ref_paths();
+        assert_eq!(doc.markdown, "#- {rd=\"kurssit/tie/a\" ra=\"x\"}\n\n#-");
+    }
+}
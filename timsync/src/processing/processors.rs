@@ -92,4 +92,15 @@ pub(in crate::processing) trait FileProcessorInternalAPI {
     ///
     /// returns: Option<String>
     fn get_project_file_local_path(&self, tim_document: &TIMDocument) -> Option<String>;
+
+    /// Get the source text of the TIM document without the front matter.
+    ///
+    /// If the TIM document is produced from a single text document, this method returns its contents
+    /// before rendering. Otherwise, it returns None.
+    ///
+    /// # Arguments
+    /// * `tim_document` - The TIM document to get the source text for.
+    ///
+    /// returns: Result<Option<String>>
+    fn get_project_file_source_text(&self, tim_document: &TIMDocument) -> Result<Option<String>>;
 }
@@ -2,7 +2,7 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use indoc::indoc;
 use serde_json::{Map, Value};
 
@@ -32,7 +32,8 @@ pub fn new(
         global_context: Rc<OnceCell<GlobalContext>>,
     ) -> Result<Self> {
         Ok(Self {
-            markdown_processor: MarkdownProcessor::new(project, sync_target, global_context)?,
+            markdown_processor: MarkdownProcessor::new(project, sync_target, global_context)?
+                .without_configured_templates(),
             file_paths_by_name: HashMap::new(),
         })
     }
@@ -103,24 +104,40 @@ fn get_tim_documents(&self) -> Vec<TIMDocument> {
 
 impl<'a> FileProcessorInternalAPI for StyleThemeProcessor<'a> {
     fn render_tim_document(&self, tim_document: &TIMDocument) -> Result<PreparedDocument> {
-        let processed_style_doc = self.markdown_processor.render_tim_document(tim_document)?;
+        let processed_style_doc = self
+            .markdown_processor
+            .render_document_contents(tim_document)?;
+
+        // Any TIM settings from the front matter are merged into the theme settings block
+        let mut theme_settings = self
+            .markdown_processor
+            .get_tim_settings(tim_document)
+            .cloned()
+            .unwrap_or_default();
+        theme_settings.insert(
+            "description".to_string(),
+            Value::String(tim_document.title.to_string()),
+        );
+        let settings_yaml = serde_yaml::to_string(&theme_settings)
+            .context("Could not serialize style theme settings")?;
 
         let final_markdown = format!(
             indoc! {r#"
             ``` {{settings=""}}
-            description: "{}"
-            ```
+            {}```
 
             ```scss
             {}
             ```"#
             },
-            tim_document.title, processed_style_doc.markdown
+            settings_yaml, processed_style_doc.markdown
         );
 
         Ok(PreparedDocument {
             markdown: final_markdown,
             upload_files: processed_style_doc.upload_files,
+            source_path: None,
+            tim_settings: None,
         })
     }
 
@@ -133,4 +150,9 @@ fn get_project_file_local_path(&self, tim_document: &TIMDocument) -> Option<Stri
         self.markdown_processor
             .get_project_file_local_path(tim_document)
     }
+
+    fn get_project_file_source_text(&self, _: &TIMDocument) -> Result<Option<String>> {
+        // Style themes are not meant to be read as text
+        Ok(None)
+    }
 }
@@ -15,16 +15,20 @@
 use crate::project::files::project_files::{ProjectFile, ProjectFileAPI};
 use crate::project::global_ctx::GlobalContext;
 use crate::project::project::Project;
-use crate::templating::ext_context::ContextExtension;
+use crate::templating::document_context::{build_document_context, DocumentContextInfo};
 use crate::templating::ext_render_with_context::RendererExtension;
 use crate::templating::tim_handlebars::{TimRendererExt, FILE_MAP_ATTRIBUTE};
+use crate::util::json::Merge;
 use crate::util::path::RelativizeExtension;
+use crate::util::text::sha1_hex;
 use crate::util::tim_client::hashed_par_id;
 
 struct TaskInfo {
     par_id: String,
     file: ProjectFile,
     task_settings: TaskSettings,
+    /// Front matter of the task file merged over the fragments it extends.
+    front_matter: Value,
 }
 
 /// Processor for TIM plugin tasks.
@@ -43,8 +47,13 @@ pub struct TaskProcessor<'a> {
     files: HashMap<String, TaskInfo>,
     renderer: Handlebars<'a>,
     global_context: Rc<OnceCell<GlobalContext>>,
+    /// Shared front matter fragments that tasks can extend, keyed by fragment name.
+    fragments: HashMap<String, Value>,
 }
 
+/// Front matter key used by the tasks to declare the fragments they extend.
+const EXTENDS_KEY: &str = "extends";
+
 /// Path to the generated tasks document.
 pub const TASKS_DOCPATH: &str = "_project_tasks";
 /// Title of the generated tasks document.
@@ -55,6 +64,11 @@ pub struct TaskProcessor<'a> {
 /// Key for the tasks reference map in the global context.
 /// Used by the templating engine to implement the `task` helper.
 pub const TASKS_REF_MAP_KEY: &str = "_timsync_tasks_ref_map";
+/// Key for the task groups in the global context.
+/// Maps the folder of the task files relative to the project root (e.g. `tasks/week1`)
+/// to the UIDs of the tasks in the folder, sorted in the order of the tasks document.
+/// Used by the templating engine to implement the `task_nth` helper.
+pub const TASKS_GROUPS_KEY: &str = "_timsync_tasks_groups";
 
 /// Settings for a task. Must be defined in front matter of each project file
 /// that will be processed as a task.
@@ -65,14 +79,14 @@ struct TaskSettings {
     plugin: String,
     /// Additional attributes to be added to the plugin paragraph. Optional.
     /// Any key-value pair will be added to the paragraph as such:
-    /// ````
+    /// ````md
     /// ``` {key1="value1" key2="value2" ...}
     /// ```
     /// ````
     plugin_attributes: Option<Map<String, Value>>,
     /// Additional classes to be added to the plugin paragraph. Optional.
     /// The value will be added to the paragraph as such:
-    /// ````
+    /// ````md
     /// ``` {.class1 .class2 ...}
     /// ```
     /// ````
@@ -89,18 +103,106 @@ impl<'a> TaskProcessor<'a> {
     ///
     /// returns: Result<TaskProcessor>
     pub fn new(project: &'a Project, global_context: Rc<OnceCell<GlobalContext>>) -> Result<Self> {
-        let renderer = Handlebars::new()
+        let mut renderer = Handlebars::new()
             .with_base_helpers()
             .with_project_templates(project)?
             .with_project_helpers(project)?;
+        renderer.set_strict_mode(project.config.strict_templating);
+
+        let fragments = Self::load_fragments(project)?;
 
         Ok(Self {
             project,
             files: HashMap::new(),
             renderer,
             global_context,
+            fragments,
         })
     }
+
+    /// Load the shared front matter fragments from the `task_fragments_folder` of the config.
+    /// Each YAML file in the folder is a fragment named by its path relative to the folder
+    /// without the extension, e.g. `_partials/cs/defaults.yml` is named `cs/defaults`.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - The project to load the fragments from.
+    ///
+    /// returns: Result<HashMap<String, Value>>
+    fn load_fragments(project: &Project) -> Result<HashMap<String, Value>> {
+        let mut fragments = HashMap::new();
+        let fragments_folder = &project.config.task_fragments_folder;
+        let fragment_files = project
+            .find_files(fragments_folder, "*.y*ml")
+            .with_context(|| {
+                format!(
+                    "Could not find task fragments from folder {}",
+                    fragments_folder
+                )
+            })?;
+        for (name, path) in fragment_files {
+            let name = name.trim_end_matches(".yml").trim_end_matches(".yaml");
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Could not read task fragment {}", path.display()))?;
+            let fragment: Value = serde_yaml::from_str(&contents)
+                .with_context(|| format!("Could not parse task fragment {}", path.display()))?;
+            if !fragment.is_object() {
+                return Err(anyhow!(
+                    "Task fragment {} must be a YAML mapping",
+                    path.display()
+                ));
+            }
+            fragments.insert(name.to_string(), fragment);
+        }
+        Ok(fragments)
+    }
+
+    /// Resolve the front matter of a task file by merging it over the fragments it extends.
+    ///
+    /// The fragments are declared with the `extends` key as a single name or a list of names.
+    /// The fragments are merged in the order they are listed, so later fragments override earlier ones.
+    /// The front matter of the task file always takes precedence over the fragments.
+    /// Fragments cannot extend other fragments.
+    ///
+    /// # Arguments
+    ///
+    /// * `front_matter` - The front matter of the task file.
+    ///
+    /// returns: Result<Value>
+    fn resolve_front_matter(&self, front_matter: Value) -> Result<Value> {
+        let fragment_names = match front_matter.get(EXTENDS_KEY) {
+            None => return Ok(front_matter),
+            Some(Value::String(name)) => vec![name.as_str()],
+            Some(Value::Array(names)) => names
+                .iter()
+                .map(|name| {
+                    name.as_str().ok_or_else(|| {
+                        anyhow!("`{}` must be a list of fragment names", EXTENDS_KEY)
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => {
+                return Err(anyhow!(
+                    "`{}` must be a fragment name or a list of fragment names",
+                    EXTENDS_KEY
+                ))
+            }
+        };
+
+        let mut result = Value::Object(Map::new());
+        for name in fragment_names {
+            let fragment = self.fragments.get(name).ok_or_else(|| {
+                anyhow!(
+                    "Task fragment `{}` not found. Fragments are loaded from the `{}` folder.",
+                    name,
+                    self.project.config.task_fragments_folder
+                )
+            })?;
+            result.merge(fragment);
+        }
+        result.merge(&front_matter);
+        Ok(result)
+    }
 }
 
 impl<'a> FileProcessorAPI for TaskProcessor<'a> {
@@ -119,17 +221,40 @@ fn add_file(&mut self, file: ProjectFile) -> Result<()> {
             ));
         }
 
-        let task_settings: TaskSettings = serde_yaml::from_str(file.front_matter()?)
+        let front_matter = self
+            .resolve_front_matter(file.front_matter_json()?)
+            .with_context(|| {
+                format!(
+                    "Could not resolve the fragments of task {}",
+                    file.path().display()
+                )
+            })?;
+        let task_settings: TaskSettings = serde_json::from_value(front_matter.clone())
             .context("Could not read task information from front matter")?;
 
         let par_id = hashed_par_id(Some(&uid));
 
+        // Paragraph IDs must be unique within the tasks document
+        if let Some((other_uid, other_task)) =
+            self.files.iter().find(|(_, task)| task.par_id == par_id)
+        {
+            return Err(anyhow!(
+                "Tasks with UIDs `{}` ({}) and `{}` ({}) produce the same paragraph ID `{}`. Rename one of the UIDs.",
+                uid,
+                file.path().display(),
+                other_uid,
+                other_task.file.path().display(),
+                par_id
+            ));
+        }
+
         self.files.insert(
             uid,
             TaskInfo {
                 par_id,
                 file,
                 task_settings,
+                front_matter,
             },
         );
         Ok(())
@@ -140,8 +265,23 @@ fn get_processor_context(&self) -> Option<Map<String, Value>> {
         for (uid, task_info) in self.files.iter() {
             ref_map.insert(uid.clone(), Value::String(task_info.par_id.clone()));
         }
+        let project_root_dir = self.project.get_root_path();
+        let mut groups = Map::new();
+        for (uid, task_info) in self.files.iter().sorted_by_key(|&(uid, _)| uid) {
+            let group = task_info
+                .file
+                .path()
+                .relativize(project_root_dir)
+                .parent()
+                .map(|folder| folder.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            if let Value::Array(uids) = groups.entry(group).or_insert_with(|| json!([])) {
+                uids.push(Value::String(uid.clone()));
+            }
+        }
         let mut res = Map::new();
         res.insert(TASKS_REF_MAP_KEY.to_string(), Value::Object(ref_map));
+        res.insert(TASKS_GROUPS_KEY.to_string(), Value::Object(groups));
         Some(res)
     }
 
@@ -156,7 +296,7 @@ fn get_tim_documents(&self) -> Vec<TIMDocument> {
 }
 
 impl<'a> FileProcessorInternalAPI for TaskProcessor<'a> {
-    fn render_tim_document(&self, _: &TIMDocument) -> Result<PreparedDocument> {
+    fn render_tim_document(&self, tim_document: &TIMDocument) -> Result<PreparedDocument> {
         // This processor produces only one document.
         // Idea:
         // 1. Iterate over all project files and pass them through the Handlebars renderer
@@ -178,20 +318,21 @@ fn render_tim_document(&self, _: &TIMDocument) -> Result<PreparedDocument> {
                 .to_string();
             let contents = task_info.file.contents_without_front_matter()?;
 
-            let mut ctx = self
-                .global_context
-                .get()
-                .expect("Global context not set")
-                .handlebars_context();
-            ctx.extend_with_json(&task_info.file.front_matter_json()?);
-            // We manually override the original "local_file_path"
-            // to correctly point to the currently processed file
-            // We also insert the path to point to the tasks document
+            // The "local_file_path" points to the currently processed task file
+            // and the path points to the tasks document
             // so that the "file" helper can be used in the task files
-            ctx.extend_with_json(&json!({
-                "path": TASKS_DOCPATH,
-                "local_file_path": proj_file_path
-            }));
+            let ctx = build_document_context(
+                self.global_context.get().expect("Global context not set"),
+                &task_info.front_matter,
+                &DocumentContextInfo {
+                    title: None,
+                    path: TASKS_DOCPATH,
+                    doc_id: tim_document.id,
+                    local_file_path: Some(proj_file_path),
+                    source_hash: Some(sha1_hex(task_info.file.contents()?)),
+                    modified: None,
+                },
+            );
 
             write!(
                 result_buf,
@@ -247,6 +388,8 @@ fn render_tim_document(&self, _: &TIMDocument) -> Result<PreparedDocument> {
         Ok(PreparedDocument {
             markdown: result_str,
             upload_files: upload_files_map,
+            source_path: None,
+            tim_settings: None,
         })
     }
 
@@ -260,4 +403,169 @@ fn get_project_file_front_matter_json(&self, _: &TIMDocument) -> Result<Value> {
     fn get_project_file_local_path(&self, _: &TIMDocument) -> Option<String> {
         None
     }
+
+    fn get_project_file_source_text(&self, _: &TIMDocument) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_project;
+    use tempfile::TempDir;
+
+    /// Create a project with the given task files in a temporary directory.
+    fn project_with_tasks(tasks: &[(&str, &str)]) -> (TempDir, Project) {
+        test_project::project("[targets]\n", tasks)
+    }
+
+    /// Create a task processor with all task files of the project added.
+    fn processor<'a>(
+        project: &'a Project,
+        tasks: &[(&str, &str)],
+        global_context: Rc<OnceCell<GlobalContext>>,
+    ) -> TaskProcessor<'a> {
+        let mut processor = TaskProcessor::new(project, global_context).unwrap();
+        for (path, _) in tasks {
+            let path = project.get_root_path().join(path);
+            processor
+                .add_file(ProjectFile::try_from(path).unwrap())
+                .unwrap();
+        }
+        processor
+    }
+
+    #[test]
+    fn task_body_can_use_misc_helpers() {
+        let tasks = [(
+            "hello.task.yml",
+            "---\nuid: hello\nplugin: csPlugin\n---\nstem: {{to_upper_case \"hello\"}} in {{len \"word\"}} letters\n",
+        )];
+        let (_dir, project) = project_with_tasks(&tasks);
+        let global_context = Rc::new(OnceCell::new());
+        let processor = processor(&project, &tasks, global_context.clone());
+        let _ = global_context.set(GlobalContext::new());
+
+        let documents = processor.get_tim_documents();
+        let prepared = processor.render_tim_document(&documents[0]).unwrap();
+        assert!(
+            prepared.markdown.contains("stem: HELLO in 4 letters"),
+            "{}",
+            prepared.markdown
+        );
+    }
+
+    #[test]
+    fn tasks_are_grouped_by_folder_in_uid_order() {
+        let task = |uid: &str| format!("---\nuid: {}\nplugin: csPlugin\n---\nstem: x\n", uid);
+        let (b2, a1, c1, root) = (task("b2"), task("a1"), task("c1"), task("root"));
+        let tasks = [
+            ("tasks/week1/second.task.yml", b2.as_str()),
+            ("tasks/week1/first.task.yml", a1.as_str()),
+            ("tasks/week2/task.task.yml", c1.as_str()),
+            ("root.task.yml", root.as_str()),
+        ];
+        let (_dir, project) = project_with_tasks(&tasks);
+        let processor = processor(&project, &tasks, Rc::new(OnceCell::new()));
+
+        let context = processor.get_processor_context().unwrap();
+        assert_eq!(
+            context[TASKS_GROUPS_KEY],
+            json!({
+                "": ["root"],
+                "tasks/week1": ["a1", "b2"],
+                "tasks/week2": ["c1"],
+            })
+        );
+    }
+
+    /// Resolve the front matter of a task in a project with the given config and fragment files.
+    fn resolve_with_fragments(
+        config: &str,
+        fragments: &[(&str, &str)],
+        front_matter: Value,
+    ) -> Result<Value> {
+        let (_dir, project) = test_project::project(config, fragments);
+        let processor = TaskProcessor::new(&project, Rc::new(OnceCell::new()))?;
+        processor.resolve_front_matter(front_matter)
+    }
+
+    const FRAGMENTS: [(&str, &str); 2] = [
+        (
+            "_partials/base.yml",
+            "plugin: csPlugin\nplugin_attributes:\n  type: py\n  stem: Base stem\n  lang: fi\n",
+        ),
+        (
+            "_partials/exam.yml",
+            "plugin_attributes:\n  stem: Exam stem\n  points: 2\n",
+        ),
+    ];
+
+    #[test]
+    fn extends_merges_fragments_in_order_under_the_task() {
+        let resolved = resolve_with_fragments(
+            "[targets]\n",
+            &FRAGMENTS,
+            json!({
+                "uid": "t1",
+                "extends": ["base", "exam"],
+                "plugin_attributes": { "lang": "en" },
+            }),
+        )
+        .unwrap();
+        assert_eq!(resolved["plugin"], "csPlugin");
+        assert_eq!(
+            resolved["plugin_attributes"],
+            json!({ "type": "py", "stem": "Exam stem", "lang": "en", "points": 2 })
+        );
+        assert_eq!(resolved["uid"], "t1");
+    }
+
+    #[test]
+    fn extends_accepts_a_single_fragment_name() {
+        let resolved = resolve_with_fragments(
+            "[targets]\n",
+            &FRAGMENTS,
+            json!({ "uid": "t1", "extends": "exam", "plugin": "other" }),
+        )
+        .unwrap();
+        assert_eq!(resolved["plugin"], "other");
+        assert_eq!(
+            resolved["plugin_attributes"],
+            json!({ "stem": "Exam stem", "points": 2 })
+        );
+    }
+
+    #[test]
+    fn extends_fails_for_unknown_fragment() {
+        let error = resolve_with_fragments(
+            "[targets]\n",
+            &FRAGMENTS,
+            json!({ "uid": "t1", "extends": ["base", "missing"] }),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.contains("`missing` not found") && error.contains("`_partials` folder"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn fragments_are_loaded_from_the_configured_folder() {
+        let config = "task_fragments_folder = \"_shared/tasks\"\n\n[targets]\n";
+        let fragments = [
+            ("_shared/tasks/base.yml", "plugin: csPlugin\n"),
+            ("_partials/base.yml", "plugin: ignored\n"),
+        ];
+        let resolved = resolve_with_fragments(
+            config,
+            &fragments,
+            json!({ "uid": "t1", "extends": "base" }),
+        )
+        .unwrap();
+        assert_eq!(resolved["plugin"], "csPlugin");
+    }
 }
@@ -29,7 +29,12 @@ pub fn render_contents(&self) -> Result<PreparedDocument> {
     /// Get the general metadata associated with the TIM document.
     pub fn general_metadata(&self) -> Result<GeneralProjectFileMetadata> {
         let json = self.renderer.get_project_file_front_matter_json(&self)?;
-        serde_json::from_value(json).context("Failed to deserialize general metadata")
+        serde_json::from_value(json).with_context(|| {
+            format!(
+                "Failed to deserialize general metadata of document {}",
+                self.get_local_file_path().unwrap_or(self.path.to_string())
+            )
+        })
     }
 
     /// Get the front matter associated with the TIM document.
@@ -47,4 +52,13 @@ pub fn front_matter_json(&self) -> Result<Value> {
     pub fn get_local_file_path(&self) -> Option<String> {
         self.renderer.get_project_file_local_path(&self)
     }
+
+    /// Get the source text of the TIM document before rendering, without the front matter.
+    ///
+    /// Returns None if the TIM document is not produced from a single text document.
+    ///
+    /// returns: Result<Option<String>>
+    pub fn source_text(&self) -> Result<Option<String>> {
+        self.renderer.get_project_file_source_text(self)
+    }
 }
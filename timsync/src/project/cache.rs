@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::project::config::CONFIG_FOLDER;
+
+/// Name of the cache folder inside the config folder
+pub const CACHE_FOLDER: &str = "cache";
+/// Name of the cache file for the intrinsic dimensions of images
+pub const IMAGE_DIMENSIONS_CACHE_FILE: &str = "image_dimensions.json";
+
+/// The cache folder of a project (`.timsync/cache`).
+///
+/// All caches of the project are stored as files in the same folder,
+/// so that they can be cleared together with `--clean-cache`.
+/// The contents of the cache can be removed at any time without affecting the synced documents.
+pub struct ProjectCache {
+    cache_path: PathBuf,
+}
+
+impl ProjectCache {
+    /// Get the cache folder of a project.
+    /// The folder is not created until a cache file is requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path`: The path to the project directory
+    ///
+    /// returns: ProjectCache
+    pub fn for_project(project_path: &Path) -> Self {
+        Self {
+            cache_path: project_path.join(CONFIG_FOLDER).join(CACHE_FOLDER),
+        }
+    }
+
+    /// Get the path to the cache folder.
+    pub fn get_path(&self) -> &Path {
+        &self.cache_path
+    }
+
+    /// Get the path to a file in the cache folder, creating the cache folder if it does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Name of the cache file, e.g. `IMAGE_DIMENSIONS_CACHE_FILE`.
+    ///
+    /// returns: Result<PathBuf, Error>
+    pub fn file(&self, name: &str) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.cache_path).with_context(|| {
+            format!(
+                "Could not create the cache folder {}",
+                self.cache_path.display()
+            )
+        })?;
+        Ok(self.cache_path.join(name))
+    }
+
+    /// Remove the cache folder and all cache files in it.
+    ///
+    /// returns: Result<bool, Error> `true` if the cache folder existed and was removed.
+    pub fn clear(&self) -> Result<bool> {
+        if !self.cache_path.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_dir_all(&self.cache_path).with_context(|| {
+            format!(
+                "Could not remove the cache folder {}",
+                self.cache_path.display()
+            )
+        })?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_removes_the_cache_folder_and_file_recreates_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ProjectCache::for_project(dir.path());
+        assert!(!cache.clear().unwrap());
+
+        let file = cache.file(IMAGE_DIMENSIONS_CACHE_FILE).unwrap();
+        std::fs::write(&file, "{}").unwrap();
+        assert!(cache.clear().unwrap());
+        assert!(!cache.get_path().exists());
+
+        let file = cache.file(IMAGE_DIMENSIONS_CACHE_FILE).unwrap();
+        assert!(cache.get_path().is_dir());
+        assert!(!file.exists());
+    }
+}
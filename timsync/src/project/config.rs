@@ -1,8 +1,14 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use itertools::Itertools;
+use lazy_regex::{regex, Captures};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::util::json::Merge;
+use crate::util::text::edit_distance;
 
 /// Default TIM host to use if no host is specified
 pub const DEFAULT_SYNC_TARGET_HOST: &str = "https://tim.jyu.fi";
@@ -10,6 +16,19 @@
 pub const CONFIG_FOLDER: &str = ".timsync";
 /// Name of the config file for TIMSync
 pub const CONFIG_FILE_NAME: &str = "config.toml";
+/// Name of the environment whose config override is always applied if present
+pub const LOCAL_CONFIG_ENV: &str = "local";
+/// Path of the shared attachments document relative to the target folder root
+pub const SHARED_FILES_DOCPATH: &str = "_files";
+/// Title of the shared attachments document
+pub const SHARED_FILES_TITLE: &str = "Shared files";
+/// Maximum edit distance of a configured target name to suggest it for a missing target
+const MAX_TARGET_SUGGESTION_DISTANCE: usize = 2;
+/// Fields that every sync target must set, with example values for error messages
+const REQUIRED_TARGET_FIELDS: [(&str, &str); 2] = [
+    ("host", DEFAULT_SYNC_TARGET_HOST),
+    ("folder_root", "kurssit/tie/kurssi"),
+];
 
 #[derive(Deserialize, Serialize)]
 /// The configuration for TIMSync
@@ -22,6 +41,236 @@ pub struct SyncConfig {
     /// the target to which the documents are synced to.
     /// The default target is called `default`.
     targets: HashMap<String, SyncTarget>,
+
+    /// Post-processing transforms applied to the documents before uploading
+    #[serde(default, skip_serializing_if = "PostProcessConfig::is_default")]
+    pub postprocess: PostProcessConfig,
+
+    /// Settings for rendering the Markdown documents
+    #[serde(default, skip_serializing_if = "MarkdownConfig::is_default")]
+    pub markdown: MarkdownConfig,
+
+    /// Whether to record the project-relative path of the source file in the synced documents.
+    /// The path is stored in the TIMSync settings block of the document in TIM.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub embed_source_path: bool,
+
+    /// The style of links generated for links between the project documents
+    #[serde(default, skip_serializing_if = "LinkStyle::is_default")]
+    pub link_style: LinkStyle,
+
+    /// Required front matter fields of the documents by document type
+    ///
+    /// The keys are values of the `type` front matter field.
+    /// Each type maps the required field names to their expected value types.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub schema: HashMap<String, HashMap<String, FieldType>>,
+
+    /// Whether references to undefined template variables are errors instead of empty strings
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_templating: bool,
+
+    /// Whether links to missing local files are errors instead of warnings
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_links: bool,
+
+    /// Glob patterns of files to exclude from the project, relative to the project root.
+    /// Applied in addition to the patterns in `.timsyncignore`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// Names of the template themes of the project, from the lowest to the highest precedence.
+    /// The templates of each theme are read from `_themes/<name>`.
+    /// The templates of the project override the templates of the themes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub template_themes: Vec<String>,
+
+    /// Folder of the shared front matter fragments of the task files, relative to the project root.
+    /// The folder should start with `_` so that the fragments are not synced as documents.
+    #[serde(
+        default = "default_task_fragments_folder",
+        skip_serializing_if = "is_default_task_fragments_folder"
+    )]
+    pub task_fragments_folder: String,
+}
+
+/// Default folder of the shared task front matter fragments
+pub const DEFAULT_TASK_FRAGMENTS_FOLDER: &str = "_partials";
+
+fn default_task_fragments_folder() -> String {
+    DEFAULT_TASK_FRAGMENTS_FOLDER.to_string()
+}
+
+fn is_default_task_fragments_folder(folder: &str) -> bool {
+    folder == DEFAULT_TASK_FRAGMENTS_FOLDER
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// The expected type of a front matter field in a document schema
+pub enum FieldType {
+    /// Any value
+    Any,
+    /// A string
+    String,
+    /// A number, either an integer or a decimal
+    Number,
+    /// An integer
+    Integer,
+    /// A boolean
+    Boolean,
+    /// A list of values
+    List,
+    /// An object (map) of values
+    Object,
+}
+
+impl FieldType {
+    /// Check whether a front matter value is of the field type.
+    ///
+    /// # Arguments
+    ///
+    /// * `value`: The value to check
+    ///
+    /// returns: bool
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            FieldType::Any => true,
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::List => value.is_array(),
+            FieldType::Object => value.is_object(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::Any => write!(f, "any value"),
+            FieldType::String => write!(f, "a string"),
+            FieldType::Number => write!(f, "a number"),
+            FieldType::Integer => write!(f, "an integer"),
+            FieldType::Boolean => write!(f, "a boolean"),
+            FieldType::List => write!(f, "a list"),
+            FieldType::Object => write!(f, "an object"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// The style of links generated for links between the project documents
+pub enum LinkStyle {
+    /// Links are absolute TIM URLs that include the target folder root, e.g. `/view/root/folder/doc`.
+    #[default]
+    Absolute,
+    /// Links are relative to the linking document, e.g. `../folder/doc`.
+    /// Relative links keep working if the synced folder is moved in TIM.
+    Relative,
+}
+
+impl LinkStyle {
+    /// Check whether the link style is the default one.
+    ///
+    /// returns: bool
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// How the TIM settings declared in the front matter of the documents are applied
+pub enum SettingsMode {
+    /// The settings are emitted as a settings block at the start of the document markdown.
+    #[default]
+    Inline,
+    /// The settings are set with the document settings API of TIM after uploading the document,
+    /// so the document markdown has no settings block.
+    Api,
+}
+
+impl SettingsMode {
+    /// Check whether the settings mode is the default one.
+    ///
+    /// returns: bool
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+/// Settings for the post-processing transforms applied to the final document markdown
+///
+/// All transforms are disabled by default.
+pub struct PostProcessConfig {
+    /// Remove trailing whitespace from all lines.
+    /// Markdown hard line breaks (two or more trailing spaces) are kept as two spaces.
+    #[serde(default)]
+    pub trim_trailing_whitespace: bool,
+
+    /// Convert straight quotes (`"` and `'`) into curly quotes.
+    /// Code blocks, inline code, math, HTML tags, `{...}` attribute blocks and TIM paragraph attributes are not changed.
+    #[serde(default)]
+    pub smart_quotes: bool,
+}
+
+impl PostProcessConfig {
+    /// Check whether all transforms are disabled.
+    ///
+    /// returns: bool
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+/// Settings for rendering the Markdown documents
+///
+/// The settings do not apply to task files or style themes.
+pub struct MarkdownConfig {
+    /// Names of the templates in `_templates` rendered before the contents of every Markdown document
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prepend: Vec<String>,
+
+    /// Names of the templates in `_templates` rendered after the contents of every Markdown document
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub append: Vec<String>,
+
+    /// Name of the layout template in `_templates` used for documents without a `layout` in the front matter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+
+    /// How the `tim_settings` of the front matter are applied to the documents
+    #[serde(default, skip_serializing_if = "SettingsMode::is_default")]
+    pub settings_mode: SettingsMode,
+
+    /// Use the date of the last git commit that changed a document as its `modified` date
+    /// instead of the modification time of the file. Falls back to the file modification time
+    /// for files that are not committed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub modified_from_git: bool,
+
+    /// Add the `created` and `modified` dates of the documents to their TIM document settings
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dates_in_settings: bool,
+
+    /// Humanize the titles derived from the file names of documents without a `title`,
+    /// e.g. `intro-to-rust.md` gets the title `Intro To Rust`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub humanize_titles: bool,
+}
+
+impl MarkdownConfig {
+    /// Check whether all settings have their default values.
+    ///
+    /// returns: bool
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -37,7 +286,9 @@ pub struct SyncTarget {
     ///
     /// For example, if the folder is visible at
     ///
-    ///     https://tim.jyu.fi/view/kurssit/tie/kurssi
+    /// ```text
+    /// https://tim.jyu.fi/view/kurssit/tie/kurssi
+    /// ```
     ///
     /// then the folder root is `kurssit/tie/kurssi`.
     pub folder_root: String,
@@ -47,6 +298,8 @@ pub struct SyncTarget {
     /// **Do not use your personal account for this!**
     /// Currently, authentication information is stored in plain text in the config file.
     /// Instead, create a separate, new TIM account for this purpose.
+    /// Not needed if `token` is set.
+    #[serde(default)]
     pub username: String,
 
     /// The password to use when authenticating to TIM.
@@ -54,7 +307,101 @@ pub struct SyncTarget {
     /// **Do not use your personal account for this!**
     /// Currently, authentication information is stored in plain text in the config file.
     /// Instead, create a separate, new TIM account for this purpose.
+    /// Not needed if `token` is set.
+    #[serde(default)]
     pub password: String,
+
+    /// API token to use for authenticating to TIM instead of the username and password.
+    /// The token is sent as a bearer token in the `Authorization` header of every request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    /// Upload all attachments once into a shared `_files` document in the folder root
+    /// instead of uploading them separately into each document that references them.
+    ///
+    /// The shared document is created in the target folder and inherits its default rights.
+    /// Make sure that everyone who can view the synced documents can also view
+    /// the shared document, otherwise attachments will not be visible.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shared_files: bool,
+
+    /// Name of the TIM user group to make the owner of the items created by the sync.
+    /// Only newly created documents and folders are transferred to the group.
+    ///
+    /// The syncing account must have the right to transfer the ownership of the created items,
+    /// e.g. by being a member of the group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_group: Option<String>,
+
+    /// Path of a TIM document to create new documents from, e.g. `kurssit/tie/pohja`.
+    /// Only newly created documents are created from the template.
+    ///
+    /// The contents of the template are replaced by the synced contents on the first upload,
+    /// so the template mainly provides the default document settings and rights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_doc: Option<String>,
+
+    /// Values for the placeholders in `folder_root`, e.g. `year = "2025"` for `courses/mycourse/{year}`.
+    /// The values can be overridden from the command line with `--var name=value`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vars: HashMap<String, String>,
+
+    /// Timeout in seconds for a single request to TIM. Defaults to 30 seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// Timeout in seconds for connecting to TIM. Defaults to 10 seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+
+    /// Accept invalid TLS certificates of the TIM host, e.g. self-signed certificates of development instances.
+    ///
+    /// **Do not enable this for production instances!**
+    /// Anyone who can intercept the connection can read and modify the traffic, including the login credentials.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl SyncTarget {
+    /// Replace the `{name}` placeholders in `folder_root` with their values.
+    ///
+    /// # Arguments
+    ///
+    /// * `vars`: Additional placeholder values that take precedence over the values in `vars`.
+    ///
+    /// returns: Result<(), Error> Fails with the names of all placeholders that have no value.
+    pub fn resolve_placeholders(&mut self, vars: &HashMap<String, String>) -> Result<()> {
+        let re = regex!(r"\{(?P<name>[A-Za-z0-9_-]+)\}");
+        let value_of = |name: &str| vars.get(name).or_else(|| self.vars.get(name));
+
+        let missing = re
+            .captures_iter(&self.folder_root)
+            .map(|captures| captures.name("name").unwrap().as_str())
+            .filter(|name| value_of(name).is_none())
+            .unique()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No value given for placeholders in folder root '{}': {}. Set them in the `vars` of the sync target or with `--var name=value`.",
+                self.folder_root,
+                missing.join(", ")
+            ));
+        }
+
+        let folder_root = re
+            .replace_all(&self.folder_root, |captures: &Captures| {
+                value_of(&captures["name"]).unwrap().to_string()
+            })
+            .into_owned();
+        self.folder_root = folder_root;
+        Ok(())
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SyncConfig {
@@ -62,9 +409,49 @@ impl SyncConfig {
     pub fn new() -> Self {
         SyncConfig {
             targets: HashMap::new(),
+            postprocess: PostProcessConfig::default(),
+            markdown: MarkdownConfig::default(),
+            embed_source_path: false,
+            link_style: LinkStyle::default(),
+            schema: HashMap::new(),
+            strict_templating: false,
+            strict_links: false,
+            ignore: Vec::new(),
+            template_themes: Vec::new(),
+            task_fragments_folder: default_task_fragments_folder(),
         }
     }
 
+    /// Check the front matter of a document against the schema of its `type`.
+    /// Documents without a type or with a type that has no schema are not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `front_matter`: The parsed front matter of the document
+    ///
+    /// returns: Vec<String> Descriptions of the missing and mistyped fields, sorted by field name.
+    pub fn validate_front_matter(&self, front_matter: &Value) -> Vec<String> {
+        let Some(fields) = front_matter
+            .get("type")
+            .and_then(|doc_type| doc_type.as_str())
+            .and_then(|doc_type| self.schema.get(doc_type))
+        else {
+            return Vec::new();
+        };
+
+        fields
+            .iter()
+            .sorted_by_key(|(name, _)| *name)
+            .filter_map(|(name, field_type)| match front_matter.get(name) {
+                None | Some(Value::Null) => Some(format!("missing field `{}`", name)),
+                Some(value) if !field_type.matches(value) => {
+                    Some(format!("field `{}` must be {}", name, field_type))
+                }
+                Some(_) => None,
+            })
+            .collect()
+    }
+
     /// Get a sync target by name.
     ///
     /// # Arguments
@@ -76,6 +463,90 @@ pub fn get_target(&self, name: &str) -> Option<&SyncTarget> {
         self.targets.get(name)
     }
 
+    /// Get a sync target by name, failing if the target is not configured.
+    /// If a configured target has a similar name, e.g. because of a typo, it is suggested in the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Sync target name
+    ///
+    /// returns: Result<&SyncTarget, Error>
+    pub fn require_target(&self, name: &str) -> Result<&SyncTarget> {
+        self.targets
+            .get(name)
+            .ok_or_else(|| self.target_not_found_error(name))
+    }
+
+    /// Get a mutable reference to a sync target by name, failing if the target is not configured.
+    /// If a configured target has a similar name, e.g. because of a typo, it is suggested in the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Sync target name
+    ///
+    /// returns: Result<&mut SyncTarget, Error>
+    pub fn require_target_mut(&mut self, name: &str) -> Result<&mut SyncTarget> {
+        if !self.targets.contains_key(name) {
+            return Err(self.target_not_found_error(name));
+        }
+        Ok(self.targets.get_mut(name).unwrap())
+    }
+
+    /// Create the error for a missing sync target, suggesting the closest configured target name.
+    fn target_not_found_error(&self, name: &str) -> anyhow::Error {
+        match self.closest_target_name(name) {
+            Some(closest) => anyhow::anyhow!(
+                "Could not find sync target {}. Did you mean {}?",
+                name,
+                closest
+            ),
+            None => anyhow::anyhow!(
+                "Could not find sync target {}. Use `timsync target add` to add the target.",
+                name
+            ),
+        }
+    }
+
+    /// Find the configured sync target whose name is closest to the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Sync target name, e.g. a misspelled one
+    ///
+    /// returns: Option<&str> The closest target name, or `None` if no target name is close enough.
+    pub fn closest_target_name(&self, name: &str) -> Option<&str> {
+        self.target_names()
+            .into_iter()
+            .map(|target| (edit_distance(name, target), target))
+            .filter(|(distance, _)| *distance <= MAX_TARGET_SUGGESTION_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, target)| target)
+    }
+
+    /// Get the names of all configured sync targets in alphabetical order.
+    ///
+    /// returns: Vec<&str>
+    pub fn target_names(&self) -> Vec<&str> {
+        let mut names = self
+            .targets
+            .keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Get a mutable reference to a sync target by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Sync target name
+    ///
+    /// returns: Option<&mut SyncTarget>
+    pub fn get_target_mut(&mut self, name: &str) -> Option<&mut SyncTarget> {
+        self.targets.get_mut(name)
+    }
+
     /// Set a sync target by name.
     ///
     /// # Arguments
@@ -86,18 +557,161 @@ pub fn set_target(&mut self, name: &str, target: SyncTarget) {
         self.targets.insert(name.to_string(), target);
     }
 
-    /// Read a SyncConfig from a TOML file.
-    /// The read might fail if it is not a valid TIMSync config file in TOML format.
+    /// Read a SyncConfig from a single TOML file without applying any overrides.
+    /// Used to modify the base config file without copying the overrides into it.
     ///
     /// # Arguments
     ///
-    /// * `path`: Path to the file to read.
+    /// * `path`: Path to the config file.
     ///
     /// returns: Result<SyncConfig, Error>
     pub fn read_file(path: &Path) -> Result<Self> {
+        Self::from_json(Self::read_toml_as_json(path)?, path)
+    }
+
+    /// Read a SyncConfig from a TOML file and apply any environment-specific overrides.
+    ///
+    /// The overrides are read from files named `config.<env>.toml` next to the base config file
+    /// and deep-merged over the base config. Targets in an override file replace or extend
+    /// the base targets by name.
+    ///
+    /// The files are applied in the following order, with later files taking precedence:
+    ///
+    /// 1. `config.toml` (base config)
+    /// 2. `config.<env>.toml` if `env` is given
+    /// 3. `config.local.toml` if it exists
+    ///
+    /// Override files usually contain machine-specific settings such as credentials,
+    /// so they should not be committed to version control.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path to the base config file.
+    /// * `env`: Optional name of the environment whose override file must be applied.
+    ///
+    /// returns: Result<SyncConfig, Error>
+    pub fn read_file_with_overrides(path: &Path, env: Option<&str>) -> Result<Self> {
+        let mut config = Self::read_toml_as_json(path)?;
+
+        if let Some(env) = env {
+            let env_path = Self::override_file_path(path, env);
+            if !env_path.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Config override file for environment '{}' does not exist: {}",
+                    env,
+                    env_path.display()
+                ));
+            }
+            config.merge(&Self::read_toml_as_json(&env_path)?);
+        }
+
+        let local_path = Self::override_file_path(path, LOCAL_CONFIG_ENV);
+        if env != Some(LOCAL_CONFIG_ENV) && local_path.is_file() {
+            config.merge(&Self::read_toml_as_json(&local_path)?);
+        }
+
+        Self::from_json(config, path)
+    }
+
+    /// Convert a config read with `read_toml_as_json()` into a SyncConfig.
+    ///
+    /// The sync targets are checked before the conversion,
+    /// so that a missing or empty required field is reported with the name of the target
+    /// instead of the generic deserialization error.
+    ///
+    /// # Arguments
+    ///
+    /// * `config`: The config as a JSON value.
+    /// * `path`: Path to the base config file, used in error messages.
+    ///
+    /// returns: Result<SyncConfig, Error>
+    fn from_json(config: Value, path: &Path) -> Result<Self> {
+        Self::check_targets(&config, path)?;
+        serde_json::from_value(config)
+            .with_context(|| format!("Could not parse TIMSync config file {}", path.display()))
+    }
+
+    /// Check that each sync target in the config sets the required fields to non-empty strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `config`: The config as a JSON value.
+    /// * `path`: Path to the base config file, used in error messages.
+    ///
+    /// returns: Result<(), Error>
+    fn check_targets(config: &Value, path: &Path) -> Result<()> {
+        let targets = match config.get("targets") {
+            Some(Value::Object(targets)) => targets,
+            Some(_) => {
+                return Err(anyhow::anyhow!(
+                    "Invalid TIMSync config file {}: `targets` must be a table of sync targets, e.g. `[targets.default]`",
+                    path.display()
+                ))
+            }
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Invalid TIMSync config file {}: the `[targets]` table is missing. Add an empty `[targets]` table or a sync target with `timsync target add`.",
+                    path.display()
+                ))
+            }
+        };
+
+        for (name, target) in targets.iter().sorted_by_key(|(name, _)| *name) {
+            let Value::Object(target) = target else {
+                return Err(anyhow::anyhow!(
+                    "Invalid TIMSync config file {}: sync target '{}' must be a table, e.g. `[targets.{}]`",
+                    path.display(),
+                    name,
+                    name
+                ));
+            };
+            for (field, example) in REQUIRED_TARGET_FIELDS {
+                let problem = match target.get(field) {
+                    None => "is missing the required field",
+                    Some(Value::String(value)) if value.trim().is_empty() => {
+                        "has an empty value for the field"
+                    }
+                    Some(Value::String(_)) => continue,
+                    Some(_) => "must have a string value for the field",
+                };
+                return Err(anyhow::anyhow!(
+                    "Invalid TIMSync config file {}: sync target '{}' {} `{}`, e.g. `{} = \"{}\"`",
+                    path.display(),
+                    name,
+                    problem,
+                    field,
+                    field,
+                    example
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the path of the config override file for an environment.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path to the base config file.
+    /// * `env`: Name of the environment.
+    ///
+    /// returns: PathBuf
+    fn override_file_path(path: &Path, env: &str) -> PathBuf {
+        path.with_file_name(format!("config.{}.toml", env))
+    }
+
+    /// Read a TOML file into a JSON value so that it can be merged with other config files.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path to the file to read.
+    ///
+    /// returns: Result<Value, Error>
+    fn read_toml_as_json(path: &Path) -> Result<Value> {
         let toml_str = std::fs::read_to_string(path)
             .with_context(|| format!("Could not open file {} for reading", path.display()))?;
-        let res: Self = toml::from_str(&toml_str)
+        let res: Value = toml::from_str(&toml_str)
             .with_context(|| format!("Could not parse TIMSync config file {}", path.display()))?;
         Ok(res)
     }
@@ -118,3 +732,214 @@ pub fn write_file(&self, path: &Path) -> Result<()> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn target(folder_root: &str, vars: Value) -> SyncTarget {
+        serde_json::from_value(json!({
+            "host": DEFAULT_SYNC_TARGET_HOST,
+            "folder_root": folder_root,
+            "username": "",
+            "password": "",
+            "vars": vars,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_placeholders_uses_target_vars() {
+        let mut target = target(
+            "courses/{course}/{year}",
+            json!({"course": "ohj1", "year": "2024"}),
+        );
+        target.resolve_placeholders(&HashMap::new()).unwrap();
+        assert_eq!(target.folder_root, "courses/ohj1/2024");
+    }
+
+    #[test]
+    fn resolve_placeholders_prefers_given_vars() {
+        let mut target = target("courses/ohj1/{year}", json!({"year": "2024"}));
+        let vars = HashMap::from([("year".to_string(), "2025".to_string())]);
+        target.resolve_placeholders(&vars).unwrap();
+        assert_eq!(target.folder_root, "courses/ohj1/2025");
+    }
+
+    #[test]
+    fn resolve_placeholders_lists_missing_names() {
+        let mut target = target("courses/{course}/{year}/{course}", json!({}));
+        let error = target
+            .resolve_placeholders(&HashMap::new())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("course, year"), "{}", error);
+        assert_eq!(target.folder_root, "courses/{course}/{year}/{course}");
+    }
+
+    #[test]
+    fn resolve_placeholders_keeps_plain_folder_root() {
+        let mut target = target("courses/ohj1", json!({}));
+        target.resolve_placeholders(&HashMap::new()).unwrap();
+        assert_eq!(target.folder_root, "courses/ohj1");
+    }
+
+    fn config_with_targets(names: &[&str]) -> SyncConfig {
+        let mut config = SyncConfig::new();
+        for name in names {
+            config.set_target(name, target("courses/ohj1", json!({})));
+        }
+        config
+    }
+
+    #[test]
+    fn require_target_suggests_target_with_one_character_typo() {
+        let config = config_with_targets(&["default", "production", "staging"]);
+        let error = config.require_target("prodution").unwrap_err().to_string();
+        assert!(error.contains("Did you mean production?"), "{}", error);
+    }
+
+    #[test]
+    fn require_target_does_not_suggest_distant_names() {
+        let config = config_with_targets(&["default", "production"]);
+        assert_eq!(config.closest_target_name("local"), None);
+        let error = config.require_target("local").unwrap_err().to_string();
+        assert!(!error.contains("Did you mean"), "{}", error);
+    }
+
+    #[test]
+    fn closest_target_name_prefers_the_smallest_distance() {
+        let config = config_with_targets(&["test", "tests", "prod"]);
+        assert_eq!(config.closest_target_name("tests"), Some("tests"));
+        assert_eq!(config.closest_target_name("tst"), Some("test"));
+    }
+
+    #[test]
+    fn require_target_finds_existing_target() {
+        let config = config_with_targets(&["default"]);
+        assert!(config.require_target("default").is_ok());
+    }
+
+    fn check_targets_error(config: Value) -> String {
+        SyncConfig::check_targets(&config, Path::new("config.toml"))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn check_targets_accepts_complete_targets() {
+        let config = json!({
+            "targets": {
+                "default": {"host": DEFAULT_SYNC_TARGET_HOST, "folder_root": "courses/ohj1"},
+            }
+        });
+        assert!(SyncConfig::check_targets(&config, Path::new("config.toml")).is_ok());
+        assert!(
+            SyncConfig::check_targets(&json!({"targets": {}}), Path::new("config.toml")).is_ok()
+        );
+    }
+
+    #[test]
+    fn check_targets_names_target_missing_host() {
+        let error = check_targets_error(json!({
+            "targets": {"prod": {"folder_root": "courses/ohj1"}}
+        }));
+        assert!(
+            error.contains("sync target 'prod' is missing the required field `host`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn check_targets_names_target_missing_folder_root() {
+        let error = check_targets_error(json!({
+            "targets": {"prod": {"host": DEFAULT_SYNC_TARGET_HOST}}
+        }));
+        assert!(
+            error.contains("sync target 'prod' is missing the required field `folder_root`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn check_targets_rejects_empty_and_non_string_fields() {
+        let error = check_targets_error(json!({
+            "targets": {"prod": {"host": DEFAULT_SYNC_TARGET_HOST, "folder_root": "  "}}
+        }));
+        assert!(
+            error.contains("has an empty value for the field `folder_root`"),
+            "{}",
+            error
+        );
+
+        let error = check_targets_error(json!({
+            "targets": {"prod": {"host": 5, "folder_root": "courses/ohj1"}}
+        }));
+        assert!(
+            error.contains("must have a string value for the field `host`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn check_targets_rejects_invalid_targets_table() {
+        let error = check_targets_error(json!({}));
+        assert!(
+            error.contains("the `[targets]` table is missing"),
+            "{}",
+            error
+        );
+
+        let error = check_targets_error(json!({"targets": 1}));
+        assert!(error.contains("`targets` must be a table"), "{}", error);
+
+        let error = check_targets_error(json!({"targets": {"prod": "x"}}));
+        assert!(
+            error.contains("sync target 'prod' must be a table"),
+            "{}",
+            error
+        );
+    }
+
+    fn config_with_assignment_schema() -> SyncConfig {
+        let mut config = SyncConfig::new();
+        config.schema = serde_json::from_value(json!({
+            "assignment": {"deadline": "string", "points": "number"}
+        }))
+        .unwrap();
+        config
+    }
+
+    #[test]
+    fn validate_front_matter_accepts_matching_document() {
+        let config = config_with_assignment_schema();
+        let front_matter = json!({"type": "assignment", "deadline": "2024-10-01", "points": 5});
+        assert!(config.validate_front_matter(&front_matter).is_empty());
+    }
+
+    #[test]
+    fn validate_front_matter_lists_missing_and_mistyped_fields() {
+        let config = config_with_assignment_schema();
+        let front_matter = json!({"type": "assignment", "points": "five"});
+        assert_eq!(
+            config.validate_front_matter(&front_matter),
+            vec![
+                "missing field `deadline`",
+                "field `points` must be a number"
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_front_matter_skips_documents_without_schema() {
+        let config = config_with_assignment_schema();
+        assert!(config.validate_front_matter(&json!({})).is_empty());
+        assert!(config
+            .validate_front_matter(&json!({"type": "lecture"}))
+            .is_empty());
+    }
+}
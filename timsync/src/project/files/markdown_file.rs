@@ -4,7 +4,7 @@
 use lazy_init::Lazy;
 
 use crate::processing::processors::FileProcessorType;
-use crate::project::files::project_files::ProjectFileAPI;
+use crate::project::files::project_files::{FrontMatterFormat, ProjectFileAPI};
 use crate::project::files::util::{get_or_read_file_contents, get_or_set_front_matter_position};
 
 /// A basic markdown file.
@@ -21,7 +21,23 @@ fn path(&self) -> &PathBuf {
     }
 
     fn front_matter_pos(&self) -> Option<(usize, usize)> {
-        get_or_set_front_matter_position(&self.contents, &self.front_matter_position, "---", "---")
+        get_or_set_front_matter_position(
+            &self.contents,
+            &self.front_matter_position,
+            &[("---", "---"), ("+++", "+++")],
+        )
+    }
+
+    fn front_matter_format(&self) -> FrontMatterFormat {
+        let is_toml = match (self.front_matter_pos(), self.contents()) {
+            (Some((start, _)), Ok(contents)) => contents[start..].starts_with("+++"),
+            _ => false,
+        };
+        if is_toml {
+            FrontMatterFormat::Toml
+        } else {
+            FrontMatterFormat::Yaml
+        }
     }
 
     fn contents(&self) -> Result<&str> {
@@ -2,6 +2,7 @@
 
 use anyhow::{Context, Result};
 use enum_dispatch::enum_dispatch;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::{Map, Value};
 
@@ -45,6 +46,15 @@ fn try_from(path: PathBuf) -> std::result::Result<Self, Self::Error> {
     }
 }
 
+/// The format in which the front matter of a project file is written.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FrontMatterFormat {
+    /// YAML front matter, e.g. delimited by `---`.
+    Yaml,
+    /// TOML front matter delimited by `+++`.
+    Toml,
+}
+
 #[enum_dispatch]
 /// Public API for the project files.
 pub trait ProjectFileAPI {
@@ -52,6 +62,11 @@ pub trait ProjectFileAPI {
     fn path(&self) -> &PathBuf;
     /// Get the position of the front matter in the project file.
     fn front_matter_pos(&self) -> Option<(usize, usize)>;
+    /// Get the format of the front matter in the project file.
+    fn front_matter_format(&self) -> FrontMatterFormat {
+        FrontMatterFormat::Yaml
+    }
+
     /// Get the contents of the project file.
     fn contents(&self) -> Result<&str>;
     /// Get the processor type to use for the project file.
@@ -77,6 +92,12 @@ pub struct GeneralProjectFileMetadata {
     #[allow(dead_code)]
     pub processor: Option<String>,
     pub uid: Option<String>,
+    /// Alternative paths of the document relative to the sync target folder root.
+    /// The paths are registered as TIM aliases of the document.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Language ID of the document in TIM, e.g. `fi` or `en`.
+    pub lang: Option<String>,
 }
 
 impl ProjectFile {
@@ -85,19 +106,51 @@ pub fn read_general_metadata(&self) -> Result<GeneralProjectFileMetadata> {
             return Ok(GeneralProjectFileMetadata {
                 processor: None,
                 uid: None,
+                aliases: Vec::new(),
+                lang: None,
             });
         };
-        let settings: GeneralProjectFileMetadata = serde_yaml::from_str(front_matter)
+        let settings: GeneralProjectFileMetadata = self
+            .parse_front_matter_str(front_matter)
             .with_context(|| {
                 format!(
                     "Could not parse front matter of file: {}",
                     self.path().display()
                 )
-            })
-            .unwrap();
+            })?;
         Ok(settings)
     }
 
+    /// Parse the front matter of the project file into the given type.
+    /// The front matter is parsed as YAML or TOML depending on the detected front matter format.
+    /// Missing or empty front matter is parsed as an empty object.
+    ///
+    /// Returns: Result<T>
+    pub fn parse_front_matter<T: DeserializeOwned>(&self) -> Result<T> {
+        let front_matter = self.front_matter().with_context(|| {
+            format!(
+                "Could not read front matter of file: {}",
+                self.path().display()
+            )
+        })?;
+        self.parse_front_matter_str(front_matter).with_context(|| {
+            format!(
+                "Could not parse front matter of file: {}",
+                self.path().display()
+            )
+        })
+    }
+
+    fn parse_front_matter_str<T: DeserializeOwned>(&self, front_matter: &str) -> Result<T> {
+        if front_matter.trim().is_empty() {
+            return Ok(serde_json::from_value(Value::Object(Map::new()))?);
+        }
+        match self.front_matter_format() {
+            FrontMatterFormat::Yaml => Ok(serde_yaml::from_str(front_matter)?),
+            FrontMatterFormat::Toml => Ok(toml::from_str(front_matter)?),
+        }
+    }
+
     /// Get the front matter of the project file.
     ///
     /// Returns: Result<&str>
@@ -122,22 +175,7 @@ pub fn front_matter(&self) -> Result<&str> {
     ///
     /// Returns: Result<Value>
     pub fn front_matter_json(&self) -> Result<Value> {
-        let front_matter = self.front_matter().with_context(|| {
-            format!(
-                "Could not read front matter of file: {}",
-                self.path().display()
-            )
-        })?;
-        if front_matter.is_empty() {
-            return Ok(Value::Object(Map::new()));
-        }
-        let front_matter = serde_yaml::from_str(&front_matter).with_context(|| {
-            format!(
-                "Could not parse front matter of file: {}",
-                self.path().display()
-            )
-        })?;
-        Ok(front_matter)
+        self.parse_front_matter()
     }
 
     /// Get the contents of the project file without the front matter.
@@ -146,3 +184,103 @@ pub fn contents_without_front_matter(&self) -> Result<&str> {
         api.contents_without_front_matter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Write a project file into a temporary directory and open it.
+    fn project_file(dir: &TempDir, name: &str, contents: &str) -> ProjectFile {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).unwrap();
+        ProjectFile::try_from(path).unwrap()
+    }
+
+    #[test]
+    fn read_general_metadata_parses_front_matter() {
+        let dir = TempDir::new().unwrap();
+        let file = project_file(
+            &dir,
+            "page.md",
+            "---\nuid: intro\naliases: [start]\nlang: fi\n---\n# Intro\n",
+        );
+        let metadata = file.read_general_metadata().unwrap();
+        assert_eq!(metadata.uid.as_deref(), Some("intro"));
+        assert_eq!(metadata.aliases, vec!["start"]);
+        assert_eq!(metadata.lang.as_deref(), Some("fi"));
+    }
+
+    #[test]
+    fn read_general_metadata_without_front_matter() {
+        let dir = TempDir::new().unwrap();
+        let file = project_file(&dir, "page.md", "# Intro\n");
+        let metadata = file.read_general_metadata().unwrap();
+        assert!(metadata.uid.is_none());
+        assert!(metadata.aliases.is_empty());
+    }
+
+    #[test]
+    fn read_general_metadata_fails_on_invalid_yaml() {
+        let dir = TempDir::new().unwrap();
+        let file = project_file(
+            &dir,
+            "broken.md",
+            "---\nuid: [intro\ntitle: x\n---\n# Intro\n",
+        );
+        let error = file.read_general_metadata().unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(
+            message.contains("Could not parse front matter of file")
+                && message.contains("broken.md"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn toml_and_yaml_front_matter_give_the_same_json() {
+        let dir = TempDir::new().unwrap();
+        let yaml = project_file(
+            &dir,
+            "yaml.md",
+            "---\ntitle: Intro\nweight: 2\ntags: [a, b]\nauthor:\n  name: Ann\n---\n# Intro\n",
+        );
+        let toml = project_file(
+            &dir,
+            "toml.md",
+            "+++\ntitle = \"Intro\"\nweight = 2\ntags = [\"a\", \"b\"]\n\n[author]\nname = \"Ann\"\n+++\n# Intro\n",
+        );
+        assert_eq!(
+            toml.front_matter_json().unwrap(),
+            yaml.front_matter_json().unwrap()
+        );
+        assert_eq!(
+            toml.contents_without_front_matter().unwrap(),
+            yaml.contents_without_front_matter().unwrap()
+        );
+    }
+
+    #[test]
+    fn dashes_inside_front_matter_values_do_not_end_it() {
+        let dir = TempDir::new().unwrap();
+        let file = project_file(
+            &dir,
+            "page.md",
+            "---\nquoted: \"---\"\nblock: |\n  Text\n  ----\n  --- not the end\ntitle: Page\n---\n# Page\n",
+        );
+        let json = file.front_matter_json().unwrap();
+        assert_eq!(json["quoted"], "---");
+        assert_eq!(json["block"], "Text\n----\n--- not the end\n");
+        assert_eq!(json["title"], "Page");
+        assert_eq!(file.contents_without_front_matter().unwrap(), "\n# Page\n");
+    }
+
+    #[test]
+    fn front_matter_end_delimiter_allows_trailing_whitespace() {
+        let dir = TempDir::new().unwrap();
+        let file = project_file(&dir, "page.md", "---\ntitle: Page\n---  \n# Page\n");
+        assert_eq!(file.front_matter_json().unwrap()["title"], "Page");
+    }
+}
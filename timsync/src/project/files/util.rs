@@ -32,19 +32,23 @@ pub fn get_or_read_file_contents<'a>(
 ///
 /// * `contents` - The contents of the file to search in.
 /// * `lazy` - The lazy value to store the front matter position in.
+/// * `delimiters` - Pairs of start and end delimiters to try in order.
 ///
 /// Returns: Option<(usize, usize)>
 pub fn get_or_set_front_matter_position<'a>(
     contents: &'a Lazy<Result<String>>,
     lazy: &'a Lazy<Option<(usize, usize)>>,
-    start_delimiter: &str,
-    end_delimiter: &str,
+    delimiters: &[(&str, &str)],
 ) -> Option<(usize, usize)> {
     let res = lazy.get_or_create(|| {
         let Some(Ok(contents)) = contents.get() else {
             return None;
         };
-        find_front_matter_simple(contents, start_delimiter, end_delimiter)
+        delimiters
+            .iter()
+            .find_map(|(start_delimiter, end_delimiter)| {
+                find_front_matter_simple(contents, start_delimiter, end_delimiter)
+            })
     });
 
     res.clone()
@@ -54,12 +58,16 @@ pub fn get_or_set_front_matter_position<'a>(
 ///
 /// This is a basic naive implementation that looks for any string of format
 ///
-/// ```
+/// ```text
 /// start_delimiter
 /// .*
 /// end_delimiter
 /// ```
 ///
+/// The delimiters must be on their own lines starting from the first column.
+/// Trailing whitespace after a delimiter is allowed, so lines such as `----` or `--- foo`
+/// inside the front matter values do not end the front matter.
+///
 /// # Arguments
 ///
 /// * `contents` - The contents of the file to search in.
@@ -82,7 +90,7 @@ pub fn find_front_matter_simple(
             if trimmed.is_empty() {
                 start += l.len();
                 continue;
-            } else if trimmed.starts_with(start_delimiter) {
+            } else if trimmed == start_delimiter {
                 found_start = true;
                 end = start + l.len();
                 continue;
@@ -91,7 +99,7 @@ pub fn find_front_matter_simple(
             }
         } else {
             let trimmed = l.trim_end();
-            if trimmed.starts_with(end_delimiter) {
+            if trimmed == end_delimiter {
                 // Here, we add just the length of the end delimiter to the end position
                 // to ensure that the last newline is not included as it is not part of the front matter
                 return Some((start, end + trimmed.len()));
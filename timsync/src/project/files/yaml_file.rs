@@ -40,7 +40,11 @@ fn path(&self) -> &PathBuf {
     }
 
     fn front_matter_pos(&self) -> Option<(usize, usize)> {
-        get_or_set_front_matter_position(&self.contents, &self.front_matter_position, "---", "---")
+        get_or_set_front_matter_position(
+            &self.contents,
+            &self.front_matter_position,
+            &[("---", "---")],
+        )
     }
 
     fn contents(&self) -> anyhow::Result<&str> {
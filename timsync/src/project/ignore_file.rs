@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
 /// Filename of the ignore file
@@ -79,6 +79,23 @@ pub fn add_ignore_patterns(&mut self, ignore_file_path: &PathBuf) -> Result<()>
         Ok(())
     }
 
+    /// Add ignore patterns relative to a base directory, e.g. the patterns set in the config.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_path`: The directory the patterns are relative to
+    /// * `patterns`: The glob patterns to add
+    ///
+    /// Returns: Result<(), Error>
+    pub fn add_patterns(&mut self, base_path: &Path, patterns: &[String]) -> Result<()> {
+        for pattern in patterns {
+            let pattern = glob::Pattern::new(base_path.join(pattern).to_string_lossy().as_ref())
+                .with_context(|| format!("Invalid ignore pattern: {}", pattern))?;
+            self.ignore_patterns.push(pattern);
+        }
+        Ok(())
+    }
+
     /// Check if a path is ignored by the ignore file.
     ///
     /// # Arguments
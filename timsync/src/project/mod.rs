@@ -1,3 +1,4 @@
+pub mod cache;
 pub mod config;
 pub mod files;
 pub mod global_ctx;
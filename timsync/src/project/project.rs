@@ -3,6 +3,7 @@
 use anyhow::{Context, Result};
 use simplelog::warn;
 
+use crate::project::cache::ProjectCache;
 use crate::project::config::{SyncConfig, CONFIG_FILE_NAME, CONFIG_FOLDER};
 use crate::project::global_ctx::{GlobalContext};
 use crate::project::ignore_file::IgnoreFile;
@@ -26,6 +27,11 @@ pub fn get_root_path(&self) -> &Path {
         &self.root_path
     }
 
+    /// Get the path of the base config file of the project (`.timsync/config.toml`)
+    pub fn config_file_path(&self) -> PathBuf {
+        self.root_path.join(CONFIG_FOLDER).join(CONFIG_FILE_NAME)
+    }
+
     /// Get the global context prefilled with data defined in the global data config file (`_config.yml`).
     ///
     /// returns: Result<GlobalContext, Error>
@@ -33,12 +39,25 @@ pub fn global_context(&self) -> Result<GlobalContext> {
         GlobalContext::for_project(&self.root_path)
     }
 
+    /// Get the cache folder of the project (`.timsync/cache`).
+    ///
+    /// returns: ProjectCache
+    pub fn cache(&self) -> ProjectCache {
+        ProjectCache::for_project(&self.root_path)
+    }
+
     /// Get the ignore file for the project.
     /// The ignore file contains patterns to exclude files from the project.
+    /// The `ignore` patterns of the config are added to the patterns of the ignore file.
     ///
     /// returns: Result<IgnoreFile, Error>
     pub fn ignore_file(&self) -> Result<IgnoreFile> {
-        IgnoreFile::for_project(&self.root_path).context("Could not read the ignore file")
+        let mut ignore_file =
+            IgnoreFile::for_project(&self.root_path).context("Could not read the ignore file")?;
+        ignore_file
+            .add_patterns(&self.root_path, &self.config.ignore)
+            .context("Could not read the ignore patterns of the config")?;
+        Ok(ignore_file)
     }
 
     /// Find files in the project directory and its subdirectories.
@@ -80,12 +99,16 @@ pub fn find_files(&self, dir: impl AsRef<Path>, glob: &str) -> Result<Vec<(Strin
     /// the parent folders are also checked up to 10 levels.
     ///
     ///
+    /// Any config overrides (`config.local.toml` and `config.<env>.toml`) are merged
+    /// over the base config, see `SyncConfig::read_file_with_overrides`.
+    ///
     /// # Arguments
     ///
     /// * `dir_path`: Directory to search the project from.
+    /// * `env`: Optional name of the config environment to apply.
     ///
     /// returns: Result<Project, Error>
-    pub fn resolve_from_directory(dir_path: &Path) -> Result<Self> {
+    pub fn resolve_from_directory(dir_path: &Path, env: Option<&str>) -> Result<Self> {
         if !dir_path.is_dir() {
             return Err(anyhow::anyhow!(
                 "The given path is not a directory or does not exist: {}",
@@ -97,7 +120,7 @@ pub fn resolve_from_directory(dir_path: &Path) -> Result<Self> {
         for parent in dir_path.ancestors().take(MAX_SEARCH_DEPTH) {
             let config_file = parent.join(CONFIG_FOLDER).join(CONFIG_FILE_NAME);
             if config_file.exists() {
-                let result = SyncConfig::read_file(&config_file);
+                let result = SyncConfig::read_file_with_overrides(&config_file, env);
                 match result {
                     Ok(config) => {
                         return Ok(Project {
@@ -122,3 +145,29 @@ pub fn resolve_from_directory(dir_path: &Path) -> Result<Self> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::project::ignore_file::SYNC_IGNORE_FILE_NAME;
+    use crate::util::test_project;
+
+    #[test]
+    fn config_and_ignore_file_patterns_both_apply() {
+        let (dir, project) = test_project::project(
+            "ignore = [\"drafts/**\"]\n[targets]\n",
+            &[(SYNC_IGNORE_FILE_NAME, "# Temporary files\n*.tmp\n")],
+        );
+        let ignore_file = project.ignore_file().unwrap();
+        let root = dir.path();
+
+        assert!(ignore_file.is_ignored(root.join("drafts/week1/intro.md")));
+        assert!(ignore_file.is_ignored(root.join("notes.tmp")));
+        assert!(!ignore_file.is_ignored(root.join("week1/intro.md")));
+    }
+
+    #[test]
+    fn invalid_config_ignore_pattern_is_an_error() {
+        let (_dir, project) = test_project::project("ignore = [\"drafts/[\"]\n[targets]\n", &[]);
+        assert!(project.ignore_file().is_err());
+    }
+}
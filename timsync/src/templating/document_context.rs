@@ -0,0 +1,137 @@
+use handlebars::Context;
+use serde_json::{Map, Value};
+
+use crate::project::global_ctx::GlobalContext;
+use crate::templating::ext_context::ContextExtension;
+
+/// Per-document information that is exposed to the templates.
+/// The information overrides any values with the same name in the front matter.
+pub struct DocumentContextInfo<'a> {
+    /// Title of the TIM document. If `None`, the title from the front matter is kept.
+    pub title: Option<&'a str>,
+    /// Path of the TIM document relative to the sync target folder root.
+    pub path: &'a str,
+    /// ID of the TIM document, if known.
+    pub doc_id: Option<u64>,
+    /// Path of the currently rendered file relative to the project root.
+    /// Used by the helpers to resolve relative file paths.
+    pub local_file_path: Option<String>,
+    /// SHA1 hash of the source file (front matter and body), if the document has a single source file.
+    pub source_hash: Option<String>,
+    /// Last modification date of the document, if known.
+    pub modified: Option<&'a str>,
+}
+
+/// Build the Handlebars context for rendering a single project file.
+///
+/// The context contains, in order of increasing precedence:
+///
+/// * the global data as the `site` variable,
+/// * the front matter of the rendered file,
+/// * the document information (`title`, `path`, `doc_id`, `local_file_path`, `modified`
+///   and `doc.source_hash`).
+///
+/// # Arguments
+///
+/// * `global`: The global context shared across all documents.
+/// * `front_matter`: The parsed front matter of the rendered file.
+/// * `info`: The document information.
+///
+/// returns: Context
+pub fn build_document_context(
+    global: &GlobalContext,
+    front_matter: &Value,
+    info: &DocumentContextInfo,
+) -> Context {
+    let mut ctx = global.handlebars_context();
+    ctx.extend_with_json(front_matter);
+
+    let mut doc_info = Map::new();
+    if let Some(title) = info.title {
+        doc_info.insert("title".to_string(), Value::from(title));
+    }
+    doc_info.insert("path".to_string(), Value::from(info.path));
+    doc_info.insert("doc_id".to_string(), Value::from(info.doc_id.unwrap_or(0)));
+    doc_info.insert(
+        "local_file_path".to_string(),
+        Value::from(info.local_file_path.clone()),
+    );
+    if let Some(source_hash) = &info.source_hash {
+        let mut doc = Map::new();
+        doc.insert("source_hash".to_string(), Value::from(source_hash.as_str()));
+        doc_info.insert("doc".to_string(), Value::Object(doc));
+    }
+    if let Some(modified) = info.modified {
+        doc_info.insert("modified".to_string(), Value::from(modified));
+    }
+    ctx.extend_with_json(&Value::Object(doc_info));
+
+    ctx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn info() -> DocumentContextInfo<'static> {
+        DocumentContextInfo {
+            title: Some("Introduction"),
+            path: "week1/intro",
+            doc_id: Some(42),
+            local_file_path: Some("week1/intro.md".to_string()),
+            source_hash: Some("abc123".to_string()),
+            modified: Some("2024-05-01"),
+        }
+    }
+
+    #[test]
+    fn document_information_overrides_front_matter() {
+        let front_matter = json!({
+            "title": "Front matter title",
+            "path": "other",
+            "points": 5,
+        });
+        let ctx = build_document_context(&GlobalContext::new(), &front_matter, &info());
+        assert_eq!(
+            ctx.data(),
+            &json!({
+                "site": {},
+                "title": "Introduction",
+                "path": "week1/intro",
+                "points": 5,
+                "doc_id": 42,
+                "local_file_path": "week1/intro.md",
+                "doc": { "source_hash": "abc123" },
+                "modified": "2024-05-01",
+            })
+        );
+    }
+
+    #[test]
+    fn missing_document_information_keeps_front_matter() {
+        let info = DocumentContextInfo {
+            title: None,
+            doc_id: None,
+            local_file_path: None,
+            source_hash: None,
+            modified: None,
+            ..info()
+        };
+        let ctx = build_document_context(
+            &GlobalContext::new(),
+            &json!({ "title": "Front matter title" }),
+            &info,
+        );
+        assert_eq!(
+            ctx.data(),
+            &json!({
+                "site": {},
+                "title": "Front matter title",
+                "path": "week1/intro",
+                "doc_id": 0,
+                "local_file_path": null,
+            })
+        );
+    }
+}
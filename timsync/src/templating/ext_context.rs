@@ -1,6 +1,6 @@
-use serde_json::Value;
-use handlebars::Context;
 use crate::util::json::Merge;
+use handlebars::Context;
+use serde_json::Value;
 
 pub trait ContextExtension {
     /// Extend the context with a JSON value.
@@ -18,4 +18,4 @@ impl ContextExtension for Context {
     fn extend_with_json(&mut self, other: &Value) {
         self.data_mut().merge(other);
     }
-}
\ No newline at end of file
+}
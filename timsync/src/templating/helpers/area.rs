@@ -1,5 +1,5 @@
 use handlebars::{
-    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext,
+    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext, RenderError,
     RenderErrorReason, Renderable,
 };
 use nanoid::nanoid;
@@ -24,6 +24,10 @@
 /// {{else}}
 /// Collapsed contents
 /// {{/area}}
+///
+/// {{#area "attributes-example" class="hidden-print" hidefrom="preview"}}
+/// Any other hash parameters are added to the area as attributes.
+/// {{/area}}
 /// ```
 pub fn area_block<'reg, 'rc>(
     h: &Helper<'rc>,
@@ -32,21 +36,56 @@ pub fn area_block<'reg, 'rc>(
     rc: &mut RenderContext<'reg, 'rc>,
     out: &mut dyn Output,
 ) -> HelperResult {
-    let area_name = match h.param(0) {
+    let area_name = area_name_param(h, "area")?;
+    write_area(h, r, ctx, rc, out, &area_name, "")
+}
+
+/// Get the area name given as the first parameter of a helper.
+/// If no name is given, a random name is generated.
+///
+/// # Arguments
+///
+/// * `h`: The helper.
+/// * `prefix`: Prefix of the generated name, e.g. `area` for names like `area-x1y2z3w4`.
+///
+/// returns: Result<String, RenderError>
+pub(super) fn area_name_param(h: &Helper, prefix: &str) -> Result<String, RenderError> {
+    match h.param(0) {
         Some(v) => match v.value() {
-            Value::String(s) => s.clone(),
-            _ => {
-                return Err(RenderErrorReason::ParamTypeMismatchForName(
-                    "name",
-                    "0".to_string(),
-                    "string".to_string(),
-                )
-                .into())
-            }
+            Value::String(s) => Ok(s.clone()),
+            _ => Err(RenderErrorReason::ParamTypeMismatchForName(
+                "name",
+                "0".to_string(),
+                "string".to_string(),
+            )
+            .into()),
         },
-        None => format!("area-{}", nanoid!(8)),
-    };
+        None => Ok(format!("{}-{}", prefix, nanoid!(8))),
+    }
+}
 
+/// Write an area with the contents of a block helper.
+/// Shared by the helpers that generate areas.
+///
+/// The `collapse` and `class` hash parameters of the helper are handled as in the `area` helper,
+/// and any other hash parameters are added to the area as attributes.
+///
+/// # Arguments
+///
+/// * `h`: The block helper.
+/// * `area_name`: Name of the area.
+/// * `default_class`: Class of the area if the helper has no `class` hash parameter.
+///
+/// returns: HelperResult
+pub(super) fn write_area<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+    area_name: &str,
+    default_class: &str,
+) -> HelperResult {
     let collapse = h
         .hash_get("collapse")
         .map(|v| v.value().is_truthy(true))
@@ -55,13 +94,29 @@ pub fn area_block<'reg, 'rc>(
     let class = h
         .hash_get("class")
         .and_then(|v| v.value().as_str())
-        .unwrap_or("");
+        .unwrap_or(default_class);
+
+    // Any other hash parameters are passed to the area as-is
+    let extra_attributes = h
+        .hash()
+        .iter()
+        .filter(|(key, _)| !matches!(**key, "collapse" | "class"))
+        .map(|(key, value)| {
+            let value = match value.value() {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            format!("{}=\"{}\"", key, value.replace('"', "\\\""))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
 
     out.write(&format!(
-        "#- {{area=\"{}\" {} {}}}\n",
+        "#- {{area=\"{}\" {} {} {}}}\n",
         area_name,
         if collapse { "collapse=\"true\"" } else { "" },
-        class
+        class,
+        extra_attributes
     ))?;
 
     if !collapse {
@@ -81,3 +136,53 @@ pub fn area_block<'reg, 'rc>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut hb = Handlebars::new();
+        hb.register_helper("area", Box::new(area_block));
+        hb.render_template(template, &json!({})).unwrap()
+    }
+
+    #[test]
+    fn writes_named_area() {
+        assert_eq!(
+            render(r#"{{#area "intro"}}Text{{/area}}"#),
+            "#- {area=\"intro\"   }\n\n#-\nText\n#- {area_end=\"intro\"}\n\n#-\n"
+        );
+    }
+
+    #[test]
+    fn adds_extra_hash_parameters_as_attributes() {
+        let area =
+            render(r#"{{#area "intro" class="hidden-print" hidefrom="preview"}}Text{{/area}}"#);
+        let opening = area.lines().next().unwrap();
+        assert!(opening.starts_with("#- {area=\"intro\""), "{}", opening);
+        assert!(opening.contains(" hidden-print "), "{}", opening);
+        assert!(opening.contains("hidefrom=\"preview\""), "{}", opening);
+        assert!(!opening.contains("class="), "{}", opening);
+    }
+
+    #[test]
+    fn quotes_extra_attribute_values() {
+        let area = render(r#"{{#area "intro" title='Say "hi"' order=2}}Text{{/area}}"#);
+        let opening = area.lines().next().unwrap();
+        assert!(opening.contains(r#"title="Say \"hi\"""#), "{}", opening);
+        assert!(opening.contains(r#"order="2""#), "{}", opening);
+    }
+
+    #[test]
+    fn keeps_collapse_and_class() {
+        let area =
+            render(r#"{{#area "intro" collapse=true class="note"}}Title{{else}}Text{{/area}}"#);
+        assert_eq!(
+            area.lines().next().unwrap(),
+            "#- {area=\"intro\" collapse=\"true\" note }"
+        );
+        assert!(!area.contains("collapse=true"), "{}", area);
+    }
+}
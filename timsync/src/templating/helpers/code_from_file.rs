@@ -0,0 +1,266 @@
+use crate::templating::util::{get_local_project_dir, resolve_full_file_path};
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+/// Code from file helper.
+/// Embeds the contents of a source file as a fenced code block.
+/// The language of the code block is detected from the file extension.
+/// Unlike the `include` helper, the file contents are never passed through the templating engine.
+///
+/// The file path can be either relative or absolute to the project root (by using `/` as a prefix).
+///
+/// Optional hash arguments:
+///
+/// * `lang` - Override the detected language of the code block.
+/// * `lines` - Embed only the given lines of the file, e.g. `"3-10"` or `"5"`. Line numbers start from 1.
+///
+/// Example:
+///
+/// ```md
+/// {{code_from_file "examples/foo.py"}}
+///
+/// {{code_from_file "/examples/Main.java" lang="java" lines="3-10"}}
+/// ```
+pub fn code_from_file_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let file_path = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("path", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "path",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    let local_project_dir = get_local_project_dir(ctx)?;
+    let target_file_path = resolve_full_file_path(ctx, file_path, local_project_dir)?;
+
+    if !target_file_path.is_file() {
+        return Err(RenderErrorReason::Other(format!(
+            "File '{}' does not exist",
+            target_file_path.display()
+        ))
+        .into());
+    }
+
+    let file_contents = std::fs::read_to_string(&target_file_path).map_err(|e| {
+        RenderErrorReason::Other(format!(
+            "Could not read file '{}': {}",
+            target_file_path.display(),
+            e
+        ))
+    })?;
+
+    let lang = match h.hash_get("lang").and_then(|v| v.value().as_str()) {
+        Some(lang) => lang.to_string(),
+        None => target_file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(language_for_extension)
+            .unwrap_or_default(),
+    };
+
+    let code = match h.hash_get("lines").map(|v| v.value()) {
+        Some(lines) => {
+            let lines = lines
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| lines.to_string());
+            let (start, end) = parse_line_range(&lines).ok_or_else(|| {
+                RenderErrorReason::Other(format!(
+                    "Invalid line range '{}'. Use a format like \"3-10\" or \"5\".",
+                    lines
+                ))
+            })?;
+            let file_lines = file_contents.lines().collect::<Vec<_>>();
+            if end > file_lines.len() {
+                return Err(RenderErrorReason::Other(format!(
+                    "Line range '{}' is out of bounds for file '{}' with {} lines",
+                    lines,
+                    target_file_path.display(),
+                    file_lines.len()
+                ))
+                .into());
+            }
+            file_lines[start - 1..end].join("\n")
+        }
+        None => file_contents.trim_end().to_string(),
+    };
+
+    // Use a fence that is longer than any backtick sequence in the code
+    // so that the code cannot close the block early
+    let longest_backtick_run = code
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_backtick_run.max(2) + 1);
+
+    out.write(&format!("{}{}\n{}\n{}", fence, lang, code, fence))?;
+
+    Ok(())
+}
+
+/// Parse a 1-based inclusive line range in format `start-end` or `line`.
+///
+/// returns: Option<(usize, usize)>
+fn parse_line_range(range: &str) -> Option<(usize, usize)> {
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let line = range.trim().parse().ok()?;
+            (line, line)
+        }
+    };
+    if start == 0 || end < start {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// Get the code block language for a file extension.
+/// Unknown extensions are used as the language as-is.
+///
+/// returns: String
+fn language_for_extension(ext: &str) -> String {
+    let ext = ext.to_lowercase();
+    match ext.as_str() {
+        "py" => "python",
+        "rs" => "rust",
+        "cs" => "csharp",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "kt" | "kts" => "kotlin",
+        "rb" => "ruby",
+        "h" => "c",
+        "cc" | "cxx" | "hpp" | "hh" => "cpp",
+        "sh" => "bash",
+        "ps1" => "powershell",
+        "md" => "markdown",
+        "yml" => "yaml",
+        "hs" => "haskell",
+        "fs" => "fsharp",
+        "txt" => "",
+        other => other,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_project::write_file;
+    use handlebars::RenderError;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const TEN_LINES: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+
+    /// Render a template in `week1/intro.md` of a project with source files in `week1/code`.
+    fn render(template: &str) -> Result<String, RenderError> {
+        let dir = TempDir::new().unwrap();
+        write_file(&dir, "week1/code/hello.py", "print('Hello')\n");
+        write_file(&dir, "week1/code/Main.KT", "fun main() {}\n");
+        write_file(&dir, "week1/code/notes.txt", "Notes\n");
+        write_file(&dir, "week1/code/script.lua", "print(1)\n");
+        write_file(&dir, "week1/code/lines.c", TEN_LINES);
+        write_file(
+            &dir,
+            "week1/code/fence.md",
+            "Code:\n\n```rust\nfn main() {}\n```\n",
+        );
+        write_file(&dir, "week1/code/page.hbs", "{{title}} {{> header}}\n");
+        let mut hb = Handlebars::new();
+        hb.register_helper("code_from_file", Box::new(code_from_file_helper));
+        let ctx = Context::wraps(json!({
+            "site": {
+                "local_project_dir": dir.path().to_str().unwrap(),
+            },
+            "local_file_path": "week1/intro.md",
+            "title": "Intro",
+        }))
+        .unwrap();
+        hb.render_template_with_context(template, &ctx)
+    }
+
+    #[test]
+    fn language_is_detected_from_extension() {
+        assert_eq!(
+            render(r#"{{code_from_file "code/hello.py"}}"#).unwrap(),
+            "```python\nprint('Hello')\n```"
+        );
+        assert_eq!(
+            render(r#"{{code_from_file "/week1/code/Main.KT"}}"#).unwrap(),
+            "```kotlin\nfun main() {}\n```"
+        );
+        assert_eq!(
+            render(r#"{{code_from_file "code/notes.txt"}}"#).unwrap(),
+            "```\nNotes\n```"
+        );
+        assert_eq!(
+            render(r#"{{code_from_file "code/script.lua"}}"#).unwrap(),
+            "```lua\nprint(1)\n```"
+        );
+    }
+
+    #[test]
+    fn lang_overrides_detected_language() {
+        assert_eq!(
+            render(r#"{{code_from_file "code/hello.py" lang="python3"}}"#).unwrap(),
+            "```python3\nprint('Hello')\n```"
+        );
+    }
+
+    #[test]
+    fn lines_embeds_only_the_given_lines() {
+        assert_eq!(
+            render(r#"{{code_from_file "code/lines.c" lines="3-10"}}"#).unwrap(),
+            "```c\n3\n4\n5\n6\n7\n8\n9\n10\n```"
+        );
+        assert_eq!(
+            render(r#"{{code_from_file "code/lines.c" lines="5"}}"#).unwrap(),
+            "```c\n5\n```"
+        );
+    }
+
+    #[test]
+    fn out_of_range_lines_are_an_error() {
+        let err = render(r#"{{code_from_file "code/lines.c" lines="8-11"}}"#).unwrap_err();
+        assert!(err.to_string().contains("out of bounds"), "{}", err);
+    }
+
+    #[test]
+    fn zero_and_reversed_lines_are_invalid() {
+        for lines in ["0", "0-3", "5-3", "a-b"] {
+            let template = format!(r#"{{{{code_from_file "code/lines.c" lines="{}"}}}}"#, lines);
+            let err = render(&template).unwrap_err();
+            assert!(err.to_string().contains("Invalid line range"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn fence_is_longer_than_backticks_in_code() {
+        assert_eq!(
+            render(r#"{{code_from_file "code/fence.md"}}"#).unwrap(),
+            "````markdown\nCode:\n\n```rust\nfn main() {}\n```\n````"
+        );
+    }
+
+    #[test]
+    fn handlebars_syntax_in_file_is_not_rendered() {
+        assert_eq!(
+            render(r#"{{code_from_file "code/page.hbs"}}"#).unwrap(),
+            "```hbs\n{{title}} {{> header}}\n```"
+        );
+    }
+}
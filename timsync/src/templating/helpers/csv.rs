@@ -0,0 +1,232 @@
+use crate::templating::util::{get_local_project_dir, resolve_full_file_path};
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext, RenderErrorReason,
+};
+
+/// CSV helper.
+/// Renders a CSV file as a Markdown table.
+///
+/// The file path can be either relative or absolute to the project root (by using `/` as a prefix).
+/// By default, the first row of the file is used as the table header and the header cells are bolded.
+///
+/// Optional hash arguments:
+///
+/// * `delimiter` - The delimiter character of the file. Defaults to `,`.
+/// * `header` - Whether the first row of the file is a header row. Defaults to `true`.
+///   If `false`, the table is rendered with an empty header row.
+///
+/// Example:
+///
+/// ```md
+/// {{csv "data/scores.csv"}}
+///
+/// {{csv "/data/scores.tsv" delimiter="\t" header=false}}
+/// ```
+pub fn csv_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let file_path = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("path", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "path",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    let delimiter = match h.hash_get("delimiter").and_then(|v| v.value().as_str()) {
+        Some(delimiter) => {
+            let delimiter = if delimiter == "\\t" { "\t" } else { delimiter };
+            match delimiter.as_bytes() {
+                [byte] => *byte,
+                _ => {
+                    return Err(RenderErrorReason::Other(format!(
+                    "Invalid CSV delimiter '{}'. The delimiter must be a single ASCII character.",
+                    delimiter
+                ))
+                    .into())
+                }
+            }
+        }
+        None => b',',
+    };
+
+    let has_header = h
+        .hash_get("header")
+        .map(|v| v.value().is_truthy(true))
+        .unwrap_or(true);
+
+    let local_project_dir = get_local_project_dir(ctx)?;
+    let target_file_path = resolve_full_file_path(ctx, file_path, local_project_dir)?;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_path(&target_file_path)
+        .map_err(|e| {
+            RenderErrorReason::Other(format!(
+                "Could not read CSV file '{}': {}",
+                target_file_path.display(),
+                e
+            ))
+        })?;
+
+    let rows = reader
+        .records()
+        .map(|record| record.map(|r| r.iter().map(escape_cell).collect::<Vec<_>>()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            RenderErrorReason::Other(format!(
+                "Could not parse CSV file '{}': {}",
+                target_file_path.display(),
+                e
+            ))
+        })?;
+
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return Ok(());
+    }
+
+    let (header, body) = if has_header {
+        let header = rows[0]
+            .iter()
+            .map(|cell| {
+                if cell.is_empty() {
+                    String::new()
+                } else {
+                    format!("**{}**", cell)
+                }
+            })
+            .collect::<Vec<_>>();
+        (header, &rows[1..])
+    } else {
+        (Vec::new(), &rows[..])
+    };
+
+    let mut lines = Vec::with_capacity(body.len() + 2);
+    lines.push(table_row(&header, columns));
+    lines.push(table_row(&vec!["---".to_string(); columns], columns));
+    lines.extend(body.iter().map(|row| table_row(row, columns)));
+
+    out.write(&lines.join("\n"))?;
+
+    Ok(())
+}
+
+/// Escape a CSV cell so that it can be used inside a Markdown table cell.
+///
+/// returns: String
+fn escape_cell(cell: &str) -> String {
+    cell.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Format a Markdown table row, padding missing cells with empty ones.
+///
+/// returns: String
+fn table_row(cells: &[String], columns: usize) -> String {
+    let cells = (0..columns)
+        .map(|i| cells.get(i).map(|c| c.as_str()).unwrap_or(""))
+        .collect::<Vec<_>>();
+    format!("| {} |", cells.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Render a template in `docs/page.md` of a project containing the given CSV file.
+    fn render(csv_path: &str, csv_contents: &str, template: &str) -> Result<String, String> {
+        let project_dir = TempDir::new().unwrap();
+        let full_path = project_dir.path().join(csv_path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(&full_path, csv_contents).unwrap();
+
+        let mut hb = Handlebars::new();
+        hb.register_helper("csv", Box::new(csv_helper));
+        let data = json!({
+            "site": { "local_project_dir": project_dir.path().to_str().unwrap() },
+            "local_file_path": "docs/page.md",
+        });
+        hb.render_template(template, &data)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn renders_header_in_bold() {
+        let table = render(
+            "docs/scores.csv",
+            "name,points\nAlice,10\nBob,7\n",
+            r#"{{csv "scores.csv"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            table,
+            "| **name** | **points** |\n| --- | --- |\n| Alice | 10 |\n| Bob | 7 |"
+        );
+    }
+
+    #[test]
+    fn renders_quoted_fields() {
+        let table = render(
+            "data/scores.csv",
+            "name,comment\n\"Doe, Jane\",\"Said \"\"hi\"\"\"\n\"Roe\",\"a | b\"\n\"Line\",\"first\nsecond\"\n",
+            r#"{{csv "/data/scores.csv"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            table,
+            "| **name** | **comment** |\n\
+             | --- | --- |\n\
+             | Doe, Jane | Said \"hi\" |\n\
+             | Roe | a \\| b |\n\
+             | Line | first<br>second |"
+        );
+    }
+
+    #[test]
+    fn renders_headerless_file_with_delimiter() {
+        let table = render(
+            "docs/scores.tsv",
+            "Alice\t10\nBob\t7\textra\n",
+            r#"{{csv "scores.tsv" delimiter="\t" header=false}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            table,
+            "|  |  |  |\n| --- | --- | --- |\n| Alice | 10 |  |\n| Bob | 7 | extra |"
+        );
+    }
+
+    #[test]
+    fn rejects_multi_character_delimiter() {
+        let error = render(
+            "docs/scores.csv",
+            "a,b\n",
+            r#"{{csv "scores.csv" delimiter="::"}}"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("Invalid CSV delimiter '::'"), "{}", error);
+    }
+
+    #[test]
+    fn reports_missing_file() {
+        let error = render("docs/scores.csv", "a,b\n", r#"{{csv "missing.csv"}}"#).unwrap_err();
+        assert!(error.contains("Could not read CSV file"), "{}", error);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::templating::helpers::file::register_file_upload;
+use crate::templating::util::{find_matching_files, get_local_project_dir};
+use handlebars::{
+    BlockContext, Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+    RenderErrorReason, Renderable,
+};
+use serde_json::json;
+use serde_json::Value;
+
+/// Each file block helper.
+/// Renders the block once for each file matching a glob pattern and marks the files
+/// to be uploaded into the current document, like the `file` helper.
+///
+/// The pattern is relative to the current file, or to the project root if it starts with `/`.
+/// The files are listed in the order of their paths.
+///
+/// Inside the block, the following fields are available for the current file:
+///
+/// * `name` - The file name, e.g. `photo.jpg`.
+/// * `url` - The final URL of the uploaded file.
+/// * `size` - The file size in bytes.
+///
+/// The `@index`, `@first` and `@last` variables work as in the `each` helper.
+/// If no files match, the `{{else}}` block is rendered instead.
+///
+/// Example:
+///
+/// ```md
+/// {{#each_file "gallery/*.jpg"}}
+/// ![{{name}}]({{url}})
+/// {{else}}
+/// No photos yet.
+/// {{/each_file}}
+/// ```
+pub fn each_file_block<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let pattern = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("each_file", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "each_file",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    let local_project_dir = get_local_project_dir(ctx)?;
+    let files = find_matching_files(ctx, pattern, local_project_dir)?;
+
+    if files.is_empty() {
+        if let Some(inverse) = h.inverse() {
+            inverse.render(r, ctx, rc, out)?;
+        }
+        return Ok(());
+    }
+
+    let Some(template) = h.template() else {
+        return Ok(());
+    };
+
+    let len = files.len();
+    for (index, file_path) in files.into_iter().enumerate() {
+        let project_file_path = file_path
+            .strip_prefix(local_project_dir)
+            .map_err(|_| {
+                RenderErrorReason::Other(format!(
+                    "File {} matched by '{}' is outside the project",
+                    file_path.display(),
+                    pattern
+                ))
+            })?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let upload =
+            register_file_upload(&format!("/{}", project_file_path), "each_file", ctx, rc)?;
+        let size = std::fs::metadata(&upload.local_path)
+            .map(|metadata| metadata.len())
+            .map_err(|e| {
+                RenderErrorReason::Other(format!(
+                    "Could not read the size of {}: {}",
+                    upload.local_path.display(),
+                    e
+                ))
+            })?;
+        let name = upload
+            .local_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut block = BlockContext::new();
+        block.set_base_value(json!({
+            "name": name,
+            "url": upload.url,
+            "size": size,
+        }));
+        block.set_local_var("index", Value::from(index));
+        block.set_local_var("first", Value::from(index == 0));
+        block.set_local_var("last", Value::from(index == len - 1));
+        rc.push_block(block);
+        let result = template.render(r, ctx, rc, out);
+        rc.pop_block();
+        result?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templating::ext_render_with_context::RendererExtension;
+    use crate::templating::tim_handlebars::FILE_MAP_ATTRIBUTE;
+    use crate::util::test_project::write_file;
+    use tempfile::TempDir;
+
+    /// Render a template in `week1/intro.md` of a project with two photos in `week1/gallery`.
+    ///
+    /// returns: (String, Value) The rendered template and the files registered for upload.
+    fn render(template: &str) -> (String, Value) {
+        let dir = TempDir::new().unwrap();
+        write_file(&dir, "week1/gallery/b.jpg", "bb");
+        write_file(&dir, "week1/gallery/a.jpg", "a");
+        write_file(&dir, "week1/gallery/notes.txt", "notes");
+        let mut hb = Handlebars::new();
+        hb.register_helper("each_file", Box::new(each_file_block));
+        let ctx = Context::wraps(json!({
+            "site": {
+                "base_path": "kurssit/ohj1",
+                "local_project_dir": dir.path().to_str().unwrap(),
+            },
+            "path": "week1/intro",
+            "local_file_path": "week1/intro.md",
+        }))
+        .unwrap();
+        let result = hb
+            .render_template_with_context_return_new_context(template, &ctx)
+            .unwrap();
+        let files = result
+            .modified_context
+            .map(|ctx| ctx.data()[FILE_MAP_ATTRIBUTE].clone())
+            .unwrap_or(Value::Null);
+        (result.rendered, files)
+    }
+
+    #[test]
+    fn renders_block_for_each_matching_file() {
+        let (rendered, files) = render(
+            "{{#each_file \"gallery/*.jpg\"}}{{@index}} {{name}} {{size}} {{url}}\n{{/each_file}}",
+        );
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2, "{}", rendered);
+        for (line, (index, name, size)) in lines.iter().zip([(0, "a.jpg", 1), (1, "b.jpg", 2)]) {
+            let (prefix, url) = line.rsplit_once(' ').unwrap();
+            assert_eq!(prefix, format!("{} {} {}", index, name, size));
+            assert!(
+                url.starts_with("/files/kurssit/ohj1/week1/intro/") && url.ends_with(".jpg"),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(files.as_object().unwrap().len(), 2, "{}", files);
+    }
+
+    #[test]
+    fn renders_else_block_without_matches() {
+        let (rendered, _) = render(
+            "{{#each_file \"/week1/gallery/*.png\"}}{{name}}{{else}}No photos{{/each_file}}",
+        );
+        assert_eq!(rendered, "No photos");
+    }
+}
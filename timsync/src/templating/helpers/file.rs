@@ -1,11 +1,15 @@
-use crate::templating::tim_handlebars::FILE_MAP_ATTRIBUTE;
-use crate::templating::util::{get_local_project_dir, get_site_ctx_json, resolve_full_file_path};
+use crate::templating::tim_handlebars::{FILE_MAP_ATTRIBUTE, SHARED_FILES_DOC_KEY};
+use crate::templating::util::{
+    find_unique_project_file, get_local_project_dir, get_site_ctx_json, resolve_full_file_path,
+};
 use crate::util::path::generate_hashed_filename;
 use handlebars::{
-    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext, RenderError,
+    RenderErrorReason,
 };
 use serde_json::map::Map;
 use serde_json::value::Value;
+use std::path::PathBuf;
 
 /// File helper.
 /// The helper is used to convert a file path to the final URL of the file and to
@@ -15,12 +19,17 @@
 /// and upload them to the TIM server. In cases where automatic detection fails, the file helper
 /// can be used to explicitly mark the file for upload.
 ///
+/// With `glob=true`, the path is a glob pattern relative to the project root.
+/// The pattern must match exactly one file in the project.
+///
 /// Example:
 ///
 /// ```md
 /// Relative import: ![]({{file "path/to/file.ext"}})
 ///
 /// Absolute import: ![]({{file "/path/to/file.ext"}})
+///
+/// Glob import: ![]({{file "assets/**/logo.png" glob=true}})
 /// ```
 pub fn file_helper<'reg, 'rc>(
     h: &Helper<'rc>,
@@ -42,6 +51,48 @@ pub fn file_helper<'reg, 'rc>(
             )
         })?;
 
+    let glob = h
+        .hash_get("glob")
+        .map(|v| v.value().is_truthy(false))
+        .unwrap_or(false);
+    let upload = if glob {
+        let file_path = find_unique_project_file(get_local_project_dir(ctx)?, file_path)?;
+        register_file_upload(&file_path, "file", ctx, rc)?
+    } else {
+        register_file_upload(file_path, "file", ctx, rc)?
+    };
+    out.write(&upload.url)?;
+
+    Ok(())
+}
+
+/// A file marked to be uploaded with `register_file_upload()`
+pub(super) struct FileUpload {
+    /// The URL of the file in TIM
+    pub url: String,
+    /// Full path to the local file
+    pub local_path: PathBuf,
+    /// The hashed name of the file in TIM
+    pub tim_file_name: String,
+}
+
+/// Mark a file to be uploaded into the current document and get the final URL of the file.
+/// Shared by the helpers that upload files.
+///
+/// # Arguments
+///
+/// * `file_path`: Path to the file, either relative to the current file or absolute to the project root.
+/// * `helper_name`: Name of the calling helper used in error messages.
+/// * `ctx`: The current context.
+/// * `rc`: The current render context. The file is added to the file map of the context.
+///
+/// returns: Result<FileUpload, RenderError>
+pub(super) fn register_file_upload<'reg, 'rc>(
+    file_path: &str,
+    helper_name: &str,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+) -> Result<FileUpload, RenderError> {
     let site_ctx_json = get_site_ctx_json(ctx)?;
     let base_path = site_ctx_json
         .get("base_path")
@@ -51,14 +102,20 @@ pub fn file_helper<'reg, 'rc>(
     let tim_doc_path =
         ctx.data().get("path").ok_or_else(|| {
             RenderErrorReason::Other(
-                "To use the 'file' helper, the template must have 'path' attribute available in context".to_string(),
+                format!("To use the '{}' helper, the template must have 'path' attribute available in context", helper_name),
             )
         })?.as_str().ok_or_else(|| {
             RenderErrorReason::Other(
-                "To use the 'file' helper, the 'path' attribute in context must be a string".to_string(),
+                format!("To use the '{}' helper, the 'path' attribute in context must be a string", helper_name),
             )
         })?;
 
+    // If shared files are enabled, the files are uploaded to the shared files document
+    let tim_doc_path = site_ctx_json
+        .get(SHARED_FILES_DOC_KEY)
+        .and_then(|v| v.as_str())
+        .unwrap_or(tim_doc_path);
+
     let local_project_dir = get_local_project_dir(ctx)?;
     let target_file_path = resolve_full_file_path(ctx, file_path, local_project_dir)?;
     let tim_file_name = generate_hashed_filename(&target_file_path)
@@ -78,10 +135,9 @@ pub fn file_helper<'reg, 'rc>(
     }
     rc.set_context(ctx);
 
-    out.write(&format!(
-        "/files/{}/{}/{}",
-        base_path, tim_doc_path, tim_file_name
-    ))?;
-
-    Ok(())
+    Ok(FileUpload {
+        url: format!("/files/{}/{}/{}", base_path, tim_doc_path, tim_file_name),
+        local_path: target_file_path,
+        tim_file_name,
+    })
 }
@@ -0,0 +1,177 @@
+use crate::templating::helpers::file::register_file_upload;
+use crate::util::image::cached_image_dimensions;
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use serde_json::Value;
+
+/// Image helper.
+/// Uploads the image into the current document like the `file` helper
+/// and inserts the Markdown image markup for it.
+///
+/// Optional hash arguments:
+///
+/// * `alt` - Alternative text of the image.
+/// * `width` - Width of the image, e.g. `400` (pixels) or `"50%"`.
+/// * `height` - Height of the image, e.g. `300` (pixels) or `"10em"`.
+/// * `class` - Space-separated list of classes to add to the image.
+///
+/// If neither `width` nor `height` is given, the intrinsic dimensions of the image are used
+/// so that the page layout does not shift while the image loads.
+/// The dimensions are read from PNG, GIF, JPEG and SVG images; other formats get no dimensions.
+///
+/// Example:
+///
+/// ```md
+/// {{image "images/diagram.png" alt="Diagram" width=400}}
+///
+/// {{image "/images/logo.svg" width="50%" class="center"}}
+/// ```
+pub fn image_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let file_path = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("path", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "path",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    let hash_str = |name: &str| {
+        h.hash_get(name).map(|v| match v.value() {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    };
+
+    let alt = hash_str("alt").unwrap_or_default();
+
+    let upload = register_file_upload(file_path, "image", ctx, rc)?;
+
+    let mut attributes = Vec::new();
+    let (width, height) = (hash_str("width"), hash_str("height"));
+    if width.is_none() && height.is_none() {
+        if let Some((width, height)) =
+            cached_image_dimensions(&upload.local_path, &upload.tim_file_name)
+        {
+            attributes.push(format!("width={}", width));
+            attributes.push(format!("height={}", height));
+        }
+    }
+    for (name, value) in [("width", width), ("height", height)] {
+        if let Some(value) = value {
+            attributes.push(format!("{}={}", name, value));
+        }
+    }
+    if let Some(class) = hash_str("class") {
+        attributes.extend(class.split_whitespace().map(|c| format!(".{}", c)));
+    }
+
+    out.write(&format!("![{}]({})", alt.replace(']', "\\]"), upload.url))?;
+    if !attributes.is_empty() {
+        out.write(&format!("{{{}}}", attributes.join(" ")))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templating::ext_render_with_context::RendererExtension;
+    use crate::templating::tim_handlebars::FILE_MAP_ATTRIBUTE;
+    use crate::util::test_project::write_file;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 32"></svg>"#;
+
+    /// Render a template in `week1/intro.md` of a project with the image `week1/diagram.svg`.
+    ///
+    /// returns: (String, Value) The rendered template and the files registered for upload.
+    fn render(template: &str) -> (String, Value) {
+        let dir = TempDir::new().unwrap();
+        write_file(&dir, "week1/diagram.svg", SVG);
+        let mut hb = Handlebars::new();
+        hb.register_helper("image", Box::new(image_helper));
+        let ctx = Context::wraps(json!({
+            "site": {
+                "base_path": "kurssit/ohj1",
+                "local_project_dir": dir.path().to_str().unwrap(),
+            },
+            "path": "week1/intro",
+            "local_file_path": "week1/intro.md",
+        }))
+        .unwrap();
+        let result = hb
+            .render_template_with_context_return_new_context(template, &ctx)
+            .unwrap();
+        let files = result.modified_context.unwrap().data()[FILE_MAP_ATTRIBUTE].clone();
+        (result.rendered, files)
+    }
+
+    /// Split Markdown image markup into the alt text, the URL and the attributes.
+    fn parse_image(markup: &str) -> (&str, &str, &str) {
+        let (alt, rest) = markup
+            .strip_prefix("![")
+            .and_then(|rest| rest.split_once("]("))
+            .unwrap();
+        let (url, attributes) = rest.split_once(')').unwrap();
+        (alt, url, attributes)
+    }
+
+    #[test]
+    fn emits_image_markup_with_attributes() {
+        let (markup, _) = render(
+            r#"{{image "diagram.svg" alt="A [small] diagram" width=400 class="center wide"}}"#,
+        );
+        let (alt, url, attributes) = parse_image(&markup);
+        assert_eq!(alt, "A [small\\] diagram");
+        assert!(
+            url.starts_with("/files/kurssit/ohj1/week1/intro/") && url.ends_with(".svg"),
+            "{}",
+            url
+        );
+        assert_eq!(attributes, "{width=400 .center .wide}");
+    }
+
+    #[test]
+    fn uses_intrinsic_dimensions_without_size() {
+        let (markup, _) = render(r#"{{image "diagram.svg"}}"#);
+        let (alt, _, attributes) = parse_image(&markup);
+        assert_eq!(alt, "");
+        assert_eq!(attributes, "{width=64 height=32}");
+    }
+
+    #[test]
+    fn keeps_given_height_without_width() {
+        let (markup, _) = render(r#"{{image "/week1/diagram.svg" height="10em"}}"#);
+        assert_eq!(parse_image(&markup).2, "{height=10em}");
+    }
+
+    #[test]
+    fn registers_image_for_upload() {
+        let (markup, files) = render(r#"{{image "diagram.svg"}}"#);
+        let files = files.as_object().unwrap();
+        assert_eq!(files.len(), 1);
+        let (local_path, tim_file_name) = files.iter().next().unwrap();
+        assert!(local_path.ends_with("diagram.svg"), "{}", local_path);
+        assert!(
+            parse_image(&markup)
+                .1
+                .ends_with(&format!("/{}", tim_file_name.as_str().unwrap())),
+            "{}",
+            markup
+        );
+    }
+}
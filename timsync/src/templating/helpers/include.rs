@@ -1,19 +1,28 @@
 use crate::templating::ext_context::ContextExtension;
-use crate::templating::util::{get_local_project_dir, resolve_full_file_path};
+use crate::templating::util::{find_matching_files, get_local_project_dir, resolve_full_file_path};
 use crate::util::path::RelativizeExtension;
 use handlebars::{
-    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext, RenderErrorReason,
+    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext, RenderError,
+    RenderErrorReason,
 };
 use serde_json::json;
 use std::ops::Deref;
 use std::path::Path;
 
+/// Characters that make the path of the include helper a glob pattern
+const GLOB_CHARACTERS: [char; 3] = ['*', '?', '['];
+
 /// Include helper.
 /// Includes the content of a file in the current document with optional templating.
 /// The file path can be either relative or absolute to the project root (by using `/` as a prefix).
 ///
 /// **Note**: To use relative paths, the local file path variable must be set in the context.
 ///
+/// If the path contains glob characters (`*`, `?` or `[`), all matching files are included
+/// in the order of their paths, separated by `separator` (a newline by default).
+/// The pattern must match at least one file.
+/// If a file exists at the literal path, it is included instead, so file names with `[` still work.
+///
 /// Example:
 ///
 /// ```md
@@ -22,6 +31,8 @@
 /// Absolute include {{include "/path/to/file.md"}}
 ///
 /// Include with templating {{include "path/to/file.md" template=true}}
+///
+/// Include all matching files {{include "exercises/*.md" separator="\n\n"}}
 /// ```
 pub fn include_helper<'reg, 'rc>(
     h: &Helper<'rc>,
@@ -49,8 +60,65 @@ pub fn include_helper<'reg, 'rc>(
         .unwrap_or(false);
 
     let local_project_dir = get_local_project_dir(ctx)?;
+
+    // A file whose name contains glob characters, e.g. `notes[1].md`, is included as-is
     let target_file_path = resolve_full_file_path(ctx, file_path, local_project_dir)?;
+    if !file_path.contains(GLOB_CHARACTERS) || target_file_path.is_file() {
+        let file_contents = read_included_file(
+            &target_file_path,
+            do_template,
+            r,
+            ctx,
+            rc,
+            local_project_dir,
+        )?;
+        out.write(&file_contents)?;
+        return Ok(());
+    }
+
+    let separator = h
+        .hash_get("separator")
+        .and_then(|v| v.value().as_str())
+        .unwrap_or("\n");
+
+    let files = find_matching_files(ctx, file_path, local_project_dir)?;
+    if files.is_empty() {
+        return Err(RenderErrorReason::Other(format!(
+            "No files match the include pattern '{}'",
+            file_path
+        ))
+        .into());
+    }
+
+    let file_contents = files
+        .iter()
+        .map(|path| read_included_file(path, do_template, r, ctx, rc, local_project_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    out.write(&file_contents.join(separator))?;
 
+    Ok(())
+}
+
+/// Read the contents of an included file, optionally rendering it as a template.
+///
+/// # Arguments
+///
+/// * `target_file_path`: Full path to the included file.
+/// * `do_template`: Whether to render the file contents as a template.
+/// * `r`: The Handlebars registry used to render the file.
+/// * `ctx`: The current context.
+/// * `rc`: The current render context.
+/// * `local_project_dir`: The project root directory.
+///
+/// returns: Result<String, RenderError>
+fn read_included_file<'reg, 'rc>(
+    target_file_path: &Path,
+    do_template: bool,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &RenderContext<'reg, 'rc>,
+    local_project_dir: &str,
+) -> Result<String, RenderError> {
     if !target_file_path.is_file() {
         return Err(RenderErrorReason::Other(format!(
             "File '{}' does not exist",
@@ -59,7 +127,7 @@ pub fn include_helper<'reg, 'rc>(
         .into());
     }
 
-    let file_contents = std::fs::read_to_string(&target_file_path).map_err(|e| {
+    let file_contents = std::fs::read_to_string(target_file_path).map_err(|e| {
         RenderErrorReason::Other(format!(
             "Could not read file '{}': {}",
             target_file_path.display(),
@@ -67,34 +135,87 @@ pub fn include_helper<'reg, 'rc>(
         ))
     })?;
 
-    let file_contents = if do_template {
-        let new_local_file_path = target_file_path
-            .relativize(Path::new(local_project_dir))
-            .to_string_lossy()
-            .to_string();
-        // Create a new context with the local file path set to the included file
-        // This allows the included file to use include helper itself
-        let mut ctx = rc
-            .context()
-            .map(|c| c.deref().clone())
-            .unwrap_or_else(|| ctx.clone());
-        ctx.extend_with_json(&json!({
-            "local_file_path": new_local_file_path
-        }));
-
-        r.render_template_with_context(&file_contents, &ctx)
-            .map_err(|e| {
-                RenderErrorReason::Other(format!(
-                    "Could not render included file '{}': {}",
-                    target_file_path.display(),
-                    e
-                ))
-            })?
-    } else {
-        file_contents
-    };
-
-    out.write(&file_contents)?;
+    if !do_template {
+        return Ok(file_contents);
+    }
 
-    Ok(())
+    let new_local_file_path = target_file_path
+        .relativize(Path::new(local_project_dir))
+        .to_string_lossy()
+        .to_string();
+    // Create a new context with the local file path set to the included file
+    // This allows the included file to use include helper itself
+    let mut ctx = rc
+        .context()
+        .map(|c| c.deref().clone())
+        .unwrap_or_else(|| ctx.clone());
+    ctx.extend_with_json(&json!({
+        "local_file_path": new_local_file_path
+    }));
+
+    let file_contents = r
+        .render_template_with_context(&file_contents, &ctx)
+        .map_err(|e| {
+            RenderErrorReason::Other(format!(
+                "Could not render included file '{}': {}",
+                target_file_path.display(),
+                e
+            ))
+        })?;
+    Ok(file_contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_project::write_file;
+    use tempfile::TempDir;
+
+    /// Render a template in `week1/intro.md` of a project with three exercise files in `week1`.
+    fn render(template: &str) -> Result<String, RenderError> {
+        let dir = TempDir::new().unwrap();
+        write_file(&dir, "week1/exercises/b.md", "B");
+        write_file(&dir, "week1/exercises/a.md", "A");
+        write_file(&dir, "week1/exercises/notes.txt", "Notes");
+        write_file(&dir, "week1/tasks[1].md", "Task 1");
+        let mut hb = Handlebars::new();
+        hb.register_helper("include", Box::new(include_helper));
+        let ctx = Context::wraps(json!({
+            "site": {
+                "local_project_dir": dir.path().to_str().unwrap(),
+            },
+            "local_file_path": "week1/intro.md",
+        }))
+        .unwrap();
+        hb.render_template_with_context(template, &ctx)
+    }
+
+    #[test]
+    fn includes_file_by_relative_and_absolute_path() {
+        assert_eq!(render(r#"{{include "exercises/a.md"}}"#).unwrap(), "A");
+        assert_eq!(
+            render(r#"{{include "/week1/exercises/b.md"}}"#).unwrap(),
+            "B"
+        );
+    }
+
+    #[test]
+    fn glob_includes_matching_files_in_path_order() {
+        assert_eq!(render(r#"{{include "exercises/*.md"}}"#).unwrap(), "A\nB");
+        assert_eq!(
+            render(r#"{{include "exercises/*.md" separator="\n---\n"}}"#).unwrap(),
+            "A\n---\nB"
+        );
+    }
+
+    #[test]
+    fn glob_without_matches_is_an_error() {
+        let err = render(r#"{{include "exercises/*.csv"}}"#).unwrap_err();
+        assert!(err.to_string().contains("No files match"), "{}", err);
+    }
+
+    #[test]
+    fn literal_path_with_glob_characters_is_included() {
+        assert_eq!(render(r#"{{include "tasks[1].md"}}"#).unwrap(), "Task 1");
+    }
 }
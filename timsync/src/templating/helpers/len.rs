@@ -0,0 +1,80 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use serde_json::Value;
+
+/// Length helper.
+/// Outputs the number of items in a list, the number of fields in an object
+/// or the number of characters in a string. Other values have the length 0.
+///
+/// Replaces the built-in `len` helper of Handlebars, which counts the bytes of strings
+/// instead of characters.
+///
+/// Example:
+///
+/// ```md
+/// {{len site.docs}} documents
+///
+/// {{len "Äänikirja"}} -> 9
+///
+/// {{#if (gt (len tags) 0)}}
+/// Tags: {{join tags ", "}}
+/// {{/if}}
+/// ```
+pub fn len_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    _: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let value = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("len", 0))?
+        .value();
+
+    let len = match value {
+        Value::Array(items) => items.len(),
+        Value::Object(fields) => fields.len(),
+        Value::String(s) => s.chars().count(),
+        _ => 0,
+    };
+
+    out.write(&len.to_string())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut hb = Handlebars::new();
+        hb.register_helper("len", Box::new(len_helper));
+        let data = json!({
+            "docs": [{ "path": "a" }, { "path": "b" }, { "path": "c" }],
+            "doc": { "a": 1, "b": 2 },
+            "count": 5,
+        });
+        hb.render_template(template, &data).unwrap()
+    }
+
+    #[test]
+    fn counts_items_and_fields() {
+        assert_eq!(render("{{len docs}}"), "3");
+        assert_eq!(render("{{len doc}}"), "2");
+    }
+
+    #[test]
+    fn counts_characters_of_strings() {
+        assert_eq!(render(r#"{{len "Äänikirja"}}"#), "9");
+    }
+
+    #[test]
+    fn other_values_have_zero_length() {
+        assert_eq!(render("{{len count}}"), "0");
+        assert_eq!(render("{{len missing}}"), "0");
+    }
+}
@@ -0,0 +1,173 @@
+use crate::templating::util::get_site_ctx_json;
+use handlebars::{
+    BlockContext, Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+    RenderErrorReason, Renderable,
+};
+use serde_json::Value;
+
+/// List documents block helper.
+/// Renders the block once for each project document in `site.docs`.
+/// Inside the block, the information of the current document (front matter, `title`, `path`, etc.)
+/// is used as the context, similar to the `each` helper.
+///
+/// Hash parameters filter the listed documents by their front matter fields.
+/// A document is listed if every given field equals the given value.
+/// If the front matter field is a list, the document is listed if the list contains the value.
+///
+/// Optional hash arguments:
+///
+/// * `sort_by` - Sort the documents by the given field. Documents without the field are listed last.
+///
+/// If no documents match, the `{{else}}` block is rendered instead.
+///
+/// Example:
+///
+/// ```md
+/// {{#list_docs type="lecture" sort_by="lecture_number"}}
+/// - [{{ title }}]({{ path }})
+/// {{else}}
+/// No lectures yet.
+/// {{/list_docs}}
+/// ```
+pub fn list_docs_block<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let docs = get_site_ctx_json(ctx)?
+        .get("docs")
+        .and_then(|docs| docs.as_array())
+        .ok_or_else(|| RenderErrorReason::Other("Document list is not set".to_string()))?;
+
+    let filters = h
+        .hash()
+        .iter()
+        .filter(|(key, _)| **key != "sort_by")
+        .map(|(key, value)| (*key, value.value()))
+        .collect::<Vec<_>>();
+
+    let mut matching_docs = docs
+        .iter()
+        .filter(|doc| {
+            filters
+                .iter()
+                .all(|(key, expected)| field_matches(doc.get(*key), expected))
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(sort_by) = h.hash_get("sort_by").and_then(|v| v.value().as_str()) {
+        matching_docs.sort_by(|a, b| match (a.get(sort_by), b.get(sort_by)) {
+            (Some(a), Some(b)) => compare_values(a, b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+    }
+
+    if matching_docs.is_empty() {
+        if let Some(inverse) = h.inverse() {
+            inverse.render(r, ctx, rc, out)?;
+        }
+        return Ok(());
+    }
+
+    let Some(template) = h.template() else {
+        return Ok(());
+    };
+
+    let len = matching_docs.len();
+    for (index, doc) in matching_docs.into_iter().enumerate() {
+        let mut block = BlockContext::new();
+        block.set_base_value(doc.clone());
+        block.set_local_var("index", Value::from(index));
+        block.set_local_var("first", Value::from(index == 0));
+        block.set_local_var("last", Value::from(index == len - 1));
+        rc.push_block(block);
+        let result = template.render(r, ctx, rc, out);
+        rc.pop_block();
+        result?;
+    }
+
+    Ok(())
+}
+
+/// Check whether a document field matches the expected filter value.
+/// List fields match if any of the list items equals the expected value.
+///
+/// returns: bool
+fn field_matches(field: Option<&Value>, expected: &Value) -> bool {
+    match field {
+        Some(Value::Array(items)) if !expected.is_array() => items.contains(expected),
+        Some(value) => value == expected,
+        None => expected.is_null(),
+    }
+}
+
+/// Compare two JSON values for sorting.
+/// Numbers are compared numerically and other values by their string representation.
+///
+/// returns: Ordering
+fn compare_values(a: &Value, b: &Value) -> std::cmp::Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        _ => match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            _ => a.to_string().cmp(&b.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("list_docs", Box::new(list_docs_block));
+        let docs = json!([
+            {"path": "a", "title": "Alpha", "type": "lecture", "number": 2, "tags": ["week1"]},
+            {"path": "b", "title": "Beta", "type": "exercise", "tags": ["week1", "week2"]},
+            {"path": "c", "title": "Gamma", "type": "lecture", "number": 1},
+        ]);
+        handlebars
+            .render_template(template, &json!({"site": {"docs": docs}}))
+            .unwrap()
+    }
+
+    #[test]
+    fn documents_are_filtered_by_front_matter_field() {
+        assert_eq!(
+            render("{{#list_docs type=\"lecture\"}}{{ title }};{{/list_docs}}"),
+            "Alpha;Gamma;"
+        );
+    }
+
+    #[test]
+    fn list_fields_match_if_they_contain_the_value() {
+        assert_eq!(
+            render("{{#list_docs tags=\"week2\"}}{{ title }};{{/list_docs}}"),
+            "Beta;"
+        );
+    }
+
+    #[test]
+    fn documents_are_sorted_by_sort_by_field_with_missing_values_last() {
+        assert_eq!(
+            render(
+                "{{#list_docs sort_by=\"number\"}}{{ path }}{{#if @last}}.{{/if}}{{/list_docs}}"
+            ),
+            "cab."
+        );
+    }
+
+    #[test]
+    fn else_block_is_rendered_if_nothing_matches() {
+        assert_eq!(
+            render("{{#list_docs type=\"quiz\"}}{{ title }}{{else}}None{{/list_docs}}"),
+            "None"
+        );
+    }
+}
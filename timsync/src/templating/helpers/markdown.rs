@@ -0,0 +1,80 @@
+use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext, Renderable};
+
+/// Markdown block helper.
+/// Renders the content as a separate paragraph block in TIM.
+///
+/// The content is separated from the surrounding text with blank lines and `#-` paragraph breaks,
+/// so that generated markdown is not merged into the preceding or following paragraph.
+/// Leading and trailing blank lines of the content are removed.
+/// If the content is empty, nothing is rendered.
+///
+/// Example:
+///
+/// ```md
+/// Some text before the generated content.
+/// {{#markdown}}
+/// {{#each exercises}}
+/// - {{ this }}
+/// {{/each}}
+/// {{/markdown}}
+/// ```
+pub fn markdown_block<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let Some(tmpl) = h.template() else {
+        return Ok(());
+    };
+
+    let content = tmpl.renders(r, ctx, rc)?;
+    let content = content.trim_matches(|c| c == '\n' || c == '\r');
+    if content.trim().is_empty() {
+        return Ok(());
+    }
+
+    out.write("\n\n#-\n")?;
+    out.write(content)?;
+    out.write("\n\n#-\n")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut hb = Handlebars::new();
+        hb.register_helper("markdown", Box::new(markdown_block));
+        hb.render_template(template, &json!({ "items": ["a", "b"] }))
+            .unwrap()
+    }
+
+    #[test]
+    fn content_is_separated_with_paragraph_breaks() {
+        assert_eq!(
+            render("Before{{#markdown}}- item{{/markdown}}After"),
+            "Before\n\n#-\n- item\n\n#-\nAfter"
+        );
+    }
+
+    #[test]
+    fn leading_and_trailing_blank_lines_are_trimmed() {
+        assert_eq!(
+            render("{{#markdown}}\n\n{{#each items}}\n- {{this}}\n{{/each}}\n\n{{/markdown}}"),
+            "\n\n#-\n- a\n- b\n\n#-\n"
+        );
+    }
+
+    #[test]
+    fn empty_content_renders_nothing() {
+        assert_eq!(
+            render("Before{{#markdown}}\n  \n{{/markdown}}After"),
+            "BeforeAfter"
+        );
+    }
+}
@@ -1,9 +1,23 @@
 pub mod area;
+pub mod code_from_file;
+pub mod csv;
 pub mod docsettings;
+pub mod each_file;
 pub mod file;
 pub mod gen_par_id;
+pub mod image;
 pub mod include;
+pub mod len;
+pub mod list_docs;
+pub mod markdown;
+pub mod number;
+pub mod partial;
 pub mod ref_area;
+pub mod ref_area_uid;
+pub mod review;
+pub mod slugify;
 pub mod task;
 pub mod task_id;
+pub mod tim_ref;
+pub mod unless_empty;
 pub mod url_for;
@@ -0,0 +1,177 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use serde_json::Value;
+
+/// Number formatting helper.
+/// Formats a number with a fixed number of decimals and groups the integer part by thousands.
+///
+/// Optional hash arguments:
+///
+/// * `decimals` - The number of decimals. Defaults to 0.
+/// * `thousands_separator` - The separator between the thousands groups. Defaults to `,`.
+/// * `decimal_separator` - The separator between the integer and the decimal part. Defaults to `.`.
+///
+/// Numbers given as strings are parsed first.
+///
+/// Example:
+///
+/// ```md
+/// {{number 1234567}} -> 1,234,567
+///
+/// {{number 1234.5 decimals=2}} -> 1,234.50
+///
+/// {{number 1234.5 decimals=1 thousands_separator=" " decimal_separator=","}} -> 1 234,5
+/// ```
+pub fn number_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    _: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let value = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("number", 0))?
+        .value();
+
+    let number = match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        RenderErrorReason::ParamTypeMismatchForName("number", "0".to_string(), "number".to_string())
+    })?;
+
+    let decimals = match h.hash_get("decimals") {
+        Some(decimals) => decimals.value().as_u64().ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "number",
+                "decimals".to_string(),
+                "integer".to_string(),
+            )
+        })? as usize,
+        None => 0,
+    };
+    let thousands_separator = hash_str(h, "thousands_separator")?.unwrap_or(",");
+    let decimal_separator = hash_str(h, "decimal_separator")?.unwrap_or(".");
+
+    out.write(&format_number(
+        number,
+        decimals,
+        thousands_separator,
+        decimal_separator,
+    ))?;
+
+    Ok(())
+}
+
+/// Get an optional string hash argument of the helper.
+///
+/// returns: Result<Option<&str>, RenderErrorReason>
+fn hash_str<'a>(
+    h: &'a Helper<'_>,
+    name: &'static str,
+) -> Result<Option<&'a str>, RenderErrorReason> {
+    match h.hash_get(name) {
+        Some(value) => value.value().as_str().map(Some).ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "number",
+                name.to_string(),
+                "string".to_string(),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Format a number with the given number of decimals and separators.
+///
+/// # Arguments
+///
+/// * `number`: The number to format.
+/// * `decimals`: The number of decimals.
+/// * `thousands_separator`: The separator between the thousands groups.
+/// * `decimal_separator`: The separator between the integer and the decimal part.
+///
+/// returns: String
+fn format_number(
+    number: f64,
+    decimals: usize,
+    thousands_separator: &str,
+    decimal_separator: &str,
+) -> String {
+    let formatted = format!("{:.*}", decimals, number.abs());
+    let (integer_part, decimal_part) = formatted
+        .split_once('.')
+        .map_or((formatted.as_str(), None), |(i, d)| (i, Some(d)));
+
+    let mut result = String::new();
+    // Avoid printing "-0" for negative numbers that round to zero
+    if number < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        result.push('-');
+    }
+    for (i, digit) in integer_part.chars().enumerate() {
+        if i > 0 && (integer_part.len() - i) % 3 == 0 {
+            result.push_str(thousands_separator);
+        }
+        result.push(digit);
+    }
+    if let Some(decimal_part) = decimal_part {
+        result.push_str(decimal_separator);
+        result.push_str(decimal_part);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> Result<String, String> {
+        let mut hb = Handlebars::new();
+        hb.register_helper("number", Box::new(number_helper));
+        hb.render_template(template, &json!({ "total": 1234567 }))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn groups_thousands() {
+        assert_eq!(format_number(1234567.0, 0, ",", "."), "1,234,567");
+        assert_eq!(format_number(123.0, 0, ",", "."), "123");
+        assert_eq!(format_number(1000.0, 0, " ", "."), "1 000");
+    }
+
+    #[test]
+    fn formats_decimals() {
+        assert_eq!(format_number(1234.5, 2, ",", "."), "1,234.50");
+        assert_eq!(format_number(1234.5, 1, " ", ","), "1 234,5");
+        assert_eq!(format_number(0.126, 2, ",", "."), "0.13");
+    }
+
+    #[test]
+    fn formats_negative_numbers() {
+        assert_eq!(format_number(-1234.5, 1, ",", "."), "-1,234.5");
+        assert_eq!(format_number(-0.001, 2, ",", "."), "0.00");
+    }
+
+    #[test]
+    fn renders_numbers_and_strings() {
+        assert_eq!(render("{{number total}}").unwrap(), "1,234,567");
+        assert_eq!(
+            render(
+                r#"{{number "1234.5" decimals=1 thousands_separator=" " decimal_separator=","}}"#
+            )
+            .unwrap(),
+            "1 234,5"
+        );
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        let error = render(r#"{{number "many"}}"#).unwrap_err();
+        assert!(error.contains("number"), "{}", error);
+    }
+}
@@ -0,0 +1,44 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason, Renderable,
+};
+
+/// Partial helper.
+/// Renders a project template by its name with the current context, like the `{{> name}}` partial syntax.
+///
+/// Unlike the partial syntax, the helper accepts any template name, including the namespaced names
+/// of theme templates (e.g. `base:header.md`). This allows a project template to include
+/// the theme template it overrides.
+///
+/// Example:
+///
+/// `_templates/header.md`:
+/// ```md
+/// {{partial "base:header.md"}}
+///
+/// Additional header content of the project
+/// ```
+pub fn partial_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let name = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("name", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "name",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    let template = r
+        .get_template(name)
+        .ok_or_else(|| RenderErrorReason::PartialNotFound(name.to_string()))?;
+    template.render(r, ctx, rc, out)
+}
@@ -0,0 +1,152 @@
+use crate::templating::util::get_site_ctx_json;
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+/// Reference area by UID helper.
+/// Inserts a reference to a named area in another document using the document UID.
+/// Works the same way as the `ref_area` helper, but the document is referenced by its UID
+/// instead of the document ID.
+///
+/// Example:
+///
+/// `doc1.md`:
+/// ````md
+/// ---
+/// uid: doc1
+/// ---
+///
+/// {{#area "area-example"}}
+/// This is the content area.
+/// {{/area}}
+/// ````
+///
+/// `doc2.md`:
+/// ````md
+/// {{ref_area_uid "doc1" "area-example"}}
+/// ````
+pub fn ref_area_uid_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let doc_uid = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("doc_uid", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "doc_uid",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    let area_name = h
+        .param(1)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("area_name", 1))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "area_name",
+                "1".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    if area_name.is_empty() {
+        return Err(RenderErrorReason::Other(format!(
+            "Area name for the reference to document '{}' must not be empty",
+            doc_uid
+        ))
+        .into());
+    }
+
+    let site_ctx_json = get_site_ctx_json(ctx)?;
+
+    let doc_map = site_ctx_json
+        .get("doc")
+        .expect("Document map is not set")
+        .as_object()
+        .expect("Document map is not an object");
+
+    let doc_id = doc_map
+        .get(doc_uid)
+        .map(|v| v.as_object().expect("Document info is not an object"))
+        .map(|v| {
+            v.get("doc_id")
+                .expect("Document ID is not set")
+                .as_u64()
+                .expect("Document ID is not a number")
+        })
+        .ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "Document with uid '{}' not found in the project",
+                doc_uid
+            ))
+        })?;
+
+    out.write(&format!(
+        "#- {{rd=\"{}\" ra=\"{}\"}}\n\n#-",
+        doc_id, area_name
+    ))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn render(template: &str) -> Result<String, handlebars::RenderError> {
+        let mut hb = Handlebars::new();
+        hb.register_helper("ref_area_uid", Box::new(ref_area_uid_helper));
+        let data: Value = json!({
+            "site": {
+                "doc": {
+                    "lecture1": {"doc_id": 1234, "path": "lectures/lecture1"},
+                    "lecture2": {"doc_id": 5678, "path": "lectures/lecture2"},
+                }
+            }
+        });
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn resolves_uid_to_document_id() {
+        assert_eq!(
+            render(r#"{{ref_area_uid "lecture2" "summary"}}"#).unwrap(),
+            "#- {rd=\"5678\" ra=\"summary\"}\n\n#-"
+        );
+    }
+
+    #[test]
+    fn fails_for_unknown_uid() {
+        let error = render(r#"{{ref_area_uid "lecture3" "summary"}}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("Document with uid 'lecture3' not found in the project"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn fails_for_empty_area_name() {
+        let error = render(r#"{{ref_area_uid "lecture1" ""}}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("must not be empty"), "{}", error);
+    }
+
+    #[test]
+    fn fails_for_missing_area_name() {
+        assert!(render(r#"{{ref_area_uid "lecture1"}}"#).is_err());
+    }
+}
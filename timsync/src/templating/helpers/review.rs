@@ -0,0 +1,72 @@
+use crate::templating::helpers::area::{area_name_param, write_area};
+use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext};
+
+/// Class of the area generated by the review helper if no `class` is given
+const DEFAULT_REVIEW_CLASS: &str = ".review";
+
+/// Review block helper.
+/// Surrounds the content into an area for reviewing, e.g. with velps.
+/// The area gets the `.review` class unless another `class` is given.
+///
+/// The review options of TIM vary between instances and versions,
+/// so the attributes of the area are not fixed: like in the `area` helper,
+/// the area name, `class` and `collapse` can be given, and any other hash parameters
+/// are added to the area as attributes.
+/// If no name is given, the helper generates a random name for the area.
+///
+/// Example:
+/// ```md
+/// {{#review}}
+/// The answers to the tasks in this area are reviewed by the teachers.
+/// {{/review}}
+///
+/// {{#review "essay-review" class=".review .hidden-print" hidefrom="preview"}}
+/// Write your essay below.
+/// {{/review}}
+/// ```
+pub fn review_block<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let area_name = area_name_param(h, "review")?;
+    write_area(h, r, ctx, rc, out, &area_name, DEFAULT_REVIEW_CLASS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut hb = Handlebars::new();
+        hb.register_helper("review", Box::new(review_block));
+        hb.render_template(template, &json!({})).unwrap()
+    }
+
+    #[test]
+    fn unnamed_review_gets_generated_name_and_default_class() {
+        let area = render("{{#review}}Text{{/review}}");
+        let opening = area.lines().next().unwrap();
+        assert!(opening.starts_with("#- {area=\"review-"), "{}", opening);
+        assert!(opening.contains(" .review "), "{}", opening);
+        assert!(
+            area.contains("\n#-\nText\n#- {area_end=\"review-"),
+            "{}",
+            area
+        );
+    }
+
+    #[test]
+    fn named_review_with_class_and_extra_attributes() {
+        let area = render(
+            r#"{{#review "essay-review" class=".review .hidden-print" hidefrom="preview"}}Text{{/review}}"#,
+        );
+        assert_eq!(
+            area,
+            "#- {area=\"essay-review\"  .review .hidden-print hidefrom=\"preview\"}\n\n#-\nText\n#- {area_end=\"essay-review\"}\n\n#-\n"
+        );
+    }
+}
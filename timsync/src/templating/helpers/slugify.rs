@@ -0,0 +1,104 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use serde_json::Value;
+
+/// Slugify helper.
+/// Converts the given value into a lowercase, hyphenated ASCII string.
+/// Useful for generating anchors and area names from titles.
+///
+/// Non-ASCII characters are transliterated into ASCII where possible (e.g. `ä` becomes `a`),
+/// other characters are removed.
+///
+/// Example:
+///
+/// ```md
+/// {{slugify "Hello, World!"}} -> hello-world
+///
+/// {{slugify "Äänikirjat ja öljy"}} -> aanikirjat-ja-oljy
+///
+/// {{#area (slugify title)}}
+/// Area named after the document title.
+/// {{/area}}
+/// ```
+pub fn slugify_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    _: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let value = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("value", 0))?
+        .value();
+
+    let value = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => {
+            return Err(RenderErrorReason::ParamTypeMismatchForName(
+                "value",
+                "0".to_string(),
+                "string".to_string(),
+            )
+            .into())
+        }
+    };
+
+    out.write(&slug::slugify(value))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut hb = Handlebars::new();
+        hb.register_helper("slugify", Box::new(slugify_helper));
+        hb.render_template(template, &json!({})).unwrap()
+    }
+
+    #[test]
+    fn replaces_spaces_with_hyphens() {
+        assert_eq!(
+            render(r#"{{slugify "Week 1 exercises"}}"#),
+            "week-1-exercises"
+        );
+    }
+
+    #[test]
+    fn removes_punctuation() {
+        assert_eq!(render(r#"{{slugify "Hello, World!"}}"#), "hello-world");
+        assert_eq!(
+            render(r#"{{slugify "  (Part 2): Loops  "}}"#),
+            "part-2-loops"
+        );
+    }
+
+    #[test]
+    fn transliterates_accented_characters() {
+        assert_eq!(
+            render(r#"{{slugify "Äänikirjat ja öljy"}}"#),
+            "aanikirjat-ja-oljy"
+        );
+        assert_eq!(render(r#"{{slugify "Crème brûlée"}}"#), "creme-brulee");
+    }
+
+    #[test]
+    fn accepts_numbers() {
+        assert_eq!(render("{{slugify 2024}}"), "2024");
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        let mut hb = Handlebars::new();
+        hb.register_helper("slugify", Box::new(slugify_helper));
+        assert!(hb
+            .render_template("{{slugify value}}", &json!({ "value": [1, 2] }))
+            .is_err());
+    }
+}
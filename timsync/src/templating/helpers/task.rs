@@ -1,4 +1,4 @@
-use crate::processing::task_processor::{TASKS_REF_MAP_KEY, TASKS_UID};
+use crate::processing::task_processor::{TASKS_GROUPS_KEY, TASKS_REF_MAP_KEY, TASKS_UID};
 use crate::templating::util::get_site_ctx_json;
 use crate::util::tim_client::hashed_par_id;
 use handlebars::{
@@ -51,6 +51,89 @@ pub fn task_helper<'reg, 'rc>(
             )
         })?;
 
+    write_task_reference(ctx, task_id, out)
+}
+
+/// Nth task helper.
+/// Inserts a reference to the Nth task (starting from 1) in a task group.
+///
+/// The tasks are grouped by the folder of the task files relative to the project root,
+/// and the tasks of each group are ordered by their UIDs like in the tasks document.
+///
+/// Example:
+///
+/// ```md
+/// The third task in `tasks/week1`:
+///
+/// {{task_nth "tasks/week1" 3}}
+/// ```
+pub fn task_nth_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let group = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("group", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "group",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?;
+    let index = h
+        .param(1)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("index", 1))?
+        .value()
+        .as_u64()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "index",
+                "1".to_string(),
+                "positive integer".to_string(),
+            )
+        })?;
+
+    let site_ctx_json = get_site_ctx_json(ctx)?;
+    let groups = site_ctx_json.get(TASKS_GROUPS_KEY).ok_or_else(|| {
+        RenderErrorReason::Other("There are no tasks registered in the project. Add tasks (`.task.yml` files) to the project to use the task_nth helper.".to_string())
+    })?.as_object().expect("Task groups are not an object");
+
+    let uids = groups
+        .get(group.trim_matches('/'))
+        .map(|v| v.as_array().expect("Task group is not an array"))
+        .ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "Task group '{}' does not exist. Available groups: {}",
+                group,
+                groups.keys().cloned().collect::<Vec<_>>().join(", ")
+            ))
+        })?;
+    let task_id = index
+        .checked_sub(1)
+        .and_then(|i| uids.get(i as usize))
+        .map(|v| v.as_str().expect("Task UID is not a string"))
+        .ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "Task index {} is out of range for task group '{}', which has {} tasks. The first task has index 1.",
+                index,
+                group,
+                uids.len()
+            ))
+        })?;
+
+    write_task_reference(ctx, task_id, out)
+}
+
+/// Write a reference paragraph to the task with the given UID.
+///
+/// returns: HelperResult
+fn write_task_reference(ctx: &Context, task_id: &str, out: &mut dyn Output) -> HelperResult {
     let site_ctx_json = get_site_ctx_json(ctx)?;
 
     let task_ref_map = site_ctx_json.get(TASKS_REF_MAP_KEY).ok_or_else(|| {
@@ -86,3 +169,77 @@ pub fn task_helper<'reg, 'rc>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> Result<String, String> {
+        let mut hb = Handlebars::new();
+        hb.register_helper("task", Box::new(task_helper));
+        hb.register_helper("task_nth", Box::new(task_nth_helper));
+        let data = json!({
+            "site": {
+                "doc": { TASKS_UID: { "doc_id": 42, "path": "_project_tasks" } },
+                TASKS_REF_MAP_KEY: { "a1": "parA1", "a2": "parA2", "b1": "parB1" },
+                TASKS_GROUPS_KEY: {
+                    "tasks/week1": ["a1", "a2"],
+                    "tasks/week2": ["b1"],
+                },
+            },
+        });
+        hb.render_template(template, &data)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn task_nth_references_nth_task_of_group() {
+        assert_eq!(
+            render(r#"{{task_nth "tasks/week1" 2}}"#).unwrap(),
+            render(r#"{{task "a2"}}"#).unwrap()
+        );
+        assert_eq!(
+            render(r#"{{task_nth "/tasks/week2/" 1}}"#).unwrap(),
+            format!(
+                "#- {{ rd=\"42\" rp=\"parB1\" id=\"{}\" }}\n#-\n",
+                hashed_par_id(Some("b1"))
+            )
+        );
+    }
+
+    #[test]
+    fn task_nth_fails_for_out_of_range_index() {
+        for index in [0, 3] {
+            let error =
+                render(&format!(r#"{{{{task_nth "tasks/week1" {}}}}}"#, index)).unwrap_err();
+            assert!(
+                error.contains(&format!("Task index {} is out of range", index))
+                    && error.contains("which has 2 tasks"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn task_nth_fails_for_unknown_group() {
+        let error = render(r#"{{task_nth "tasks/week3" 1}}"#).unwrap_err();
+        assert!(
+            error.contains("Task group 'tasks/week3' does not exist")
+                && error.contains("tasks/week1, tasks/week2"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn task_fails_for_unknown_uid() {
+        let error = render(r#"{{task "c1"}}"#).unwrap_err();
+        assert!(
+            error.contains("Task with UID 'c1' is not registered"),
+            "{}",
+            error
+        );
+    }
+}
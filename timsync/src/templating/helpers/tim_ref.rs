@@ -0,0 +1,77 @@
+use crate::templating::tim_handlebars::TIM_PATH_REF_PREFIX;
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+/// TIM reference helper.
+/// Inserts a reference to a named area in a document that already exists in TIM,
+/// but is not part of the project. The document is referenced by its full path in TIM.
+///
+/// TIM references documents only by their IDs, and the templates are rendered offline.
+/// Because of that, the helper only inserts a placeholder for the document ID.
+/// The placeholders are resolved to the actual document IDs during sync
+/// by querying TIM for the referenced paths.
+/// The sync fails if any of the referenced documents does not exist in TIM.
+///
+/// Example:
+///
+/// ```md
+/// {{tim_ref "kurssit/tie/kurssi/materiaali" "area-example"}}
+/// ```
+pub fn tim_ref_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    _: &'reg Handlebars<'reg>,
+    _: &'rc Context,
+    _: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let tim_path = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("tim_path", 0))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "tim_path",
+                "0".to_string(),
+                "string".to_string(),
+            )
+        })?
+        .trim_matches('/');
+
+    if tim_path.is_empty() || tim_path.contains(['"', ' ']) {
+        return Err(RenderErrorReason::Other(format!(
+            "Invalid TIM document path '{}' for the reference",
+            tim_path
+        ))
+        .into());
+    }
+
+    let area_name = h
+        .param(1)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("area_name", 1))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "area_name",
+                "1".to_string(),
+                "string".to_string(),
+            )
+        })?;
+
+    if area_name.is_empty() {
+        return Err(RenderErrorReason::Other(format!(
+            "Area name for the reference to document '{}' must not be empty",
+            tim_path
+        ))
+        .into());
+    }
+
+    out.write(&format!(
+        "#- {{rd=\"{}{}\" ra=\"{}\"}}\n\n#-",
+        TIM_PATH_REF_PREFIX, tim_path, area_name
+    ))?;
+
+    Ok(())
+}
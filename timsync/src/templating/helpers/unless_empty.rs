@@ -0,0 +1,113 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, JsonTruthy, Output, RenderContext,
+    RenderErrorReason, Renderable,
+};
+use serde_json::Value;
+
+/// Unless empty block helper.
+/// Renders the block only if the given value is not empty.
+/// Useful for guarding generated areas and lists so that no empty wrappers are emitted.
+///
+/// Arrays, objects and strings are empty if they have no items, fields or characters.
+/// Missing values and `null` are empty. Other values are empty if they are falsy (e.g. `false`).
+/// Unlike `if`, the number `0` is not empty.
+///
+/// If the value is empty, the `{{else}}` block is rendered instead.
+///
+/// Example:
+///
+/// ```md
+/// {{#unless_empty exercises}}
+/// {{#area "exercises"}}
+/// {{#each exercises}}
+/// - {{ this }}
+/// {{/each}}
+/// {{/area}}
+/// {{else}}
+/// No exercises this week.
+/// {{/unless_empty}}
+/// ```
+pub fn unless_empty_block<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let value = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("unless_empty", 0))?
+        .value();
+
+    let template = if is_empty(value) {
+        h.inverse()
+    } else {
+        h.template()
+    };
+
+    match template {
+        Some(template) => template.render(r, ctx, rc, out),
+        None => Ok(()),
+    }
+}
+
+/// Check whether a value is empty for the `unless_empty` helper.
+///
+/// returns: bool
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        Value::String(s) => s.is_empty(),
+        other => !other.is_truthy(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(value: Value) -> String {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("unless_empty", Box::new(unless_empty_block));
+        handlebars
+            .render_template(
+                "{{#unless_empty value}}full{{else}}empty{{/unless_empty}}",
+                &json!({ "value": value }),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn empty_values_render_the_else_block() {
+        for value in [json!([]), json!({}), json!(""), json!(null), json!(false)] {
+            assert_eq!(render(value.clone()), "empty", "{}", value);
+        }
+    }
+
+    #[test]
+    fn non_empty_values_render_the_block() {
+        for value in [
+            json!(["a"]),
+            json!({"a": 1}),
+            json!("a"),
+            json!(0),
+            json!(true),
+        ] {
+            assert_eq!(render(value.clone()), "full", "{}", value);
+        }
+    }
+
+    #[test]
+    fn missing_value_is_empty() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("unless_empty", Box::new(unless_empty_block));
+        assert_eq!(
+            handlebars
+                .render_template("{{#unless_empty missing}}full{{/unless_empty}}", &json!({}))
+                .unwrap(),
+            ""
+        );
+    }
+}
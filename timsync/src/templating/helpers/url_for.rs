@@ -6,11 +6,13 @@
 /// URL generation helper,
 /// Generates a full URL to the given document uid.
 ///
+/// If no document has the given uid, the argument is matched against the TIM paths
+/// of the project documents (e.g. `folder/doc`), so documents without a uid can be linked too.
 ///
 /// Example:
 ///
 /// `doc1.md`:
-/// ````
+/// ````md
 /// ---
 /// uid: doc1
 /// ---
@@ -19,8 +21,10 @@
 /// ````
 ///
 /// `doc2.md`:
-/// ````
+/// ````md
 /// [Link to Document 1]({{url_for "doc1"}})
+///
+/// [Link to a document without uid]({{url_for "folder/doc3"}})
 /// ````
 pub fn url_for_helper<'reg, 'rc>(
     h: &Helper<'rc>,
@@ -61,21 +65,32 @@ pub fn url_for_helper<'reg, 'rc>(
         .as_object()
         .expect("Document map is not an object");
 
-    let doc_path = doc_map
-        .get(doc_uid)
-        .map(|v| v.as_object().expect("Document info is not an object"))
-        .map(|v| {
-            v.get("path")
-                .expect("Document TIM path is not set")
-                .as_str()
-                .expect("Document TIM path is not a string")
-        })
-        .ok_or_else(|| {
-            RenderErrorReason::Other(format!(
-                "Document with uid '{}' not found in the project",
-                doc_uid
-            ))
-        })?;
+    let doc_path = match doc_map.get(doc_uid) {
+        Some(doc_info) => doc_info
+            .as_object()
+            .expect("Document info is not an object")
+            .get("path")
+            .expect("Document TIM path is not set")
+            .as_str()
+            .expect("Document TIM path is not a string"),
+        None => {
+            // Fall back to matching the TIM paths of all documents
+            let target_path = doc_uid.trim_matches('/').to_lowercase();
+            site_ctx_json
+                .get("docs")
+                .and_then(|docs| docs.as_array())
+                .expect("Document list is not set")
+                .iter()
+                .filter_map(|doc_info| doc_info.get("path").and_then(|path| path.as_str()))
+                .find(|path| *path == target_path)
+                .ok_or_else(|| {
+                    RenderErrorReason::Other(format!(
+                        "Document with uid or path '{}' not found in the project",
+                        doc_uid
+                    ))
+                })?
+        }
+    };
 
     if view_url.is_empty() {
         out.write(&format!("{}/{}", base_path, doc_path))?;
@@ -85,3 +100,70 @@ pub fn url_for_helper<'reg, 'rc>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> Result<String, String> {
+        let mut hb = Handlebars::new();
+        hb.register_helper("url_for", Box::new(url_for_helper));
+        let data = json!({
+            "site": {
+                "base_path": "kurssit/ohj1",
+                "doc": {
+                    "intro": { "doc_id": 1, "path": "luennot/intro" },
+                },
+                "docs": [
+                    { "path": "luennot/intro", "title": "Intro" },
+                    { "path": "tehtavat/demo1", "title": "Demo 1" },
+                ],
+            },
+        });
+        hb.render_template(template, &data)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn resolves_uid() {
+        assert_eq!(
+            render(r#"{{url_for "intro"}}"#).unwrap(),
+            "/view/kurssit/ohj1/luennot/intro"
+        );
+    }
+
+    #[test]
+    fn resolves_path_of_document_without_uid() {
+        assert_eq!(
+            render(r#"{{url_for "tehtavat/demo1"}}"#).unwrap(),
+            "/view/kurssit/ohj1/tehtavat/demo1"
+        );
+        assert_eq!(
+            render(r#"{{url_for "/Tehtavat/Demo1/"}}"#).unwrap(),
+            "/view/kurssit/ohj1/tehtavat/demo1"
+        );
+    }
+
+    #[test]
+    fn uses_view_route() {
+        assert_eq!(
+            render(r#"{{url_for "intro" view="teacher"}}"#).unwrap(),
+            "/teacher/kurssit/ohj1/luennot/intro"
+        );
+        assert_eq!(
+            render(r#"{{url_for "tehtavat/demo1" view=""}}"#).unwrap(),
+            "kurssit/ohj1/tehtavat/demo1"
+        );
+    }
+
+    #[test]
+    fn fails_for_unknown_document() {
+        let error = render(r#"{{url_for "missing"}}"#).unwrap_err();
+        assert!(
+            error.contains("Document with uid or path 'missing' not found"),
+            "{}",
+            error
+        );
+    }
+}
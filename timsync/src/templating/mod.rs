@@ -1,3 +1,4 @@
+pub mod document_context;
 pub mod ext_context;
 pub mod ext_render_with_context;
 mod helpers;
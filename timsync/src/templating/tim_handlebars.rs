@@ -1,18 +1,44 @@
 use crate::project::project::Project;
 use crate::templating::helpers::area::area_block;
+use crate::templating::helpers::code_from_file::code_from_file_helper;
+use crate::templating::helpers::csv::csv_helper;
 use crate::templating::helpers::docsettings::docsettings_block;
+use crate::templating::helpers::each_file::each_file_block;
 use crate::templating::helpers::file::file_helper;
 use crate::templating::helpers::gen_par_id::gen_par_id_helper;
+use crate::templating::helpers::image::image_helper;
 use crate::templating::helpers::include::include_helper;
+use crate::templating::helpers::len::len_helper;
+use crate::templating::helpers::list_docs::list_docs_block;
+use crate::templating::helpers::markdown::markdown_block;
+use crate::templating::helpers::number::number_helper;
+use crate::templating::helpers::partial::partial_helper;
 use crate::templating::helpers::ref_area::ref_area_helper;
-use crate::templating::helpers::task::task_helper;
+use crate::templating::helpers::ref_area_uid::ref_area_uid_helper;
+use crate::templating::helpers::review::review_block;
+use crate::templating::helpers::slugify::slugify_helper;
+use crate::templating::helpers::task::{task_helper, task_nth_helper};
 use crate::templating::helpers::task_id::task_id_helper;
+use crate::templating::helpers::tim_ref::tim_ref_helper;
+use crate::templating::helpers::unless_empty::unless_empty_block;
 use crate::templating::helpers::url_for::url_for_helper;
 use anyhow::Context;
 use handlebars::Handlebars;
+use std::path::Path;
 
 pub const FILE_MAP_ATTRIBUTE: &str = "$_timsync_upload_files";
-const TEMPLATE_FOLDER: &str = "_templates";
+/// Key of the site context variable that holds the path of the shared files document.
+/// The variable is only set if shared files are enabled for the sync target.
+pub const SHARED_FILES_DOC_KEY: &str = "shared_files_doc";
+/// Prefix of the placeholder document IDs inserted by the `tim_ref` helper.
+/// The placeholders are resolved to actual document IDs during sync.
+pub const TIM_PATH_REF_PREFIX: &str = "timsync-path:";
+pub const TEMPLATE_FOLDER: &str = "_templates";
+/// Folder that contains the template themes, each in a subfolder named by the theme.
+pub const THEMES_FOLDER: &str = "_themes";
+/// Separator between the theme name and the template name in the namespaced names of theme templates,
+/// e.g. `base:header.md`.
+pub const THEME_TEMPLATE_SEPARATOR: &str = ":";
 const HELPERS_FOLDER: &str = "_helpers";
 
 pub trait TimRendererExt
@@ -20,11 +46,14 @@ pub trait TimRendererExt
     Self: Sized,
 {
     /// Extend the renderer instance with the TIM templates for documents.
+    /// Also registers the base helpers.
     ///
     /// returns: &Self
     fn with_tim_doc_helpers(self) -> Self;
 
-    /// Extend the renderer instance with the file helpers.
+    /// Extend the renderer instance with the file helpers and the general-purpose helpers
+    /// of `handlebars_misc_helpers`.
+    /// These helpers are shared by all processors, including the task processor.
     ///
     /// returns: &Self
     fn with_base_helpers(self) -> Self;
@@ -35,6 +64,12 @@ pub trait TimRendererExt
     /// Templates are scanned from the `_templates` folder in a project.
     /// All files in the folder are registered as templates.
     ///
+    /// The templates of the themes listed in `template_themes` of the config are registered first
+    /// from `_themes/<name>`, so that later themes and the project templates override them.
+    /// Each theme template is also registered with a name prefixed with the theme name
+    /// (e.g. `base:header.md`), so an overriding template can still include the original one
+    /// with the `partial` helper.
+    ///
     /// # Arguments
     ///
     /// * `project`: The project to get the templates from.
@@ -60,23 +95,57 @@ impl TimRendererExt for Handlebars<'_> {
     fn with_tim_doc_helpers(mut self) -> Self {
         self.register_escape_fn(handlebars::no_escape);
         self.register_helper("area", Box::new(area_block));
+        self.register_helper("review", Box::new(review_block));
         self.register_helper("docsettings", Box::new(docsettings_block));
         self.register_helper("ref_area", Box::new(ref_area_helper));
+        self.register_helper("ref_area_uid", Box::new(ref_area_uid_helper));
+        self.register_helper("tim_ref", Box::new(tim_ref_helper));
         self.register_helper("task", Box::new(task_helper));
-        handlebars_misc_helpers::register(&mut self);
+        self.register_helper("task_nth", Box::new(task_nth_helper));
+        self.register_helper("code_from_file", Box::new(code_from_file_helper));
+        self.register_helper("csv", Box::new(csv_helper));
+        self.register_helper("list_docs", Box::new(list_docs_block));
+        self.register_helper("markdown", Box::new(markdown_block));
         self.with_base_helpers()
     }
 
     fn with_base_helpers(mut self) -> Self {
         self.register_helper("include", Box::new(include_helper));
         self.register_helper("file", Box::new(file_helper));
+        self.register_helper("each_file", Box::new(each_file_block));
+        self.register_helper("image", Box::new(image_helper));
         self.register_helper("task_id", Box::new(task_id_helper));
         self.register_helper("url_for", Box::new(url_for_helper));
         self.register_helper("gen_par_id", Box::new(gen_par_id_helper));
+        self.register_helper("slugify", Box::new(slugify_helper));
+        self.register_helper("unless_empty", Box::new(unless_empty_block));
+        handlebars_misc_helpers::register(&mut self);
+        self.register_helper("len", Box::new(len_helper));
+        self.register_helper("number", Box::new(number_helper));
+        self.register_helper("partial", Box::new(partial_helper));
         self
     }
 
     fn with_project_templates(mut self, project: &Project) -> anyhow::Result<Self> {
+        for theme in project.config.template_themes.iter() {
+            let theme_folder = Path::new(THEMES_FOLDER).join(theme);
+            if !project.get_root_path().join(&theme_folder).is_dir() {
+                return Err(anyhow::anyhow!(
+                    "Template theme '{}' in `template_themes` of the config was not found in the {} folder",
+                    theme,
+                    THEMES_FOLDER
+                ));
+            }
+            let template_files = project
+                .find_files(&theme_folder, "*")
+                .with_context(|| format!("Could not find templates of theme {}", theme))?;
+            for (name, template) in template_files {
+                let namespaced_name = format!("{}{}{}", theme, THEME_TEMPLATE_SEPARATOR, name);
+                self.register_template_file(&namespaced_name, &template)?;
+                self.register_template_file(&name, template)?;
+            }
+        }
+
         let template_files = project
             .find_files(TEMPLATE_FOLDER, "*")
             .with_context(|| format!("Could not find templates from folder {}", TEMPLATE_FOLDER))?;
@@ -92,10 +161,203 @@ fn with_project_helpers(mut self, project: &Project) -> anyhow::Result<Self> {
             .find_files(HELPERS_FOLDER, "*.rhai")
             .with_context(|| format!("Could not find helpers from folder {}", HELPERS_FOLDER))?;
         for (name, helper) in helper_files {
-            let name = name.trim_end_matches(".rhai");
-            self.register_script_helper_file(&name, helper)?;
+            let helper_path = helper
+                .strip_prefix(project.get_root_path())
+                .unwrap_or(&helper)
+                .display()
+                .to_string();
+            let script = std::fs::read_to_string(&helper)
+                .with_context(|| format!("Could not read helper {}", helper_path))?;
+            // Compile the script first to report syntax errors with the helper file and position
+            if let Err(e) = self.engine().compile(&script) {
+                let position = e.position();
+                let location = match (position.line(), position.position()) {
+                    (Some(line), Some(column)) => format!("{}:{}:{}", helper_path, line, column),
+                    (Some(line), None) => format!("{}:{}", helper_path, line),
+                    _ => helper_path,
+                };
+                return Err(anyhow::anyhow!(
+                    "Syntax error in helper {}: {}",
+                    location,
+                    e.err_type()
+                ));
+            }
+            self.register_script_helper_file(name.trim_end_matches(".rhai"), &helper)
+                .with_context(|| format!("Could not register helper {}", helper_path))?;
         }
 
         Ok(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_project::project;
+    use serde_json::json;
+
+    fn renderers() -> Vec<(&'static str, Handlebars<'static>)> {
+        vec![
+            ("base", Handlebars::new().with_base_helpers()),
+            ("document", Handlebars::new().with_tim_doc_helpers()),
+        ]
+    }
+
+    #[test]
+    fn len_and_number_helpers_are_available_in_both_renderers() {
+        let data = json!({ "docs": [{ "path": "a" }, { "path": "b" }] });
+        for (name, renderer) in renderers() {
+            let result = renderer
+                .render_template(
+                    "{{len docs}} documents, {{number 1234.5 decimals=1}} points",
+                    &data,
+                )
+                .unwrap_or_else(|e| panic!("{} renderer: {}", name, e));
+            assert_eq!(result, "2 documents, 1,234.5 points", "{} renderer", name);
+        }
+    }
+
+    #[test]
+    fn misc_helpers_are_available_in_both_renderers() {
+        for (name, renderer) in renderers() {
+            let result = renderer
+                .render_template(r#"{{to_upper_case "task"}}"#, &json!({}))
+                .unwrap_or_else(|e| panic!("{} renderer: {}", name, e));
+            assert_eq!(result, "TASK", "{} renderer", name);
+        }
+    }
+
+    fn render_with_templates(project: &Project, template: &str) -> String {
+        Handlebars::new()
+            .with_base_helpers()
+            .with_project_templates(project)
+            .unwrap()
+            .render_template(template, &json!({}))
+            .unwrap()
+    }
+
+    #[test]
+    fn project_templates_override_theme_templates() {
+        let (_dir, project) = project(
+            "template_themes = [\"base\"]\n\n[targets]\n",
+            &[
+                ("_themes/base/header.md", "Theme header"),
+                ("_themes/base/footer.md", "Theme footer"),
+                ("_templates/header.md", "Project header"),
+            ],
+        );
+        assert_eq!(
+            render_with_templates(&project, "{{> header.md}} / {{> footer.md}}"),
+            "Project header / Theme footer"
+        );
+    }
+
+    #[test]
+    fn overriding_template_can_include_shadowed_theme_template() {
+        let (_dir, project) = project(
+            "template_themes = [\"base\"]\n\n[targets]\n",
+            &[
+                ("_themes/base/header.md", "Theme header"),
+                (
+                    "_templates/header.md",
+                    "{{partial \"base:header.md\"}} with a logo",
+                ),
+            ],
+        );
+        assert_eq!(
+            render_with_templates(&project, "{{> header.md}}"),
+            "Theme header with a logo"
+        );
+    }
+
+    #[test]
+    fn later_themes_override_earlier_themes() {
+        let (_dir, project) = project(
+            "template_themes = [\"base\", \"course\"]\n\n[targets]\n",
+            &[
+                ("_themes/base/header.md", "Base header"),
+                ("_themes/base/nested/note.md", "Base note"),
+                ("_themes/course/header.md", "Course header"),
+            ],
+        );
+        assert_eq!(
+            render_with_templates(
+                &project,
+                "{{> header.md}} / {{partial \"base:header.md\"}} / {{> nested/note.md}}"
+            ),
+            "Course header / Base header / Base note"
+        );
+    }
+
+    #[test]
+    fn missing_theme_is_an_error() {
+        let (_dir, project) = project("template_themes = [\"missing\"]\n\n[targets]\n", &[]);
+        let error = Handlebars::new()
+            .with_project_templates(&project)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            error.contains("Template theme 'missing'") && error.contains(THEMES_FOLDER),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn partial_helper_renders_with_current_context() {
+        let (_dir, project) = project(
+            "template_themes = [\"base\"]\n\n[targets]\n",
+            &[("_themes/base/greeting.md", "Hello {{name}}")],
+        );
+        let renderer = Handlebars::new()
+            .with_base_helpers()
+            .with_project_templates(&project)
+            .unwrap();
+        let template = r#"{{#each people}}{{partial "base:greeting.md"}}. {{/each}}"#;
+        let data = json!({ "people": [{ "name": "Alice" }, { "name": "Bob" }] });
+        assert_eq!(
+            renderer.render_template(template, &data).unwrap(),
+            "Hello Alice. Hello Bob. "
+        );
+        let error = renderer
+            .render_template(r#"{{partial "base:missing.md"}}"#, &data)
+            .unwrap_err();
+        assert!(error.to_string().contains("base:missing.md"), "{}", error);
+    }
+
+    #[test]
+    fn project_helpers_are_registered() {
+        let (_dir, project) = project(
+            "[targets]\n",
+            &[("_helpers/double.rhai", "params[0] * 2\n")],
+        );
+        let renderer = Handlebars::new()
+            .with_base_helpers()
+            .with_project_helpers(&project)
+            .unwrap();
+        assert_eq!(
+            renderer
+                .render_template("{{double 21}}", &json!({}))
+                .unwrap(),
+            "42"
+        );
+    }
+
+    #[test]
+    fn broken_helper_error_names_file_and_line() {
+        let (_dir, project) = project(
+            "[targets]\n",
+            &[("_helpers/broken.rhai", "let x = 1;\nlet y = ;\n")],
+        );
+        let Err(error) = Handlebars::new().with_project_helpers(&project) else {
+            panic!("Expected a syntax error");
+        };
+        let message = error.to_string();
+        assert!(
+            message.contains("Syntax error in helper _helpers/broken.rhai:2:"),
+            "{}",
+            message
+        );
+    }
+}
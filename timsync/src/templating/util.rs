@@ -9,6 +9,8 @@ pub fn resolve_full_file_path(
     file_path: &str,
     local_project_dir: &str,
 ) -> anyhow::Result<PathBuf, RenderError> {
+    // Accept Windows path separators in the path on all platforms
+    let file_path = file_path.replace('\\', "/");
     let target_file_path = if file_path.starts_with("/") {
         // Absolute path, resolve from project root
         Path::new(local_project_dir).join(&file_path[1..])
@@ -39,6 +41,77 @@ pub fn resolve_full_file_path(
     Ok(target_file_path)
 }
 
+/// Find the files matching a glob pattern, sorted by path.
+///
+/// # Arguments
+///
+/// * `ctx`: The current context, used to resolve patterns relative to the current file.
+/// * `pattern`: The glob pattern, relative to the current file or absolute to the project root.
+/// * `local_project_dir`: The project root directory.
+///
+/// returns: Result<Vec<PathBuf>, RenderError>
+pub fn find_matching_files(
+    ctx: &Context,
+    pattern: &str,
+    local_project_dir: &str,
+) -> Result<Vec<PathBuf>, RenderError> {
+    let full_pattern = resolve_full_file_path(ctx, pattern, local_project_dir)?;
+    let mut files = glob::glob(&full_pattern.to_string_lossy())
+        .map_err(|e| {
+            RenderErrorReason::Other(format!("Invalid glob pattern '{}': {}", pattern, e))
+        })?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+/// Find the single file matching a glob pattern relative to the project root.
+/// Fails if no files or multiple files match the pattern.
+///
+/// # Arguments
+///
+/// * `local_project_dir`: The project root directory.
+/// * `pattern`: The glob pattern, e.g. `assets/**/logo.png`. A leading `/` is ignored.
+///
+/// returns: Result<String, RenderError> The path of the matching file relative to the project root, prefixed with `/`.
+pub fn find_unique_project_file(
+    local_project_dir: &str,
+    pattern: &str,
+) -> anyhow::Result<String, RenderError> {
+    let project_dir = Path::new(local_project_dir);
+    let full_pattern = project_dir.join(pattern.trim_start_matches('/'));
+    let matches = glob::glob(&full_pattern.to_string_lossy())
+        .map_err(|e| {
+            RenderErrorReason::Other(format!("Invalid glob pattern '{}': {}", pattern, e))
+        })?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .map(|path| {
+            path.strip_prefix(project_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace("\\", "/")
+        })
+        .collect::<Vec<_>>();
+
+    match matches.as_slice() {
+        [file] => Ok(format!("/{}", file)),
+        [] => Err(RenderErrorReason::Other(format!(
+            "No files in the project match the pattern '{}'",
+            pattern
+        ))
+        .into()),
+        files => Err(RenderErrorReason::Other(format!(
+            "Multiple files match the pattern '{}': {}. Make the pattern more specific.",
+            pattern,
+            files.join(", ")
+        ))
+        .into()),
+    }
+}
+
 pub fn get_local_project_dir(ctx: &Context) -> anyhow::Result<&str, RenderError> {
     let site_ctx_json = get_site_ctx_json(ctx)?;
     let local_project_dir = site_ctx_json
@@ -77,3 +150,94 @@ pub fn new(write: W) -> WriteOutput<W> {
         WriteOutput { write }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn resolve(file_path: &str) -> PathBuf {
+        let ctx = Context::wraps(json!({ "local_file_path": "week1/intro.md" })).unwrap();
+        resolve_full_file_path(&ctx, file_path, "/project").unwrap()
+    }
+
+    #[test]
+    fn resolves_relative_path() {
+        assert_eq!(
+            resolve("images/chart.png"),
+            Path::new("/project/week1/images/chart.png")
+        );
+        assert_eq!(
+            resolve("../data/scores.csv"),
+            Path::new("/project/data/scores.csv")
+        );
+    }
+
+    #[test]
+    fn resolves_absolute_path_from_project_root() {
+        assert_eq!(
+            resolve("/data/scores.csv"),
+            Path::new("/project/data/scores.csv")
+        );
+    }
+
+    #[test]
+    fn resolves_paths_with_backslashes() {
+        assert_eq!(
+            resolve(r"images\chart.png"),
+            Path::new("/project/week1/images/chart.png")
+        );
+        assert_eq!(
+            resolve(r"..\data/scores.csv"),
+            Path::new("/project/data/scores.csv")
+        );
+        assert_eq!(
+            resolve(r"\data\scores.csv"),
+            Path::new("/project/data/scores.csv")
+        );
+    }
+
+    /// Find a unique file in a temporary project with the given files.
+    fn find_unique(files: &[&str], pattern: &str) -> Result<String, RenderError> {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            crate::util::test_project::write_file(&dir, file, "");
+        }
+        find_unique_project_file(&dir.path().to_string_lossy(), pattern)
+    }
+
+    #[test]
+    fn find_unique_project_file_returns_the_single_match() {
+        assert_eq!(
+            find_unique(
+                &["assets/logos/logo.png", "assets/icon.png"],
+                "assets/**/logo.png"
+            )
+            .unwrap(),
+            "/assets/logos/logo.png"
+        );
+    }
+
+    #[test]
+    fn find_unique_project_file_fails_without_matches() {
+        let error = find_unique(&["assets/icon.png"], "assets/**/logo.png")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("No files in the project match"), "{}", error);
+    }
+
+    #[test]
+    fn find_unique_project_file_lists_ambiguous_matches() {
+        let error = find_unique(
+            &["assets/a/logo.png", "assets/b/logo.png"],
+            "/assets/**/logo.png",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.contains("assets/a/logo.png, assets/b/logo.png"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Format a point in time as an RFC 3339 date and time in UTC, e.g. `2024-05-01T12:30:00Z`.
+///
+/// # Arguments
+///
+/// * `time`: The point in time to format.
+///
+/// returns: Option<String> `None` if the time cannot be represented.
+pub fn format_date_time(time: OffsetDateTime) -> Option<String> {
+    time.replace_nanosecond(0).ok()?.format(&Rfc3339).ok()
+}
+
+/// Get the last modification time of a file from the file system.
+///
+/// # Arguments
+///
+/// * `path`: Path to the file.
+///
+/// returns: Option<String> The modification time formatted with `format_date_time()`,
+/// or `None` if it is not available.
+pub fn file_modified_date(path: &Path) -> Option<String> {
+    let modified: SystemTime = path.metadata().ok()?.modified().ok()?;
+    format_date_time(OffsetDateTime::from(modified))
+}
+
+/// Get the date of the last git commit that changed a file.
+///
+/// # Arguments
+///
+/// * `repo_dir`: A directory inside the git repository, used as the working directory for git.
+/// * `path`: Path to the file.
+///
+/// returns: Option<String> The commit date formatted with `format_date_time()`,
+/// or `None` if git is not available, the file is not in a repository or the file has no commits.
+pub fn git_last_commit_date(repo_dir: &Path, path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("log")
+        .arg("-1")
+        .arg("--format=%ct")
+        .arg("--")
+        .arg(path)
+        .current_dir(repo_dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let timestamp = String::from_utf8(output.stdout)
+        .ok()?
+        .trim()
+        .parse::<i64>()
+        .ok()?;
+    format_date_time(OffsetDateTime::from_unix_timestamp(timestamp).ok()?)
+}
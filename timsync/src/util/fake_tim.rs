@@ -0,0 +1,463 @@
+//! An in-memory fake of a TIM instance for testing the sync pipeline without a server.
+//!
+//! The fake keeps the items (folders and documents) of the instance in memory and implements
+//! the operations of `TimApi` on them. Like TIM, it requires the parent folder of a new item
+//! to exist and be editable, and rejects uploads whose `original` does not match the current document.
+//! The operations that change the instance are recorded so that tests can assert which calls were made.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::util::tim_api::TimApi;
+use crate::util::tim_client::{
+    AliasInfo, DocVersion, DocVersionInfo, ItemInfo, ItemRights, ItemType, TimClientErrors,
+    UploadFileInfo,
+};
+
+/// An item (folder or document) in the fake TIM instance
+#[derive(Clone)]
+pub struct FakeItem {
+    /// Item ID
+    pub id: u64,
+    /// Item type
+    pub item_type: ItemType,
+    /// Item title
+    pub title: String,
+    /// Language ID of the document
+    pub lang_id: Option<String>,
+    /// Markdown of the document, empty for folders
+    pub markdown: String,
+    /// Current version of the document
+    pub version: DocVersion,
+    /// Names of the files uploaded to the document
+    pub uploads: Vec<String>,
+    /// Document settings set with the document settings API
+    pub settings: Map<String, Value>,
+    /// Whether the current user can edit the item, or create items in it if it is a folder
+    pub editable: bool,
+}
+
+#[derive(Default)]
+struct FakeTimState {
+    /// Items keyed by their full path
+    items: BTreeMap<String, FakeItem>,
+    /// Aliases keyed by their full path, mapped to the document IDs
+    aliases: BTreeMap<String, u64>,
+    /// The ID of the last created item
+    last_id: u64,
+    /// The operations that changed the instance, e.g. `create document courses/ohj1/intro`
+    calls: Vec<String>,
+}
+
+impl FakeTimState {
+    fn item(&self, path: &str) -> Result<&FakeItem> {
+        self.items.get(path).ok_or_else(|| {
+            TimClientErrors::ItemNotFound(path.to_string(), "404 Not Found".to_string()).into()
+        })
+    }
+
+    fn document_mut(&mut self, path: &str) -> Result<&mut FakeItem> {
+        let item = self.items.get_mut(path).ok_or_else(|| {
+            TimClientErrors::ItemNotFound(path.to_string(), "404 Not Found".to_string())
+        })?;
+        if item.item_type != ItemType::Document {
+            return Err(TimClientErrors::InvalidItemType(
+                path.to_string(),
+                ItemType::Document.to_string(),
+                item.item_type.to_string(),
+            )
+            .into());
+        }
+        Ok(item)
+    }
+
+    fn path_of(&self, doc_id: u64) -> Result<String> {
+        self.items
+            .iter()
+            .find(|(_, item)| item.id == doc_id)
+            .map(|(path, _)| path.clone())
+            .ok_or_else(|| {
+                TimClientErrors::ItemNotFound(doc_id.to_string(), "404 Not Found".to_string())
+                    .into()
+            })
+    }
+
+    fn insert(&mut self, path: &str, item_type: ItemType, title: &str, markdown: &str) -> u64 {
+        self.last_id += 1;
+        self.items.insert(
+            path.to_string(),
+            FakeItem {
+                id: self.last_id,
+                item_type,
+                title: title.to_string(),
+                lang_id: None,
+                markdown: markdown.to_string(),
+                version: DocVersion(0, 0),
+                uploads: Vec::new(),
+                settings: Map::new(),
+                editable: true,
+            },
+        );
+        self.last_id
+    }
+}
+
+/// An in-memory fake of a TIM instance
+#[derive(Default)]
+pub struct FakeTim {
+    state: Mutex<FakeTimState>,
+}
+
+impl FakeTim {
+    /// Create an empty fake instance.
+    ///
+    /// returns: FakeTim
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a folder to the instance without recording a call, e.g. the sync target folder.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Full path of the folder.
+    ///
+    /// returns: FakeTim
+    pub fn with_folder(self, path: &str) -> Self {
+        let title = path.rsplit('/').next().unwrap_or(path);
+        self.state
+            .lock()
+            .unwrap()
+            .insert(path, ItemType::Folder, title, "");
+        self
+    }
+
+    /// Add a folder in which the current user cannot create items.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Full path of the folder.
+    ///
+    /// returns: FakeTim
+    pub fn with_read_only_folder(self, path: &str) -> Self {
+        let this = self.with_folder(path);
+        this.state
+            .lock()
+            .unwrap()
+            .items
+            .get_mut(path)
+            .unwrap()
+            .editable = false;
+        this
+    }
+
+    /// Add a document to the instance without recording a call.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Full path of the document.
+    /// * `title`: Title of the document.
+    /// * `markdown`: Markdown of the document.
+    ///
+    /// returns: FakeTim
+    pub fn with_document(self, path: &str, title: &str, markdown: &str) -> Self {
+        self.state
+            .lock()
+            .unwrap()
+            .insert(path, ItemType::Document, title, markdown);
+        self
+    }
+
+    /// Get an item of the instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Full path of the item.
+    ///
+    /// returns: Option<FakeItem>
+    pub fn item(&self, path: &str) -> Option<FakeItem> {
+        self.state.lock().unwrap().items.get(path).cloned()
+    }
+
+    /// Replace the markdown of a document, like a user editing the document in TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Full path of the document.
+    /// * `markdown`: New markdown of the document.
+    pub fn edit_markdown(&self, path: &str, markdown: &str) {
+        let mut state = self.state.lock().unwrap();
+        let item = state.document_mut(path).unwrap();
+        item.markdown = markdown.to_string();
+        item.version.1 += 1;
+    }
+
+    /// Get the operations that changed the instance in the order they were made.
+    ///
+    /// returns: Vec<String>
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// Count the recorded operations that start with a prefix, e.g. `upload `.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix`: Prefix of the operations to count.
+    ///
+    /// returns: usize
+    pub fn count(&self, prefix: &str) -> usize {
+        self.calls()
+            .iter()
+            .filter(|call| call.starts_with(prefix))
+            .count()
+    }
+}
+
+impl TimApi for FakeTim {
+    async fn get_item_info(&self, item_path: &str) -> Result<ItemInfo> {
+        let state = self.state.lock().unwrap();
+        let item = state.item(item_path)?;
+        let (location, short_name) = item_path.rsplit_once('/').unwrap_or(("", item_path));
+        Ok(ItemInfo {
+            id: item.id,
+            item_type: item.item_type,
+            title: item.title.clone(),
+            location: location.to_string(),
+            short_name: short_name.to_string(),
+            lang_id: item.lang_id.clone(),
+            rights: Some(ItemRights {
+                editable: item.editable,
+                manage: item.editable,
+                owner: item.editable,
+            }),
+            owners: Vec::new(),
+        })
+    }
+
+    async fn can_edit(&self, item_path: &str) -> Result<bool> {
+        Ok(self.state.lock().unwrap().item(item_path)?.editable)
+    }
+
+    async fn create_or_update_item(
+        &self,
+        item_type: ItemType,
+        path: &str,
+        title: &str,
+        _owner_group: Option<&str>,
+        template: Option<&str>,
+    ) -> Result<(ItemInfo, bool)> {
+        let created = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(item) = state.items.get_mut(path) {
+                if item.item_type != item_type {
+                    return Err(TimClientErrors::InvalidItemType(
+                        path.to_string(),
+                        item_type.to_string(),
+                        item.item_type.to_string(),
+                    )
+                    .into());
+                }
+                if item.title != title {
+                    item.title = title.to_string();
+                    state.calls.push(format!("rename {} {}", item_type, path));
+                }
+                false
+            } else {
+                let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+                if !state.items.contains_key(parent) {
+                    return Err(TimClientErrors::CouldNotCreateItem(
+                        path.to_string(),
+                        "404 Not Found".to_string(),
+                        format!("Parent folder {} does not exist", parent),
+                    )
+                    .into());
+                }
+                if !state.items[parent].editable {
+                    return Err(TimClientErrors::CouldNotCreateItem(
+                        path.to_string(),
+                        "403 Forbidden".to_string(),
+                        "Permission denied".to_string(),
+                    )
+                    .into());
+                }
+                let markdown = match template.filter(|_| item_type == ItemType::Document) {
+                    Some(template) => state.item(template)?.markdown.clone(),
+                    None => String::new(),
+                };
+                state.insert(path, item_type, title, &markdown);
+                state.calls.push(format!("create {} {}", item_type, path));
+                true
+            }
+        };
+        Ok((self.get_item_info(path).await?, created))
+    }
+
+    async fn get_or_create_document(&self, path: &str, title: &str) -> Result<ItemInfo> {
+        if self.item(path).is_some() {
+            return self.get_item_info(path).await;
+        }
+        self.create_or_update_item(ItemType::Document, path, title, None, None)
+            .await
+            .map(|(info, _)| info)
+    }
+
+    async fn set_item_language(&self, item_path: &str, lang_id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.document_mut(item_path)?.lang_id = Some(lang_id.to_string());
+        state
+            .calls
+            .push(format!("language {} {}", item_path, lang_id));
+        Ok(())
+    }
+
+    async fn get_aliases(&self, doc_id: u64) -> Result<Vec<AliasInfo>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .aliases
+            .iter()
+            .filter(|(_, id)| **id == doc_id)
+            .map(|(path, _)| AliasInfo {
+                name: path.clone(),
+                public: true,
+            })
+            .collect())
+    }
+
+    async fn add_alias(&self, doc_id: u64, alias_path: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.aliases.insert(alias_path.to_string(), doc_id);
+        state.calls.push(format!("alias {} {}", doc_id, alias_path));
+        Ok(())
+    }
+
+    async fn remove_alias(&self, alias_path: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.aliases.remove(alias_path);
+        state.calls.push(format!("remove alias {}", alias_path));
+        Ok(())
+    }
+
+    async fn get_document_uploads(&self, item_path: &str) -> Result<Vec<UploadFileInfo>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .item(item_path)?
+            .uploads
+            .iter()
+            .enumerate()
+            .map(|(i, filename)| UploadFileInfo {
+                id: i as u64 + 1,
+                filename: filename.clone(),
+            })
+            .collect())
+    }
+
+    async fn upload_file(&self, item_path: &str, _file_path: &Path, file_name: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .document_mut(item_path)?
+            .uploads
+            .push(file_name.to_string());
+        state
+            .calls
+            .push(format!("upload file {} {}", item_path, file_name));
+        Ok(())
+    }
+
+    async fn try_download_markdown(&self, item_path: &str) -> Result<Option<String>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.items.get(item_path).map(|item| item.markdown.clone()))
+    }
+
+    async fn download_markdown_with_ids(&self, item_path: &str) -> Result<String> {
+        // The fake stores the markdown as given, so the paragraph IDs are kept if there are any
+        let state = self.state.lock().unwrap();
+        Ok(state.item(item_path)?.markdown.clone())
+    }
+
+    async fn get_document_versions(
+        &self,
+        item_path: &str,
+        count: usize,
+    ) -> Result<Vec<DocVersionInfo>> {
+        let state = self.state.lock().unwrap();
+        let version = state.item(item_path)?.version;
+        Ok(std::iter::once(DocVersionInfo {
+            version,
+            time: None,
+        })
+        .take(count)
+        .collect())
+    }
+
+    async fn upload_markdown(
+        &self,
+        item_path: &str,
+        markdown: &str,
+        original: Option<&str>,
+    ) -> Result<Option<DocVersion>> {
+        let mut state = self.state.lock().unwrap();
+        let item = state.document_mut(item_path)?;
+        if original.is_some_and(|original| original != item.markdown) {
+            return Err(TimClientErrors::RemoteModified(item_path.to_string()).into());
+        }
+        item.markdown = markdown.to_string();
+        item.version.1 += 1;
+        let version = item.version;
+        state.calls.push(format!("upload {}", item_path));
+        Ok(Some(version))
+    }
+
+    async fn set_document_settings(
+        &self,
+        doc_id: u64,
+        settings: &Map<String, Value>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let path = state.path_of(doc_id)?;
+        state.document_mut(&path)?.settings = settings.clone();
+        state.calls.push(format!("settings {}", path));
+        Ok(())
+    }
+
+    async fn update_paragraph(&self, doc_id: u64, par_id: &str, content: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let path = state.path_of(doc_id)?;
+        let item = state.document_mut(&path)?;
+
+        // A paragraph starts at a line with its ID and ends at the next line with an ID
+        let is_id_line = |line: &str| {
+            (line.starts_with("#-") || line.starts_with("```")) && line.contains("id=\"")
+        };
+        let lines = item.markdown.split_inclusive('\n').collect::<Vec<_>>();
+        let start = lines
+            .iter()
+            .position(|line| is_id_line(line) && line.contains(&format!("id=\"{}\"", par_id)))
+            .ok_or_else(|| {
+                TimClientErrors::ItemError(
+                    path.clone(),
+                    "404 Not Found".to_string(),
+                    format!("Paragraph {} not found", par_id),
+                )
+            })?;
+        let end = lines[start + 1..]
+            .iter()
+            .position(|line| is_id_line(line))
+            .map_or(lines.len(), |i| start + 1 + i);
+        item.markdown = format!(
+            "{}{}{}",
+            lines[..start].concat(),
+            content,
+            lines[end..].concat()
+        );
+        item.version.1 += 1;
+        state
+            .calls
+            .push(format!("update paragraph {} {}", path, par_id));
+        Ok(())
+    }
+}
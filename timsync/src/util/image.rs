@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use image::ImageReader;
+use lazy_regex::regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// Image dimensions read with `cached_image_dimensions()`, keyed by the content hash of the image
+    static ref DIMENSIONS_CACHE: Mutex<HashMap<String, Option<(u32, u32)>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Read the intrinsic dimensions of an image file.
+///
+/// PNG, GIF and JPEG images are read with the `image` crate, which only decodes the image header.
+/// SVG images are supported if they have `width` and `height` attributes in pixels or a `viewBox` attribute.
+///
+/// # Arguments
+///
+/// * `path`: Path to the image file.
+///
+/// returns: Option<(u32, u32)> The width and height of the image in pixels,
+/// or `None` if the file could not be read or the format is not supported.
+pub fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let bytes = std::fs::read(path).ok()?;
+    raster_dimensions(&bytes).or_else(|| svg_dimensions(std::str::from_utf8(&bytes).ok()?))
+}
+
+/// Read the intrinsic dimensions of an image file, reusing the result for images with the same contents.
+///
+/// # Arguments
+///
+/// * `path`: Path to the image file.
+/// * `content_hash`: Hash of the file contents, e.g. the hashed file name of the uploaded file.
+///
+/// returns: Option<(u32, u32)>
+pub fn cached_image_dimensions(path: &Path, content_hash: &str) -> Option<(u32, u32)> {
+    if let Some(dimensions) = DIMENSIONS_CACHE.lock().unwrap().get(content_hash) {
+        return *dimensions;
+    }
+    let dimensions = image_dimensions(path);
+    DIMENSIONS_CACHE
+        .lock()
+        .unwrap()
+        .insert(content_hash.to_string(), dimensions);
+    dimensions
+}
+
+/// Load image dimensions saved with `save_dimensions_cache()` into the dimensions cache.
+/// Missing or unreadable cache files are ignored, since the dimensions can always be read again.
+///
+/// # Arguments
+///
+/// * `cache_file`: Path to the cache file.
+pub fn load_dimensions_cache(cache_file: &Path) {
+    let Some(saved) = std::fs::read_to_string(cache_file)
+        .ok()
+        .and_then(|contents| {
+            serde_json::from_str::<HashMap<String, Option<(u32, u32)>>>(&contents).ok()
+        })
+    else {
+        return;
+    };
+    let mut cache = DIMENSIONS_CACHE.lock().unwrap();
+    for (content_hash, dimensions) in saved {
+        cache.entry(content_hash).or_insert(dimensions);
+    }
+}
+
+/// Save the dimensions cache to a file so that it can be reused in later runs.
+///
+/// # Arguments
+///
+/// * `cache_file`: Path to the cache file.
+///
+/// returns: Result<(), Error>
+pub fn save_dimensions_cache(cache_file: &Path) -> Result<()> {
+    let contents = serde_json::to_string(&*DIMENSIONS_CACHE.lock().unwrap())?;
+    std::fs::write(cache_file, contents)
+        .with_context(|| format!("Could not write the cache file {}", cache_file.display()))
+}
+
+/// Read the dimensions of a raster image from its header.
+/// The format is detected from the contents, so the file extension does not matter.
+fn raster_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Read the dimensions of an SVG image from the attributes of the root element.
+/// The `width` and `height` attributes are used if both are given in pixels, otherwise the `viewBox` is used.
+fn svg_dimensions(source: &str) -> Option<(u32, u32)> {
+    let svg_tag = regex!(r"(?s)<svg\b[^>]*>").find(source)?.as_str();
+    let attribute = |name: &str| {
+        regex!(r#"(?s)\s([\w:-]+)\s*=\s*["']([^"']*)["']"#)
+            .captures_iter(svg_tag)
+            .find(|captures| &captures[1] == name)
+            .map(|captures| captures[2].trim().to_string())
+    };
+    let pixels = |value: String| {
+        value
+            .strip_suffix("px")
+            .unwrap_or(&value)
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| *v > 0.0)
+    };
+
+    if let (Some(width), Some(height)) = (
+        attribute("width").and_then(pixels),
+        attribute("height").and_then(pixels),
+    ) {
+        return Some((width.round() as u32, height.round() as u32));
+    }
+
+    let view_box = attribute("viewBox")?
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match view_box[..] {
+        [_, _, width, height] if width > 0.0 && height > 0.0 => {
+            Some((width.round() as u32, height.round() as u32))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Encode a blank 3×2 image in the given format.
+    fn encoded_image(format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        RgbImage::new(3, 2).write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    /// Write a file into the directory and read its dimensions.
+    fn dimensions_of(dir: &TempDir, name: &str, contents: &[u8]) -> Option<(u32, u32)> {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).unwrap();
+        image_dimensions(&path)
+    }
+
+    #[test]
+    fn reads_raster_image_dimensions() {
+        let dir = TempDir::new().unwrap();
+        for (name, format) in [
+            ("image.png", ImageFormat::Png),
+            ("image.jpg", ImageFormat::Jpeg),
+            ("image.gif", ImageFormat::Gif),
+        ] {
+            let bytes = encoded_image(format);
+            assert_eq!(dimensions_of(&dir, name, &bytes), Some((3, 2)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn detects_format_from_contents() {
+        let dir = TempDir::new().unwrap();
+        let bytes = encoded_image(ImageFormat::Png);
+        assert_eq!(dimensions_of(&dir, "image.jpg", &bytes), Some((3, 2)));
+    }
+
+    #[test]
+    fn truncated_raster_images_have_no_dimensions() {
+        let dir = TempDir::new().unwrap();
+        for (name, format) in [
+            ("image.png", ImageFormat::Png),
+            ("image.jpg", ImageFormat::Jpeg),
+            ("image.gif", ImageFormat::Gif),
+        ] {
+            let bytes = encoded_image(format);
+            // Cut the image in the middle of the header
+            assert_eq!(dimensions_of(&dir, name, &bytes[..8]), None, "{}", name);
+        }
+        assert_eq!(dimensions_of(&dir, "empty.png", &[]), None);
+    }
+
+    #[test]
+    fn reads_svg_size_attributes() {
+        assert_eq!(
+            svg_dimensions(r#"<svg xmlns="http://www.w3.org/2000/svg" width="120" height="80px">"#),
+            Some((120, 80))
+        );
+        assert_eq!(
+            svg_dimensions("<?xml version=\"1.0\"?>\n<svg\n  width='10.6'\n  height='20'>"),
+            Some((11, 20))
+        );
+    }
+
+    #[test]
+    fn reads_svg_view_box() {
+        assert_eq!(
+            svg_dimensions(r#"<svg viewBox="0 0 300 150">"#),
+            Some((300, 150))
+        );
+        // Sizes in other units than pixels fall back to the view box
+        assert_eq!(
+            svg_dimensions(r#"<svg width="10em" height="5em" viewBox="0,0,40,20">"#),
+            Some((40, 20))
+        );
+    }
+
+    #[test]
+    fn svg_without_size_has_no_dimensions() {
+        assert_eq!(svg_dimensions(r#"<svg width="100%">"#), None);
+        assert_eq!(svg_dimensions(r#"<svg viewBox="0 0 300">"#), None);
+        assert_eq!(svg_dimensions("<html></html>"), None);
+    }
+
+    #[test]
+    fn reads_svg_file_dimensions() {
+        let dir = TempDir::new().unwrap();
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 32"><rect/></svg>"#;
+        assert_eq!(dimensions_of(&dir, "diagram.svg", svg), Some((64, 32)));
+        // Truncated before the end of the root element
+        assert_eq!(dimensions_of(&dir, "truncated.svg", &svg[..20]), None);
+    }
+}
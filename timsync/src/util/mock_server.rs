@@ -0,0 +1,297 @@
+//! A minimal HTTP server for testing `TimClient` against canned TIM responses.
+//!
+//! The server answers each request with the response returned by a handler function
+//! and records the requests so that tests can assert which calls were made.
+//! Only requests with a `Content-Length` body are supported, and each connection is closed
+//! after a single response.
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::util::tim_client::TimClient;
+
+/// A request received by the mock server
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    /// HTTP method, e.g. `GET`
+    pub method: String,
+    /// Path and query of the request, e.g. `/itemInfo/courses/ohj1`
+    pub path: String,
+    /// Request headers with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    /// Get the value of a request header.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Header name in lowercase, e.g. `accept-encoding`.
+    ///
+    /// returns: Option<&str>
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parse the request body as JSON.
+    ///
+    /// returns: Value
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+/// A response sent by the mock server
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl MockResponse {
+    /// Create a response with an empty body.
+    ///
+    /// # Arguments
+    ///
+    /// * `status`: HTTP status code.
+    ///
+    /// returns: MockResponse
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Create a response with a text body.
+    ///
+    /// # Arguments
+    ///
+    /// * `status`: HTTP status code.
+    /// * `body`: Response body.
+    ///
+    /// returns: MockResponse
+    pub fn text(status: u16, body: &str) -> Self {
+        Self::status(status).with_body(body.as_bytes().to_vec())
+    }
+
+    /// Create a successful response with a JSON body.
+    ///
+    /// # Arguments
+    ///
+    /// * `body`: Response body.
+    ///
+    /// returns: MockResponse
+    pub fn json(body: Value) -> Self {
+        Self::json_status(200, body)
+    }
+
+    /// Create a response with a JSON body.
+    ///
+    /// # Arguments
+    ///
+    /// * `status`: HTTP status code.
+    /// * `body`: Response body.
+    ///
+    /// returns: MockResponse
+    pub fn json_status(status: u16, body: Value) -> Self {
+        Self::text(status, &body.to_string()).with_header("Content-Type", "application/json")
+    }
+
+    /// Add a header to the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Header name.
+    /// * `value`: Header value.
+    ///
+    /// returns: MockResponse
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Replace the body of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `body`: Response body.
+    ///
+    /// returns: MockResponse
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+type Handler = dyn Fn(&MockRequest) -> MockResponse + Send + Sync;
+
+/// A mock HTTP server running in the background of a test
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Start a mock server on a free local port.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler`: Function returning the response to each request.
+    ///
+    /// returns: MockServer
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let task_requests = requests.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let requests = task_requests.clone();
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, requests, handler).await;
+                });
+            }
+        });
+
+        Self {
+            url,
+            requests,
+            task,
+        }
+    }
+
+    /// Get the base URL of the server, e.g. `http://127.0.0.1:12345`.
+    ///
+    /// returns: &str
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the requests received so far in the order they were received.
+    ///
+    /// returns: Vec<MockRequest>
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Count the received requests with a method and a path starting with a prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: HTTP method, e.g. `PUT`.
+    /// * `path_prefix`: Prefix of the request path, e.g. `/changeTitle/`.
+    ///
+    /// returns: usize
+    pub fn count(&self, method: &str, path_prefix: &str) -> usize {
+        self.requests()
+            .iter()
+            .filter(|request| request.method == method && request.path.starts_with(path_prefix))
+            .count()
+    }
+
+    /// Create a TIM client that sends its requests to the server.
+    /// The client is not logged in and has no XSRF token.
+    ///
+    /// returns: TimClient
+    pub fn client(&self) -> TimClient {
+        let client = Client::builder().cookie_store(true).build().unwrap();
+        TimClient::new(self.url.clone(), client)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Read a single request from the connection, record it and write the response.
+async fn handle_connection(
+    mut stream: TcpStream,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    handler: Arc<Handler>,
+) -> std::io::Result<()> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        data.extend_from_slice(&buffer[..read]);
+        if let Some(pos) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect::<Vec<_>>();
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while data.len() < header_end + content_length {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+
+    let body_end = data.len().min(header_end + content_length);
+    let request = MockRequest {
+        method,
+        path,
+        headers,
+        body: data[header_end..body_end].to_vec(),
+    };
+    let response = handler(&request);
+    requests.lock().unwrap().push(request);
+
+    let reason = StatusCode::from_u16(response.status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Unknown");
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason,
+        response.body.len()
+    );
+    for (name, value) in response.headers.iter() {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.shutdown().await
+}
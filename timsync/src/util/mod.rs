@@ -1,3 +1,13 @@
+pub mod date;
+#[cfg(test)]
+pub mod fake_tim;
+pub mod image;
 pub mod json;
+#[cfg(test)]
+pub mod mock_server;
 pub mod path;
+#[cfg(test)]
+pub mod test_project;
+pub mod text;
+pub mod tim_api;
 pub mod tim_client;
@@ -165,3 +165,40 @@ pub fn generate_hashed_filename(target_file_path: &PathBuf) -> anyhow::Result<St
 
     Ok(format!("{}{}", file_sha1, file_ext))
 }
+
+/// Guess the MIME type of a file from its extension.
+/// Files with unknown or missing extensions are `application/octet-stream`.
+///
+/// # Arguments
+///
+/// * `path`: Path or name of the file, e.g. `images/diagram.png`.
+///
+/// returns: String The MIME type, e.g. `image/png`.
+pub fn guess_mime_type(path: impl AsRef<Path>) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guess_mime_type_from_extension() {
+        assert_eq!(guess_mime_type("images/diagram.png"), "image/png");
+        assert_eq!(guess_mime_type("photo.JPG"), "image/jpeg");
+        assert_eq!(guess_mime_type("slides.pdf"), "application/pdf");
+        assert_eq!(guess_mime_type("data/scores.csv"), "text/csv");
+        assert_eq!(guess_mime_type("icon.svg"), "image/svg+xml");
+    }
+
+    #[test]
+    fn guess_mime_type_defaults_to_octet_stream() {
+        assert_eq!(
+            guess_mime_type("archive.unknownext"),
+            "application/octet-stream"
+        );
+        assert_eq!(guess_mime_type("Makefile"), "application/octet-stream");
+    }
+}
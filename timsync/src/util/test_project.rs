@@ -0,0 +1,51 @@
+//! Temporary projects for tests that need a project on disk.
+//!
+//! The projects are created in a temporary directory that is removed when the returned `TempDir` is dropped,
+//! so the directory must be kept alive for as long as the project is used.
+
+use std::fs;
+use std::path::Path;
+
+use tempfile::TempDir;
+
+use crate::project::config::{CONFIG_FILE_NAME, CONFIG_FOLDER};
+use crate::project::project::Project;
+
+/// Create a project with the given config and files in a temporary directory.
+///
+/// # Arguments
+///
+/// * `config`: Contents of the config file `.timsync/config.toml`
+/// * `files`: Paths relative to the project root and the contents of the files to create
+///
+/// returns: (TempDir, Project)
+pub fn project(config: &str, files: &[(&str, &str)]) -> (TempDir, Project) {
+    // The default temporary directory names start with a dot, which would hide the whole project
+    let dir = tempfile::Builder::new()
+        .prefix("project")
+        .tempdir()
+        .unwrap();
+    write_file(
+        &dir,
+        &format!("{}/{}", CONFIG_FOLDER, CONFIG_FILE_NAME),
+        config,
+    );
+    for (name, contents) in files {
+        write_file(&dir, name, contents);
+    }
+    let project = Project::resolve_from_directory(dir.path(), None).unwrap();
+    (dir, project)
+}
+
+/// Write a file into the project directory, creating the missing parent folders.
+///
+/// # Arguments
+///
+/// * `dir`: The project directory
+/// * `name`: Path of the file relative to the project directory
+/// * `contents`: Contents of the file
+pub fn write_file(dir: impl AsRef<Path>, name: &str, contents: &str) {
+    let path = dir.as_ref().join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
@@ -0,0 +1,182 @@
+use lazy_regex::regex;
+use sha1::{Digest, Sha1};
+
+/// Average reading speed used to estimate the reading time, in words per minute.
+pub const READING_WORDS_PER_MINUTE: usize = 200;
+
+/// Count the words in a document source.
+///
+/// Template expressions (`{{...}}`) are not counted, and only tokens that contain
+/// at least one alphanumeric character are counted as words.
+/// Because the count is calculated from the source and not from the rendered document,
+/// any content inserted by the templates (e.g., includes or tasks) is not counted.
+///
+/// # Arguments
+///
+/// * `source`: The document source to count the words of.
+///
+/// returns: usize
+pub fn count_words(source: &str) -> usize {
+    let template_re = regex!(r"\{\{[\s\S]*?\}\}");
+    template_re
+        .replace_all(source, " ")
+        .split_whitespace()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Estimate the reading time of a text in whole minutes.
+/// Non-empty texts take at least one minute to read.
+///
+/// # Arguments
+///
+/// * `word_count`: The number of words in the text.
+///
+/// returns: usize
+pub fn reading_minutes(word_count: usize) -> usize {
+    word_count.div_ceil(READING_WORDS_PER_MINUTE)
+}
+
+/// Calculate the SHA1 hash of a text as a hexadecimal string.
+///
+/// # Arguments
+///
+/// * `text`: The text to hash.
+///
+/// returns: String
+pub fn sha1_hex(text: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Calculate the edit (Levenshtein) distance between two strings,
+/// i.e. the number of single-character insertions, deletions and substitutions
+/// needed to change one string into the other.
+///
+/// # Arguments
+///
+/// * `a`: The first string.
+/// * `b`: The second string.
+///
+/// returns: usize
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    // Distances from the prefix of `a` processed so far to each prefix of `b`
+    let mut distances = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_diagonal + usize::from(a_char != *b_char);
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution
+                .min(distances[j] + 1)
+                .min(previous_diagonal + 1);
+        }
+    }
+    distances[b.len()]
+}
+
+/// Convert a file name slug into a human-readable title.
+///
+/// Dashes and underscores are replaced with spaces and the first letter of each word is capitalized.
+/// The rest of each word is kept as is, so that acronyms like `API` are not changed.
+///
+/// Example: `intro-to-rust` becomes `Intro To Rust`.
+///
+/// # Arguments
+///
+/// * `slug`: The slug to convert, e.g. the file stem of a document.
+///
+/// returns: String
+pub fn humanize_title(slug: &str) -> String {
+    slug.split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_words_counts_whitespace_separated_words() {
+        assert_eq!(count_words("Hello world,\nthis is\ta test."), 6);
+        assert_eq!(count_words(""), 0);
+    }
+
+    #[test]
+    fn count_words_ignores_template_expressions() {
+        assert_eq!(count_words("Hello {{ name }}!"), 1);
+        assert_eq!(count_words("{{#if x}}\nOne two\n{{/if}}"), 2);
+        assert_eq!(
+            count_words("{{include \"a.md\"\n  separator=\" \"}} word"),
+            1
+        );
+    }
+
+    #[test]
+    fn count_words_ignores_tokens_without_letters_or_digits() {
+        assert_eq!(count_words("# Title\n\n- item 1\n---\n* * *"), 3);
+    }
+
+    #[test]
+    fn reading_minutes_rounds_up() {
+        assert_eq!(reading_minutes(0), 0);
+        assert_eq!(reading_minutes(1), 1);
+        assert_eq!(reading_minutes(READING_WORDS_PER_MINUTE), 1);
+        assert_eq!(reading_minutes(READING_WORDS_PER_MINUTE + 1), 2);
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("prod", "prod"), 0);
+        assert_eq!(edit_distance("prod", "prd"), 1);
+        assert_eq!(edit_distance("prod", "prods"), 1);
+        assert_eq!(edit_distance("prod", "prad"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn edit_distance_counts_characters_not_bytes() {
+        assert_eq!(edit_distance("sää", "saa"), 2);
+    }
+
+    #[test]
+    fn humanize_title_replaces_separators_and_capitalizes_words() {
+        assert_eq!(humanize_title("intro-to-rust"), "Intro To Rust");
+        assert_eq!(humanize_title("week_1_exercises"), "Week 1 Exercises");
+        assert_eq!(
+            humanize_title("mixed-separators_here"),
+            "Mixed Separators Here"
+        );
+    }
+
+    #[test]
+    fn humanize_title_collapses_repeated_separators() {
+        assert_eq!(humanize_title("--lecture__notes--"), "Lecture Notes");
+        assert_eq!(humanize_title(""), "");
+    }
+
+    #[test]
+    fn humanize_title_keeps_the_rest_of_each_word() {
+        assert_eq!(humanize_title("REST-API-basics"), "REST API Basics");
+        assert_eq!(humanize_title("iPhone-apps"), "IPhone Apps");
+    }
+
+    #[test]
+    fn humanize_title_capitalizes_non_ascii_letters() {
+        assert_eq!(humanize_title("äidinkieli-ja-öljy"), "Äidinkieli Ja Öljy");
+    }
+}
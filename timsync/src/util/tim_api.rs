@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::util::tim_client::{
+    AliasInfo, DocVersion, DocVersionInfo, ItemInfo, ItemType, TimClient, UploadFileInfo,
+};
+
+/// Operations on a TIM instance used by the sync pipeline.
+///
+/// The pipeline depends on this trait instead of `TimClient` directly,
+/// so that it can be run against other implementations, e.g. an in-memory fake.
+/// See `TimClient` for the documentation of each operation.
+pub trait TimApi {
+    /// Get information about an item (document or folder) in TIM.
+    async fn get_item_info(&self, item_path: &str) -> Result<ItemInfo>;
+
+    /// Check whether the current user can edit an item, or create items in it if it is a folder.
+    async fn can_edit(&self, item_path: &str) -> Result<bool>;
+
+    /// Create a new item (document or folder) in TIM, or update the title if it already exists.
+    /// Returns the item information and whether the item was created.
+    async fn create_or_update_item(
+        &self,
+        item_type: ItemType,
+        path: &str,
+        title: &str,
+        owner_group: Option<&str>,
+        template: Option<&str>,
+    ) -> Result<(ItemInfo, bool)>;
+
+    /// Get information about a document in TIM, creating the document if it does not exist.
+    async fn get_or_create_document(&self, path: &str, title: &str) -> Result<ItemInfo>;
+
+    /// Set the language of a document in TIM.
+    async fn set_item_language(&self, item_path: &str, lang_id: &str) -> Result<()>;
+
+    /// Get the aliases of a document in TIM.
+    async fn get_aliases(&self, doc_id: u64) -> Result<Vec<AliasInfo>>;
+
+    /// Add an alias to a document in TIM.
+    async fn add_alias(&self, doc_id: u64, alias_path: &str) -> Result<()>;
+
+    /// Remove a document alias from TIM.
+    async fn remove_alias(&self, alias_path: &str) -> Result<()>;
+
+    /// Get a list of uploaded files in a document in TIM.
+    async fn get_document_uploads(&self, item_path: &str) -> Result<Vec<UploadFileInfo>>;
+
+    /// Upload a file to a document in TIM.
+    async fn upload_file(&self, item_path: &str, file_path: &Path, file_name: &str) -> Result<()>;
+
+    /// Download the markdown contents of a document in TIM if the document exists.
+    async fn try_download_markdown(&self, item_path: &str) -> Result<Option<String>>;
+
+    /// Download the markdown contents of a document in TIM with the IDs of the paragraphs.
+    async fn download_markdown_with_ids(&self, item_path: &str) -> Result<String>;
+
+    /// Get the latest versions of a document in TIM.
+    async fn get_document_versions(
+        &self,
+        item_path: &str,
+        count: usize,
+    ) -> Result<Vec<DocVersionInfo>>;
+
+    /// Upload markdown contents to a document in TIM.
+    async fn upload_markdown(
+        &self,
+        item_path: &str,
+        markdown: &str,
+        original: Option<&str>,
+    ) -> Result<Option<DocVersion>>;
+
+    /// Set the document settings of a document in TIM.
+    async fn set_document_settings(&self, doc_id: u64, settings: &Map<String, Value>)
+        -> Result<()>;
+
+    /// Replace the contents of a single paragraph in a document in TIM.
+    async fn update_paragraph(&self, doc_id: u64, par_id: &str, content: &str) -> Result<()>;
+}
+
+impl TimApi for TimClient {
+    async fn get_item_info(&self, item_path: &str) -> Result<ItemInfo> {
+        TimClient::get_item_info(self, item_path).await
+    }
+
+    async fn can_edit(&self, item_path: &str) -> Result<bool> {
+        TimClient::can_edit(self, item_path).await
+    }
+
+    async fn create_or_update_item(
+        &self,
+        item_type: ItemType,
+        path: &str,
+        title: &str,
+        owner_group: Option<&str>,
+        template: Option<&str>,
+    ) -> Result<(ItemInfo, bool)> {
+        TimClient::create_or_update_item(self, item_type, path, title, owner_group, template).await
+    }
+
+    async fn get_or_create_document(&self, path: &str, title: &str) -> Result<ItemInfo> {
+        TimClient::get_or_create_document(self, path, title).await
+    }
+
+    async fn set_item_language(&self, item_path: &str, lang_id: &str) -> Result<()> {
+        TimClient::set_item_language(self, item_path, lang_id).await
+    }
+
+    async fn get_aliases(&self, doc_id: u64) -> Result<Vec<AliasInfo>> {
+        TimClient::get_aliases(self, doc_id).await
+    }
+
+    async fn add_alias(&self, doc_id: u64, alias_path: &str) -> Result<()> {
+        TimClient::add_alias(self, doc_id, alias_path).await
+    }
+
+    async fn remove_alias(&self, alias_path: &str) -> Result<()> {
+        TimClient::remove_alias(self, alias_path).await
+    }
+
+    async fn get_document_uploads(&self, item_path: &str) -> Result<Vec<UploadFileInfo>> {
+        TimClient::get_document_uploads(self, item_path).await
+    }
+
+    async fn upload_file(&self, item_path: &str, file_path: &Path, file_name: &str) -> Result<()> {
+        TimClient::upload_file(self, item_path, file_path, file_name).await
+    }
+
+    async fn try_download_markdown(&self, item_path: &str) -> Result<Option<String>> {
+        TimClient::try_download_markdown(self, item_path).await
+    }
+
+    async fn download_markdown_with_ids(&self, item_path: &str) -> Result<String> {
+        TimClient::download_markdown_with_ids(self, item_path).await
+    }
+
+    async fn get_document_versions(
+        &self,
+        item_path: &str,
+        count: usize,
+    ) -> Result<Vec<DocVersionInfo>> {
+        TimClient::get_document_versions(self, item_path, count).await
+    }
+
+    async fn upload_markdown(
+        &self,
+        item_path: &str,
+        markdown: &str,
+        original: Option<&str>,
+    ) -> Result<Option<DocVersion>> {
+        TimClient::upload_markdown(self, item_path, markdown, original).await
+    }
+
+    async fn set_document_settings(
+        &self,
+        doc_id: u64,
+        settings: &Map<String, Value>,
+    ) -> Result<()> {
+        TimClient::set_document_settings(self, doc_id, settings).await
+    }
+
+    async fn update_paragraph(&self, doc_id: u64, par_id: &str, content: &str) -> Result<()> {
+        TimClient::update_paragraph(self, doc_id, par_id, content).await
+    }
+}
@@ -1,3 +1,4 @@
+use crate::util::path::guess_mime_type;
 use anyhow::{Context, Result};
 use rand::Rng;
 use rand_seeder::Seeder;
@@ -5,18 +6,63 @@
 use rand_xoshiro::Xoroshiro128PlusPlus;
 use reqwest::multipart::{Form, Part};
 use reqwest::{Body, Client, ClientBuilder, RequestBuilder};
-use serde::Deserialize;
-use serde_json::json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use simplelog::debug;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
 
+/// Maximum number of characters of a TIM error response included in error messages
+const MAX_ERROR_TEXT_LENGTH: usize = 500;
+
+/// Access type ID of the owner right in TIM
+const OWNER_ACCESS_TYPE: u64 = 6;
+
 /// TIM API client
 pub struct TimClient {
     client: Client,
     tim_host: String,
     xsrf_token: String,
+    /// API token sent as a bearer token with every request, set with `login_token()`
+    auth_token: Option<String>,
+    /// Item information prefetched with `prefetch_folder_items()`, keyed by item path
+    prefetched_items: RwLock<HashMap<String, ItemInfo>>,
+    /// Whether the current user can edit an item, keyed by item path. See `can_edit()`.
+    edit_rights: RwLock<HashMap<String, bool>>,
+    /// Markdown downloaded with `download_markdown()` and the document ID, keyed by document path.
+    /// Invalidated when the document is modified through the client.
+    markdown_cache: RwLock<HashMap<String, (u64, String)>>,
+}
+
+/// Read the body of a failed TIM response for an error message.
+/// If the body is a JSON object with an `error` field, only the field is returned.
+/// Long bodies (e.g. HTML error pages) are truncated.
+///
+/// # Arguments
+///
+/// * `response`: The failed response.
+///
+/// returns: String
+async fn response_error_text(response: reqwest::Response) -> String {
+    let text = match response.text().await {
+        Ok(text) if !text.trim().is_empty() => text,
+        _ => return "<none>".to_string(),
+    };
+    let text = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|json| json.get("error").and_then(|e| e.as_str()).map(String::from))
+        .unwrap_or(text);
+    let text = text.trim();
+
+    match text.char_indices().nth(MAX_ERROR_TEXT_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
 }
 
 #[derive(Error, Debug)]
@@ -25,22 +71,34 @@ pub enum TimClientErrors {
     NoXsrfToken,
     #[error("No TIM host given")]
     NoHost,
-    #[error("Invalid username or password for basic login. Server responded with: {0}")]
-    InvalidLogin(String),
+    #[error("{0} does not appear to be a TIM server: {1}. Check the host of the sync target.")]
+    NotTimServer(String, String),
+    #[error("Invalid username or password for basic login. Server responded with: {0}: {1}")]
+    InvalidLogin(String, String),
     #[error("Item not found from {0}: {1}")]
     ItemNotFound(String, String),
-    #[error("Could not create item {0}: {1}")]
-    CouldNotCreateItem(String, String),
+    #[error("Could not create item {0}: {1}. Server answered with: {2}")]
+    CouldNotCreateItem(String, String, String),
     #[error("Item {0} is not a {1}, but a {2}")]
     InvalidItemType(String, String, String),
     #[error("Failed to process {0}: {1}. Server answered with: {2}")]
     ItemError(String, String, String),
     #[error("File not found: {0}")]
     FileNotFound(String),
+    #[error("Could not get information about the current user. Server responded with: {0}: {1}")]
+    CouldNotGetCurrentUser(String, String),
+    #[error("Document {0} was modified in TIM while it was being updated")]
+    RemoteModified(String),
+    #[error("The API token was not accepted: {0}")]
+    InvalidToken(String),
+    #[error("Could not create item {0}: the current user has no edit rights to the folder {1}. Grant the user at least edit rights to the folder in TIM.")]
+    NoEditRights(String, String),
+    #[error("Could not make group {1} the owner of {0}. Make sure the syncing account has the right to transfer the ownership. Server responded with: {2}")]
+    CouldNotSetOwner(String, String, String),
 }
 
 /// Information about a TIM item (e.g., document or folder)
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 #[allow(dead_code)]
 pub struct ItemInfo {
     /// Item ID
@@ -62,6 +120,51 @@ pub struct ItemInfo {
 
     /// Language ID of the item if it is a document and has a language set
     pub lang_id: Option<String>,
+
+    /// Rights of the current user to the item, if reported by TIM
+    pub rights: Option<ItemRights>,
+
+    /// User groups that own the item, if reported by TIM
+    #[serde(default)]
+    pub owners: Vec<GroupInfo>,
+}
+
+impl ItemInfo {
+    /// Get the full path to the item in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: String
+    pub fn path(&self) -> String {
+        if self.location.is_empty() {
+            self.short_name.clone()
+        } else {
+            format!("{}/{}", self.location, self.short_name)
+        }
+    }
+}
+
+/// Rights of the current user to a TIM item
+#[derive(Deserialize, Clone)]
+#[allow(dead_code)]
+pub struct ItemRights {
+    /// Whether the user can edit the item (or create items in it, if it is a folder)
+    #[serde(default)]
+    pub editable: bool,
+    /// Whether the user can manage the item
+    #[serde(default)]
+    pub manage: bool,
+    /// Whether the user owns the item
+    #[serde(default)]
+    pub owner: bool,
+}
+
+/// Information about a TIM user group
+#[derive(Deserialize, Clone)]
+#[allow(dead_code)]
+pub struct GroupInfo {
+    /// Group ID
+    pub id: u64,
+    /// Group name
+    pub name: String,
 }
 
 /// Information about a file uploaded to TIM
@@ -74,7 +177,74 @@ pub struct UploadFileInfo {
     pub filename: String,
 }
 
-#[derive(Deserialize, PartialEq)]
+/// Information about a document alias in TIM
+#[derive(Deserialize)]
+#[allow(dead_code)]
+pub struct AliasInfo {
+    /// Full path of the alias, e.g. `kurssit/tie/kurssi/vanha`
+    #[serde(alias = "path")]
+    pub name: String,
+    /// Whether the alias is visible in the folder listing
+    #[serde(default)]
+    pub public: bool,
+}
+
+/// Version of a TIM document as a `[major, minor]` pair
+/// TIM creates a new version of the document on every edit.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DocVersion(pub u64, pub u64);
+
+impl std::fmt::Display for DocVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.0, self.1)
+    }
+}
+
+/// Information about a single version of a TIM document
+#[derive(Deserialize)]
+#[allow(dead_code)]
+pub struct DocVersionInfo {
+    /// The document version
+    #[serde(rename = "ver")]
+    pub version: DocVersion,
+    /// Time of the change that created the version
+    pub time: Option<String>,
+}
+
+/// Changelog of a TIM document, as returned by TIM when the document is changed
+#[derive(Deserialize)]
+struct DocChangelog {
+    /// Versions of the document, newest first
+    #[serde(default)]
+    versions: Vec<DocVersionInfo>,
+}
+
+/// Information about a TIM user
+#[derive(Deserialize)]
+#[allow(dead_code)]
+pub struct UserInfo {
+    /// User ID
+    pub id: u64,
+    /// Username
+    pub name: String,
+    /// User's full name if set
+    pub real_name: Option<String>,
+    /// User's primary email address if set
+    pub email: Option<String>,
+}
+
+/// Information about a TIM server, as reported by its status endpoint
+#[derive(Deserialize)]
+#[allow(dead_code)]
+pub struct ServerInfo {
+    /// Status of the server, `ok` if the server is running
+    pub status: String,
+    /// Version of TIM, if reported by the server
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 // TIM item type
 pub enum ItemType {
@@ -107,13 +277,18 @@ impl TimClient {
     /// # Arguments
     ///
     /// * `tim_host`: TIM host URL, e.g. `https://tim.jyu.fi`
+    /// * `client`: HTTP client to use for the requests. The client must have a cookie store enabled.
     ///
     /// returns: TimClient
-    pub fn new(tim_host: String) -> Self {
+    pub fn new(tim_host: String, client: Client) -> Self {
         Self {
-            client: ClientBuilder::new().cookie_store(true).build().unwrap(),
+            client,
             tim_host,
             xsrf_token: String::new(),
+            auth_token: None,
+            prefetched_items: RwLock::new(HashMap::new()),
+            edit_rights: RwLock::new(HashMap::new()),
+            markdown_cache: RwLock::new(HashMap::new()),
         }
     }
 
@@ -123,18 +298,57 @@ pub fn new(tim_host: String) -> Self {
     /// Usually, calling this method once is enough before any other calls,
     /// as the same CSRF token can be reused for multiple calls.
     pub async fn refresh_xsrf_token(&mut self) -> Result<()> {
-        let result = self.client.get(&self.tim_host).send().await?;
+        debug!("Refreshing XSRF token from {}", &self.tim_host);
+        let result = self
+            .client
+            .get(&self.tim_host)
+            .send()
+            .await
+            .with_context(|| format!("Could not connect to {}", self.tim_host))?;
 
         self.xsrf_token = result
             .cookies()
             .find(|c| c.name() == "XSRF-TOKEN")
-            .unwrap()
+            .ok_or_else(|| {
+                TimClientErrors::NotTimServer(
+                    self.tim_host.clone(),
+                    "the server did not set an XSRF token".to_string(),
+                )
+            })?
             .value()
             .to_string();
 
         Ok(())
     }
 
+    /// Get information about the TIM server from its status endpoint.
+    /// Can be used to check that the host is a running TIM instance before logging in.
+    ///
+    /// returns: Result<ServerInfo, Error>
+    pub async fn server_info(&self) -> Result<ServerInfo> {
+        let result = self
+            .get("ping")
+            .send()
+            .await
+            .with_context(|| format!("Could not connect to {}", self.tim_host))?;
+
+        if !result.status().is_success() {
+            return Err(TimClientErrors::NotTimServer(
+                self.tim_host.clone(),
+                format!("the status endpoint responded with {}", result.status()),
+            )
+            .into());
+        }
+
+        result.json::<ServerInfo>().await.map_err(|_| {
+            TimClientErrors::NotTimServer(
+                self.tim_host.clone(),
+                "the status endpoint did not respond with TIM status information".to_string(),
+            )
+            .into()
+        })
+    }
+
     /// Log in to TIM using basic username-password authentication.
     ///
     /// Basic authentication uses TIM password to log in the user.
@@ -164,13 +378,82 @@ pub async fn login_basic(&self, username: &str, password: &str) -> Result<()> {
             .send()
             .await?;
 
+        debug!("Login as {} returned {}", username, result.status());
+
         if !result.status().is_success() {
-            return Err(TimClientErrors::InvalidLogin(result.status().to_string()).into());
+            return Err(TimClientErrors::InvalidLogin(
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Log in to TIM using an API token.
+    ///
+    /// The token is sent as a bearer token in the `Authorization` header of all subsequent requests,
+    /// in addition to the CSRF token. The TIM instance must accept bearer tokens for its API.
+    /// The token is verified by requesting the information of the current user.
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: TIM API token.
+    ///
+    /// returns: Result<(), Error>
+    pub async fn login_token(&mut self, token: &str) -> Result<()> {
+        if self.xsrf_token.is_empty() {
+            return Err(TimClientErrors::NoXsrfToken.into());
+        }
+
+        self.auth_token = Some(token.to_string());
+
+        if let Err(e) = self.current_user().await {
+            self.auth_token = None;
+            return Err(TimClientErrors::InvalidToken(format!("{:#}", e)).into());
         }
 
         Ok(())
     }
 
+    /// Get information about the currently logged-in user.
+    ///
+    /// Use this after `login_basic()` or `login_token()` to check which account the client is authenticated as.
+    ///
+    /// returns: Result<UserInfo, Error>
+    pub async fn current_user(&self) -> Result<UserInfo> {
+        let result = self
+            .get("user/current")
+            .send()
+            .await
+            .context("Could not get current user info")?;
+
+        if result.status().is_success() {
+            let user = result
+                .json::<UserInfo>()
+                .await
+                .context("Could not parse user info JSON")?;
+            Ok(user)
+        } else {
+            Err(TimClientErrors::CouldNotGetCurrentUser(
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Add the API token to a request if the client is logged in with a token.
+    ///
+    /// returns: RequestBuilder
+    fn with_auth_token(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.auth_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
     /// Create a POST request to a TIM API endpoint.
     ///
     /// # Arguments
@@ -179,10 +462,13 @@ pub async fn login_basic(&self, username: &str, password: &str) -> Result<()> {
     ///
     /// returns: RequestBuilder
     pub fn post(&self, tim_url: &str) -> RequestBuilder {
-        self.client
+        debug!("POST {}/{}", &self.tim_host, tim_url);
+        let request = self
+            .client
             .post(format!("{}/{}", &self.tim_host, tim_url))
             .header("X-XSRF-TOKEN", &self.xsrf_token)
-            .header("Referer", &self.tim_host)
+            .header("Referer", &self.tim_host);
+        self.with_auth_token(request)
     }
 
     /// Create a PUT request to a TIM API endpoint.
@@ -193,10 +479,13 @@ pub fn post(&self, tim_url: &str) -> RequestBuilder {
     ///
     /// returns: RequestBuilder
     pub fn put(&self, tim_url: &str) -> RequestBuilder {
-        self.client
+        debug!("PUT {}/{}", &self.tim_host, tim_url);
+        let request = self
+            .client
             .put(format!("{}/{}", &self.tim_host, tim_url))
             .header("X-XSRF-TOKEN", &self.xsrf_token)
-            .header("Referer", &self.tim_host)
+            .header("Referer", &self.tim_host);
+        self.with_auth_token(request)
     }
 
     /// Create a GET request to a TIM API endpoint.
@@ -207,10 +496,30 @@ pub fn put(&self, tim_url: &str) -> RequestBuilder {
     ///
     /// returns: RequestBuilder
     pub fn get(&self, tim_url: &str) -> RequestBuilder {
-        self.client
+        debug!("GET {}/{}", &self.tim_host, tim_url);
+        let request = self
+            .client
             .get(format!("{}/{}", &self.tim_host, tim_url))
             .header("X-XSRF-TOKEN", &self.xsrf_token)
-            .header("Referer", &self.tim_host)
+            .header("Referer", &self.tim_host);
+        self.with_auth_token(request)
+    }
+
+    /// Create a DELETE request to a TIM API endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `tim_url`: Endpoint to make the request to. The hostname is automatically prepended.
+    ///
+    /// returns: RequestBuilder
+    pub fn delete(&self, tim_url: &str) -> RequestBuilder {
+        debug!("DELETE {}/{}", &self.tim_host, tim_url);
+        let request = self
+            .client
+            .delete(format!("{}/{}", &self.tim_host, tim_url))
+            .header("X-XSRF-TOKEN", &self.xsrf_token)
+            .header("Referer", &self.tim_host);
+        self.with_auth_token(request)
     }
 
     /// Get information about an item (document or folder) in TIM.
@@ -221,6 +530,10 @@ pub fn get(&self, tim_url: &str) -> RequestBuilder {
     ///
     /// returns: Result<ItemInfo, Error>
     pub async fn get_item_info(&self, item_path: &str) -> Result<ItemInfo> {
+        if let Some(info) = self.prefetched_items.read().unwrap().get(item_path) {
+            return Ok(info.clone());
+        }
+
         let result = self
             .get(&format!("itemInfo/{}", item_path))
             .send()
@@ -247,13 +560,125 @@ pub async fn get_item_info(&self, item_path: &str) -> Result<ItemInfo> {
         }
     }
 
+    /// Get information about all items (documents and folders) in a TIM folder and its subfolders.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_path`: Path to the folder in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<HashMap<String, ItemInfo>, Error> Item information keyed by item path.
+    pub async fn get_items_in_folder(
+        &self,
+        folder_path: &str,
+    ) -> Result<HashMap<String, ItemInfo>> {
+        let items = self.request_items(folder_path, true).await?;
+        Ok(items.into_iter().map(|item| (item.path(), item)).collect())
+    }
+
+    /// Request information about the items in a TIM folder, including the rights of the current user.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_path`: Path to the folder in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `recursive`: Whether to include the items in the subfolders.
+    ///
+    /// returns: Result<Vec<ItemInfo>, Error>
+    async fn request_items(&self, folder_path: &str, recursive: bool) -> Result<Vec<ItemInfo>> {
+        let result = self
+            .get("getItems")
+            .query(&[
+                ("folder", folder_path),
+                ("recursive", if recursive { "true" } else { "false" }),
+                ("include_rights", "true"),
+            ])
+            .send()
+            .await
+            .with_context(|| format!("Could not get items in folder {}", folder_path))?;
+
+        if !result.status().is_success() {
+            return Err(TimClientErrors::ItemError(
+                folder_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into());
+        }
+
+        result
+            .json::<Vec<ItemInfo>>()
+            .await
+            .context("Could not parse folder items JSON")
+    }
+
+    /// Prefetch information about all items in a TIM folder in a single request.
+    ///
+    /// After prefetching, `get_item_info()` returns the prefetched information for the items
+    /// in the folder instead of making a request for each item.
+    /// Items that are not found from the prefetched items are still requested from TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_path`: Path to the folder in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<usize, Error> The number of prefetched items.
+    pub async fn prefetch_folder_items(&self, folder_path: &str) -> Result<usize> {
+        let items = self.get_items_in_folder(folder_path).await?;
+        let count = items.len();
+        self.prefetched_items.write().unwrap().extend(items);
+        Ok(count)
+    }
+
+    /// Check whether the current user can edit an item in TIM.
+    /// For folders, this means that the user can create new items in the folder.
+    ///
+    /// The result is cached for each path for the lifetime of the client.
+    /// Rights prefetched with `prefetch_folder_items()` are reused, so the check usually does not
+    /// need a separate request. Otherwise the rights are requested by listing the parent folder,
+    /// because the item information of a single item does not include them.
+    /// If TIM does not report the rights of the item, the item is assumed to be editable
+    /// and TIM decides when the item is modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the item in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<bool, Error>
+    pub async fn can_edit(&self, item_path: &str) -> Result<bool> {
+        if let Some(can_edit) = self.edit_rights.read().unwrap().get(item_path) {
+            return Ok(*can_edit);
+        }
+
+        let rights = match self.get_item_info(item_path).await?.rights {
+            Some(rights) => Some(rights),
+            None => {
+                let folder_path = item_path.rsplit_once('/').map_or("", |(folder, _)| folder);
+                self.request_items(folder_path, false)
+                    .await?
+                    .into_iter()
+                    .find(|item| item.path() == item_path)
+                    .and_then(|item| item.rights)
+            }
+        };
+        let can_edit = rights.is_none_or(|rights| rights.editable || rights.manage || rights.owner);
+        self.edit_rights
+            .write()
+            .unwrap()
+            .insert(item_path.to_string(), can_edit);
+        Ok(can_edit)
+    }
+
     /// Create a new item (document or folder) in TIM.
+    /// Documents are created empty or as a copy of a template document, because TIM does not
+    /// accept initial contents when creating an item. Use `upload_markdown()` to set the contents of the document.
     ///
     /// # Arguments
     ///
     /// * `item_type`: Item type to create.
     /// * `item_path`: Full path to the new item, e.g. `kurssit/tie/kurssi`.
     /// * `title`: Human-readable title for the item.
+    /// * `template`: Path of the TIM document to create a new document from, e.g. `kurssit/tie/pohja`.
+    ///   The contents and settings of the template are copied into the new document.
+    ///   Ignored for folders.
     ///
     /// returns: Result<(), Error>
     pub async fn create_item(
@@ -261,14 +686,21 @@ pub async fn create_item(
         item_type: ItemType,
         item_path: &str,
         title: &str,
+        template: Option<&str>,
     ) -> Result<()> {
+        let item_type_name = item_type.to_string();
+        let mut form = vec![
+            ("item_path", item_path),
+            ("item_title", title),
+            ("item_type", &item_type_name),
+        ];
+        if let (ItemType::Document, Some(template)) = (item_type, template) {
+            form.push(("template", template));
+        }
+
         let result = self
             .post("createItem")
-            .form(&[
-                ("item_path", item_path),
-                ("item_title", title),
-                ("item_type", &item_type.to_string()),
-            ])
+            .form(&form)
             .send()
             .await
             .with_context(|| format!("Could not create item {}", item_path));
@@ -281,6 +713,7 @@ pub async fn create_item(
                     Err(TimClientErrors::CouldNotCreateItem(
                         item_path.to_string(),
                         result.status().to_string(),
+                        response_error_text(result).await,
                     )
                     .into())
                 }
@@ -290,27 +723,34 @@ pub async fn create_item(
     }
 
     /// Create a new item (document or folder) in TIM, or update the title if it already exists.
-    /// Returns information about the item.
+    /// Returns information about the item and whether the item was created.
     ///
     /// # Arguments
     ///
     /// * `item_type`: Item type to create.
     /// * `path`: Full path to the new item, e.g. `kurssit/tie/kurssi`.
     /// * `title`: Human-readable title for the item.
+    /// * `owner_group`: User group to make the owner of the item if the item is created.
+    /// * `template`: Path of the TIM document to create the document from if the document is created.
     ///
-    /// returns: Result<ItemInfo, Error>
+    /// returns: Result<(ItemInfo, bool), Error> The item information, and `true` if the item was created.
     pub async fn create_or_update_item(
         &self,
         item_type: ItemType,
         path: &str,
         title: &str,
-    ) -> Result<ItemInfo> {
+        owner_group: Option<&str>,
+        template: Option<&str>,
+    ) -> Result<(ItemInfo, bool)> {
         let item_info = self.get_item_info(&path).await;
         match item_info {
             Ok(info) => {
                 if info.item_type == item_type {
-                    self.set_item_title(&path, title).await?;
-                    Ok(info)
+                    // Avoid needless writes (and new document versions) if the title is unchanged
+                    if info.title != title {
+                        self.set_item_title(&path, title).await?;
+                    }
+                    Ok((info, false))
                 } else {
                     Err(TimClientErrors::InvalidItemType(
                         path.to_string(),
@@ -324,9 +764,16 @@ pub async fn create_or_update_item(
                 match e.downcast_ref::<TimClientErrors>() {
                     Some(TimClientErrors::ItemNotFound(_, _)) => {
                         // Item does not exist, create it
-                        self.create_item(item_type, &path, title).await?;
+                        debug!("Item {} does not exist, creating a new {}", path, item_type);
+                        self.create_item(item_type, &path, title, template).await?;
                         let item_info = self.get_item_info(&path).await?;
-                        Ok(item_info)
+                        if let Some(group) = owner_group {
+                            if !item_info.owners.iter().any(|owner| owner.name == group) {
+                                debug!("Setting owner of {} to {}", path, group);
+                                self.set_owner(&item_info, group).await?;
+                            }
+                        }
+                        Ok((item_info, true))
                     }
                     _ => Err(e),
                 }
@@ -334,6 +781,38 @@ pub async fn create_or_update_item(
         }
     }
 
+    /// Get information about a document in TIM, creating the document if it does not exist.
+    /// Unlike `create_or_update_item`, the title of an existing document is not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Full path to the document, e.g. `kurssit/tie/kurssi/_files`.
+    /// * `title`: Human-readable title to use if the document is created.
+    ///
+    /// returns: Result<ItemInfo, Error>
+    pub async fn get_or_create_document(&self, path: &str, title: &str) -> Result<ItemInfo> {
+        match self.get_item_info(path).await {
+            Ok(info) => match info.item_type {
+                ItemType::Document => Ok(info),
+                _ => Err(TimClientErrors::InvalidItemType(
+                    path.to_string(),
+                    ItemType::Document.to_string(),
+                    info.item_type.to_string(),
+                )
+                .into()),
+            },
+            Err(e) => match e.downcast_ref::<TimClientErrors>() {
+                Some(TimClientErrors::ItemNotFound(_, _)) => {
+                    debug!("Document {} does not exist, creating it", path);
+                    self.create_item(ItemType::Document, path, title, None)
+                        .await?;
+                    self.get_item_info(path).await
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
     /// Set the title of an item (document or folder) in TIM.
     ///
     /// # Arguments
@@ -355,150 +834,626 @@ pub async fn set_item_title(&self, item_path: &str, title: &str) -> Result<()> {
             .with_context(|| format!("Could not set title for item {}", item_path))?;
 
         if result.status().is_success() {
+            if let Some(info) = self.prefetched_items.write().unwrap().get_mut(item_path) {
+                info.title = title.to_string();
+            }
             Ok(())
         } else {
             Err(TimClientErrors::ItemError(
                 item_path.to_string(),
                 result.status().to_string(),
-                result.text().await.unwrap_or("<none>".to_string()),
+                response_error_text(result).await,
             )
             .into())
         }
     }
 
-    /// Download the markdown contents of a document in TIM.
+    /// Make a user group the owner of an item in TIM.
+    /// The current user must have the right to transfer the ownership of the item.
     ///
     /// # Arguments
     ///
-    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `item`: The item to change the owner of.
+    /// * `group`: Name of the user group, e.g. `kurssi-opettajat`.
     ///
-    /// returns: Result<String, Error>
-    pub async fn download_markdown(&self, item_path: &str) -> Result<String> {
-        let item = self.get_item_info(item_path).await?;
-
+    /// returns: Result<(), Error>
+    pub async fn set_owner(&self, item: &ItemInfo, group: &str) -> Result<()> {
         let result = self
-            .get(&format!("download/{}", item.id))
+            .put("permissions/add")
+            .json(&json!({
+                "id": item.id,
+                "groups": [group],
+                "type": OWNER_ACCESS_TYPE,
+                "time": {
+                    "type": "always",
+                },
+                "confirm": false,
+            }))
             .send()
             .await
-            .with_context(|| format!("Could not download item {}", item_path))?;
+            .with_context(|| format!("Could not set owner for item {}", item.path()))?;
 
         if result.status().is_success() {
-            let markdown = result
-                .text()
-                .await
-                .context("Could not load markdown response")?;
-            Ok(markdown)
+            Ok(())
         } else {
-            Err(TimClientErrors::ItemError(
-                item_path.to_string(),
-                result.status().to_string(),
-                result.text().await.unwrap_or("<none>".to_string()),
+            Err(TimClientErrors::CouldNotSetOwner(
+                item.path(),
+                group.to_string(),
+                response_error_text(result).await,
             )
             .into())
         }
     }
 
-    /// Upload markdown contents to a document in TIM.
+    /// Set the language of a document in TIM.
     ///
     /// # Arguments
     ///
-    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
-    /// * `markdown`: New markdown contents of the document.
+    /// * `item_path`: Full path to the document, e.g. `kurssit/tie/kurssi`.
+    /// * `lang_id`: Language ID to set, e.g. `fi` or `en`.
     ///
     /// returns: Result<(), Error>
-    pub async fn upload_markdown(&self, item_path: &str, markdown: &str) -> Result<()> {
+    pub async fn set_item_language(&self, item_path: &str, lang_id: &str) -> Result<()> {
         let item = self.get_item_info(item_path).await?;
 
-        match item.item_type {
-            ItemType::Document => (),
-            _ => {
-                return Err(TimClientErrors::InvalidItemType(
-                    item_path.to_string(),
-                    ItemType::Document.to_string(),
-                    item.item_type.to_string(),
-                )
-                .into());
-            }
-        }
-
-        let current_markdown = self.download_markdown(item_path).await?;
-
+        // TIM updates the language and the title of a document in the same request
         let result = self
-            .post(&format!("update/{}", item.id))
+            .post(&format!("translation/{}", item.id))
             .json(&json!({
-                "fulltext": markdown,
-                "original": current_markdown.as_str(),
+                "new_langid": lang_id,
+                "new_title": item.title,
             }))
             .send()
             .await
-            .with_context(|| format!("Could not upload markdown to {}", item_path))?;
+            .with_context(|| format!("Could not set language for item {}", item_path))?;
 
         if result.status().is_success() {
+            if let Some(info) = self.prefetched_items.write().unwrap().get_mut(item_path) {
+                info.lang_id = Some(lang_id.to_string());
+            }
             Ok(())
         } else {
             Err(TimClientErrors::ItemError(
                 item_path.to_string(),
                 result.status().to_string(),
-                result.text().await.unwrap_or("<none>".to_string()),
+                response_error_text(result).await,
             )
             .into())
         }
     }
 
-    /// Get a list of uploaded files in a document in TIM.
+    /// Get the aliases of a document in TIM.
+    /// Note that the document's own path is also listed as an alias.
     ///
     /// # Arguments
     ///
-    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `doc_id`: ID of the document.
     ///
-    /// returns: Result<Vec<UploadFileInfo>, Error>
-    pub async fn get_document_uploads(&self, item_path: &str) -> Result<Vec<UploadFileInfo>> {
-        let item = self.get_item_info(item_path).await?;
-
-        match item.item_type {
-            ItemType::Document => (),
-            _ => {
-                return Err(TimClientErrors::InvalidItemType(
-                    item_path.to_string(),
-                    ItemType::Document.to_string(),
-                    item.item_type.to_string(),
-                )
-                .into());
-            }
-        }
-
+    /// returns: Result<Vec<AliasInfo>, Error>
+    pub async fn get_aliases(&self, doc_id: u64) -> Result<Vec<AliasInfo>> {
         let result = self
-            .get(&format!("docUploads/{}", item_path))
+            .get(&format!("alias/{}", doc_id))
             .send()
             .await
-            .with_context(|| format!("Could not get uploads for {}", item_path))?;
+            .with_context(|| format!("Could not get aliases for document {}", doc_id))?;
 
         if result.status().is_success() {
-            let uploads = result
-                .json::<Vec<UploadFileInfo>>()
+            let aliases = result
+                .json::<Vec<AliasInfo>>()
                 .await
-                .context("Could not parse upload info JSON")?;
-            Ok(uploads)
+                .context("Could not parse alias info JSON")?;
+            Ok(aliases)
         } else {
             Err(TimClientErrors::ItemError(
-                item_path.to_string(),
+                doc_id.to_string(),
                 result.status().to_string(),
-                result.text().await.unwrap_or("<none>".to_string()),
+                response_error_text(result).await,
             )
             .into())
         }
     }
 
-    pub async fn upload_file(
-        &self,
-        item_path: &str,
-        file_path: impl AsRef<Path>,
-        file_name: &str,
-    ) -> Result<()> {
-        let file_path = file_path.as_ref();
-        if !file_path.is_file() {
-            return Err(
-                TimClientErrors::FileNotFound(file_path.to_string_lossy().to_string()).into(),
+    /// Add an alias to a document in TIM.
+    /// The document can then be also accessed via the alias path.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc_id`: ID of the document.
+    /// * `alias_path`: Full path of the alias, e.g. `kurssit/tie/kurssi/vanha`.
+    ///
+    /// returns: Result<(), Error>
+    pub async fn add_alias(&self, doc_id: u64, alias_path: &str) -> Result<()> {
+        let result = self
+            .put(&format!("alias/{}/{}", doc_id, alias_path))
+            .json(&json!({
+                "public": true,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("Could not add alias {}", alias_path))?;
+
+        if result.status().is_success() {
+            Ok(())
+        } else {
+            Err(TimClientErrors::ItemError(
+                alias_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Remove a document alias from TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias_path`: Full path of the alias to remove, e.g. `kurssit/tie/kurssi/vanha`.
+    ///
+    /// returns: Result<(), Error>
+    pub async fn remove_alias(&self, alias_path: &str) -> Result<()> {
+        let result = self
+            .delete(&format!("alias/{}", alias_path))
+            .send()
+            .await
+            .with_context(|| format!("Could not remove alias {}", alias_path))?;
+
+        if result.status().is_success() {
+            Ok(())
+        } else {
+            Err(TimClientErrors::ItemError(
+                alias_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Download the markdown contents of a document in TIM.
+    ///
+    /// The downloaded markdown is cached for each path for the lifetime of the client,
+    /// so downloading the same document again does not make a new request.
+    /// The cached markdown is discarded when the document is modified with `upload_markdown()`
+    /// or `update_paragraph()`. Changes made in TIM by others are not noticed after the first download,
+    /// except by the conflict check of TIM when the markdown is uploaded with `upload_markdown()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<String, Error>
+    pub async fn download_markdown(&self, item_path: &str) -> Result<String> {
+        if let Some((_, markdown)) = self.markdown_cache.read().unwrap().get(item_path) {
+            return Ok(markdown.clone());
+        }
+        self.download_markdown_uncached(item_path).await
+    }
+
+    /// Download the markdown contents of a document in TIM without using the cached markdown.
+    /// The cached markdown of the document is replaced with the downloaded markdown.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<String, Error>
+    pub async fn download_markdown_uncached(&self, item_path: &str) -> Result<String> {
+        let item = self.get_item_info(item_path).await?;
+
+        let result = self
+            .get(&format!("download/{}", item.id))
+            .send()
+            .await
+            .with_context(|| format!("Could not download item {}", item_path))?;
+
+        if result.status().is_success() {
+            let markdown = result
+                .text()
+                .await
+                .context("Could not load markdown response")?;
+            self.markdown_cache
+                .write()
+                .unwrap()
+                .insert(item_path.to_string(), (item.id, markdown.clone()));
+            Ok(markdown)
+        } else {
+            Err(TimClientErrors::ItemError(
+                item_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Download the markdown contents of a document in TIM if the document exists.
+    /// Unlike `download_markdown`, a missing document is not an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<Option<String>, Error> `None` if there is no item at the path.
+    pub async fn try_download_markdown(&self, item_path: &str) -> Result<Option<String>> {
+        match self.download_markdown(item_path).await {
+            Ok(markdown) => Ok(Some(markdown)),
+            Err(e) => match e.downcast_ref::<TimClientErrors>() {
+                Some(TimClientErrors::ItemNotFound(_, _)) => Ok(None),
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Download the markdown contents of a document in TIM with the IDs of the paragraphs.
+    /// TIM only includes the paragraph IDs in the markdown if they are requested, so the markdown
+    /// differs from the markdown of `download_markdown()` and is not cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<String, Error>
+    pub async fn download_markdown_with_ids(&self, item_path: &str) -> Result<String> {
+        let item = self.get_item_info(item_path).await?;
+
+        let result = self
+            .get(&format!("download/{}", item.id))
+            .query(&[("with_ids", "true")])
+            .send()
+            .await
+            .with_context(|| format!("Could not download item {}", item_path))?;
+
+        if result.status().is_success() {
+            let markdown = result
+                .text()
+                .await
+                .context("Could not load markdown response")?;
+            Ok(markdown)
+        } else {
+            Err(TimClientErrors::ItemError(
+                item_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Download the markdown contents of a specific version of a document in TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `version`: The version of the document to download.
+    ///
+    /// returns: Result<String, Error>
+    pub async fn download_markdown_version(
+        &self,
+        item_path: &str,
+        version: DocVersion,
+    ) -> Result<String> {
+        let item = self.get_item_info(item_path).await?;
+
+        let result = self
+            .get(&format!("download/{}", item.id))
+            .query(&[("major", version.0), ("minor", version.1)])
+            .send()
+            .await
+            .with_context(|| format!("Could not download version {} of {}", version, item_path))?;
+
+        if result.status().is_success() {
+            let markdown = result
+                .text()
+                .await
+                .context("Could not load markdown response")?;
+            Ok(markdown)
+        } else {
+            Err(TimClientErrors::ItemError(
+                item_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Get the latest versions of a document in TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `count`: The maximum number of versions to get.
+    ///
+    /// returns: Result<Vec<DocVersionInfo>, Error> The versions, newest first.
+    pub async fn get_document_versions(
+        &self,
+        item_path: &str,
+        count: usize,
+    ) -> Result<Vec<DocVersionInfo>> {
+        let item = self.get_item_info(item_path).await?;
+
+        let result = self
+            .get(&format!("changelog/{}/{}", item.id, count))
+            .send()
+            .await
+            .with_context(|| format!("Could not get versions of {}", item_path))?;
+
+        if result.status().is_success() {
+            let changelog = result
+                .json::<DocChangelog>()
+                .await
+                .context("Could not parse document changelog JSON")?;
+            Ok(changelog.versions)
+        } else {
+            Err(TimClientErrors::ItemError(
+                item_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Restore a document in TIM to the contents of an earlier version.
+    /// The contents are uploaded as a new version, so the reverted versions are kept in the history.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `version`: The version to restore.
+    ///
+    /// returns: Result<Option<DocVersion>, Error> The version of the document created by the revert,
+    /// if TIM reported it.
+    pub async fn revert_document(
+        &self,
+        item_path: &str,
+        version: DocVersion,
+    ) -> Result<Option<DocVersion>> {
+        let markdown = self.download_markdown_version(item_path, version).await?;
+        self.upload_markdown(item_path, &markdown, None).await
+    }
+
+    /// Upload markdown contents to a document in TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    /// * `markdown`: New markdown contents of the document.
+    /// * `original`: The markdown of the document the new contents are based on.
+    ///   If given, it is sent to TIM as the original of the update, and the upload fails with
+    ///   `TimClientErrors::RemoteModified` when TIM rejects it because the document was edited since.
+    ///   If `None`, the current markdown is downloaded and the document is overwritten regardless of its contents.
+    ///
+    /// returns: Result<Option<DocVersion>, Error> The version of the document created by the upload,
+    /// if TIM reported it.
+    pub async fn upload_markdown(
+        &self,
+        item_path: &str,
+        markdown: &str,
+        original: Option<&str>,
+    ) -> Result<Option<DocVersion>> {
+        let item = self.get_item_info(item_path).await?;
+
+        match item.item_type {
+            ItemType::Document => (),
+            _ => {
+                return Err(TimClientErrors::InvalidItemType(
+                    item_path.to_string(),
+                    ItemType::Document.to_string(),
+                    item.item_type.to_string(),
+                )
+                .into());
+            }
+        }
+
+        // TIM rejects the update if the original differs from the current markdown,
+        // so the original given by the caller is only checked by TIM and not downloaded again
+        let original_markdown = match original {
+            Some(original) => original.to_string(),
+            None => self.download_markdown_uncached(item_path).await?,
+        };
+
+        debug!(
+            "Uploading {} bytes of markdown to {}",
+            markdown.len(),
+            item_path
+        );
+
+        let result = self
+            .post(&format!("update/{}", item.id))
+            .json(&json!({
+                "fulltext": markdown,
+                "original": original_markdown.as_str(),
+            }))
+            .send()
+            .await;
+        // The document may have changed even if the request failed
+        self.forget_markdown(item.id);
+        let result =
+            result.with_context(|| format!("Could not upload markdown to {}", item_path))?;
+
+        if result.status().is_success() {
+            // The response contains the changelog of the document with the new version first.
+            // The version is only informative, so a missing or unexpected changelog is not an error.
+            let version = result
+                .json::<DocChangelog>()
+                .await
+                .ok()
+                .and_then(|changelog| changelog.versions.into_iter().next())
+                .map(|info| info.version);
+            Ok(version)
+        } else {
+            let error = TimClientErrors::ItemError(
+                item_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            );
+            // TIM's error for a conflicting edit is not distinguishable from other errors,
+            // so check whether the document was edited after the original was downloaded
+            if original.is_some() {
+                let current_markdown = self.download_markdown_uncached(item_path).await?;
+                if current_markdown != original_markdown {
+                    return Err(TimClientErrors::RemoteModified(item_path.to_string()).into());
+                }
+            }
+            Err(error.into())
+        }
+    }
+
+    /// Discard the cached markdown of a document, see `download_markdown()`.
+    /// The document is identified by its ID, so that all paths (e.g. aliases) of the document are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc_id`: ID of the document.
+    fn forget_markdown(&self, doc_id: u64) {
+        self.markdown_cache
+            .write()
+            .unwrap()
+            .retain(|_, (cached_id, _)| *cached_id != doc_id);
+    }
+
+    /// Replace the contents of a single paragraph in a document in TIM.
+    /// The rest of the document is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc_id`: ID of the document.
+    /// * `par_id`: ID of the paragraph to replace.
+    /// * `content`: New markdown of the paragraph, including the attribute line with the paragraph ID.
+    ///
+    /// returns: Result<(), Error>
+    pub async fn update_paragraph(&self, doc_id: u64, par_id: &str, content: &str) -> Result<()> {
+        debug!(
+            "Uploading {} bytes of markdown to paragraph {} of document {}",
+            content.len(),
+            par_id,
+            doc_id
+        );
+
+        let result = self
+            .post("postParagraph/")
+            .json(&json!({
+                "docId": doc_id,
+                "par": par_id,
+                "text": content,
+            }))
+            .send()
+            .await;
+        self.forget_markdown(doc_id);
+        let result = result.with_context(|| {
+            format!(
+                "Could not update paragraph {} of document {}",
+                par_id, doc_id
+            )
+        })?;
+
+        if result.status().is_success() {
+            Ok(())
+        } else {
+            Err(TimClientErrors::ItemError(
+                format!("paragraph {} of document {}", par_id, doc_id),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Replace the document settings of a document in TIM with the document settings API.
+    /// Unlike a settings block uploaded in the markdown, the settings are not part of the editable
+    /// document body. The settings are sent as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc_id`: ID of the document.
+    /// * `settings`: New document settings.
+    ///
+    /// returns: Result<(), Error>
+    pub async fn set_document_settings(
+        &self,
+        doc_id: u64,
+        settings: &Map<String, Value>,
+    ) -> Result<()> {
+        debug!("Setting {} settings of document {}", settings.len(), doc_id);
+
+        let result = self
+            .post(&format!("documentSettings/{}", doc_id))
+            .json(&json!({ "settings": settings }))
+            .send()
+            .await;
+        // The settings are stored in the document, so the cached markdown is outdated
+        self.forget_markdown(doc_id);
+        let result =
+            result.with_context(|| format!("Could not set the settings of document {}", doc_id))?;
+
+        if result.status().is_success() {
+            Ok(())
+        } else {
+            Err(TimClientErrors::ItemError(
+                format!("settings of document {}", doc_id),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    /// Get a list of uploaded files in a document in TIM.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_path`: Path to the document in TIM, e.g. `kurssit/tie/kurssi`.
+    ///
+    /// returns: Result<Vec<UploadFileInfo>, Error>
+    pub async fn get_document_uploads(&self, item_path: &str) -> Result<Vec<UploadFileInfo>> {
+        let item = self.get_item_info(item_path).await?;
+
+        match item.item_type {
+            ItemType::Document => (),
+            _ => {
+                return Err(TimClientErrors::InvalidItemType(
+                    item_path.to_string(),
+                    ItemType::Document.to_string(),
+                    item.item_type.to_string(),
+                )
+                .into());
+            }
+        }
+
+        let result = self
+            .get(&format!("docUploads/{}", item_path))
+            .send()
+            .await
+            .with_context(|| format!("Could not get uploads for {}", item_path))?;
+
+        if result.status().is_success() {
+            let uploads = result
+                .json::<Vec<UploadFileInfo>>()
+                .await
+                .context("Could not parse upload info JSON")?;
+            Ok(uploads)
+        } else {
+            Err(TimClientErrors::ItemError(
+                item_path.to_string(),
+                result.status().to_string(),
+                response_error_text(result).await,
+            )
+            .into())
+        }
+    }
+
+    pub async fn upload_file(
+        &self,
+        item_path: &str,
+        file_path: impl AsRef<Path>,
+        file_name: &str,
+    ) -> Result<()> {
+        let file_path = file_path.as_ref();
+        if !file_path.is_file() {
+            return Err(
+                TimClientErrors::FileNotFound(file_path.to_string_lossy().to_string()).into(),
             );
         }
 
@@ -516,6 +1471,15 @@ pub async fn upload_file(
             }
         }
 
+        let mime_type = guess_mime_type(file_path);
+        debug!(
+            "Uploading file {} to {} as {} ({})",
+            file_path.display(),
+            item_path,
+            file_name,
+            mime_type
+        );
+
         let form = Form::new().text("doc_id", item.id.to_string()).part(
             "file",
             Part::stream({
@@ -523,7 +1487,8 @@ pub async fn upload_file(
                 let stream = FramedRead::new(file, BytesCodec::new());
                 Body::wrap_stream(stream)
             })
-            .file_name(file_name.to_string()),
+            .file_name(file_name.to_string())
+            .mime_str(&mime_type)?,
         );
 
         let result = self
@@ -539,22 +1504,35 @@ pub async fn upload_file(
             Err(TimClientErrors::ItemError(
                 item_path.to_string(),
                 result.status().to_string(),
-                result.text().await.unwrap_or("<none>".to_string()),
+                response_error_text(result).await,
             )
             .into())
         }
     }
 }
 
+/// Default timeout for a single request to TIM
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default timeout for connecting to TIM
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Builder for TimClient
 pub struct TimClientBuilder {
     tim_host: Option<String>,
+    timeout: Duration,
+    connect_timeout: Duration,
+    accept_invalid_certs: bool,
 }
 
 impl TimClientBuilder {
     /// Create a new TimClientBuilder.
     pub fn new() -> Self {
-        Self { tim_host: None }
+        Self {
+            tim_host: None,
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            accept_invalid_certs: false,
+        }
     }
 
     /// Set the TIM host URL.
@@ -571,14 +1549,73 @@ pub fn tim_host(mut self, tim_host: &str) -> Self {
         self
     }
 
+    /// Set the timeout for a single request, including reading the response.
+    /// Defaults to `DEFAULT_TIMEOUT`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: Request timeout
+    ///
+    /// returns: TimClientBuilder
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for connecting to the TIM host.
+    /// Defaults to `DEFAULT_CONNECT_TIMEOUT`.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect_timeout`: Connection timeout
+    ///
+    /// returns: TimClientBuilder
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Accept invalid TLS certificates, e.g. self-signed certificates of development instances.
+    ///
+    /// **Warning:** This disables the verification of the TIM host identity.
+    /// Anyone who can intercept the connection can read and modify all traffic,
+    /// including the login credentials. Never use this for production instances.
+    ///
+    /// # Arguments
+    ///
+    /// * `accept_invalid_certs`: Whether to accept invalid certificates
+    ///
+    /// returns: TimClientBuilder
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
     /// Build a new TimClient.
     ///
     /// This will validate the host and refresh the CSRF token, making the client ready to use.
+    /// The client accepts gzip and deflate compressed responses to reduce the transfer size of large documents.
     ///
     /// returns: Result<TimClient, Error>
     pub async fn build(self) -> Result<TimClient> {
         let host = self.tim_host.clone().ok_or(TimClientErrors::NoHost)?;
-        let mut tim_client = TimClient::new(host);
+        let client = ClientBuilder::new()
+            .cookie_store(true)
+            .gzip(true)
+            .deflate(true)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .build()
+            .context("Could not create HTTP client")?;
+        let mut tim_client = TimClient::new(host, client);
+        let server_info = tim_client.server_info().await?;
+        debug!(
+            "Connected to TIM at {} (status: {}, version: {})",
+            tim_client.tim_host,
+            server_info.status,
+            server_info.version.as_deref().unwrap_or("unknown")
+        );
         tim_client.refresh_xsrf_token().await?;
         Ok(tim_client)
     }
@@ -642,3 +1679,578 @@ fn id_checksum(id: &str) -> char {
 
     format!("{}{}", random_id, id_checksum(&random_id))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::mock_server::{MockResponse, MockServer};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use serde_json::Value;
+    use std::collections::HashSet;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    fn item_json(id: u64, path: &str, item_type: &str, title: &str) -> Value {
+        let (location, short_name) = path.rsplit_once('/').unwrap_or(("", path));
+        json!({
+            "id": id,
+            "type": item_type,
+            "title": title,
+            "location": location,
+            "short_name": short_name,
+        })
+    }
+
+    async fn title_server() -> MockServer {
+        MockServer::start(|request| match request.path.as_str() {
+            "/itemInfo/course/doc" => {
+                MockResponse::json(item_json(1, "course/doc", "document", "Old title"))
+            }
+            _ => MockResponse::json(json!({})),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_or_update_item_skips_unchanged_title() {
+        let server = title_server().await;
+        server
+            .client()
+            .create_or_update_item(ItemType::Document, "course/doc", "Old title", None, None)
+            .await
+            .unwrap();
+        assert_eq!(server.count("PUT", "/changeTitle/"), 0);
+    }
+
+    #[tokio::test]
+    async fn create_or_update_item_updates_changed_title() {
+        let server = title_server().await;
+        server
+            .client()
+            .create_or_update_item(ItemType::Document, "course/doc", "New title", None, None)
+            .await
+            .unwrap();
+        assert_eq!(server.count("PUT", "/changeTitle/"), 1);
+        assert_eq!(server.count("PUT", "/changeTitle/1"), 1);
+    }
+
+    #[tokio::test]
+    async fn create_or_update_item_reports_whether_the_item_was_created() {
+        let server = title_server().await;
+        let (info, created) = server
+            .client()
+            .create_or_update_item(ItemType::Document, "course/doc", "Old title", None, None)
+            .await
+            .unwrap();
+        assert_eq!((info.id, created), (1, false));
+
+        let exists = Arc::new(Mutex::new(false));
+        let server = {
+            let exists = exists.clone();
+            MockServer::start(move |request| match request.path.as_str() {
+                "/itemInfo/course/new" if *exists.lock().unwrap() => {
+                    MockResponse::json(item_json(2, "course/new", "document", "New"))
+                }
+                "/itemInfo/course/new" => MockResponse::text(404, "Not found"),
+                "/createItem" => {
+                    *exists.lock().unwrap() = true;
+                    MockResponse::json(json!({}))
+                }
+                _ => MockResponse::json(json!({})),
+            })
+            .await
+        };
+        let (info, created) = server
+            .client()
+            .create_or_update_item(ItemType::Document, "course/new", "New", None, None)
+            .await
+            .unwrap();
+        assert_eq!((info.id, created), (2, true));
+        assert_eq!(server.count("POST", "/createItem"), 1);
+    }
+
+    /// Create an item with a template against a mock server and return the body of the create request.
+    async fn create_item_form(item_type: ItemType) -> String {
+        let server = MockServer::start(|_| MockResponse::json(json!({}))).await;
+        server
+            .client()
+            .create_item(item_type, "course/item", "Item", Some("course/template"))
+            .await
+            .unwrap();
+        let request = server
+            .requests()
+            .into_iter()
+            .find(|request| request.path == "/createItem")
+            .unwrap();
+        String::from_utf8(request.body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn create_item_sends_template_for_documents() {
+        let form = create_item_form(ItemType::Document).await;
+        assert!(form.contains("item_type=document"), "{}", form);
+        assert!(form.contains("template=course%2Ftemplate"), "{}", form);
+    }
+
+    #[tokio::test]
+    async fn create_item_omits_template_for_folders() {
+        let form = create_item_form(ItemType::Folder).await;
+        assert!(form.contains("item_type=folder"), "{}", form);
+        assert!(!form.contains("template"), "{}", form);
+    }
+
+    /// Start a mock server where `course` is a folder with the given rights of the current user.
+    async fn rights_server(rights: Value) -> MockServer {
+        MockServer::start(move |request| match request.path.as_str() {
+            "/itemInfo/course" => {
+                let mut info = item_json(1, "course", "folder", "Course");
+                info["rights"] = rights.clone();
+                MockResponse::json(info)
+            }
+            _ => MockResponse::text(404, "Not found"),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn can_edit_allows_editable_items_and_caches_the_result() {
+        let server =
+            rights_server(json!({"editable": true, "manage": false, "owner": false})).await;
+        let client = server.client();
+        assert!(client.can_edit("course").await.unwrap());
+        assert!(client.can_edit("course").await.unwrap());
+        assert_eq!(server.count("GET", "/itemInfo/course"), 1);
+    }
+
+    #[tokio::test]
+    async fn can_edit_requests_rights_missing_from_item_info() {
+        // The item information of a single item does not include the rights
+        let server = MockServer::start(|request| {
+            if request.path == "/itemInfo/course/ohj1" {
+                return MockResponse::json(item_json(2, "course/ohj1", "folder", "Ohj1"));
+            }
+            if request.path.starts_with("/getItems?") {
+                let mut info = item_json(2, "course/ohj1", "folder", "Ohj1");
+                info["rights"] = json!({"editable": false, "manage": false, "owner": false});
+                return MockResponse::json(json!([info]));
+            }
+            MockResponse::text(404, "Not found")
+        })
+        .await;
+
+        assert!(!server.client().can_edit("course/ohj1").await.unwrap());
+        let requests = server.requests();
+        let items_request = requests
+            .iter()
+            .find(|request| request.path.starts_with("/getItems?"))
+            .unwrap();
+        assert!(
+            items_request.path.contains("folder=course&")
+                && items_request.path.contains("include_rights=true"),
+            "{}",
+            items_request.path
+        );
+    }
+
+    #[tokio::test]
+    async fn can_edit_denies_items_without_edit_rights() {
+        let server =
+            rights_server(json!({"editable": false, "manage": false, "owner": false})).await;
+        assert!(!server.client().can_edit("course").await.unwrap());
+    }
+
+    #[test]
+    fn hashed_par_id_is_deterministic() {
+        assert_eq!(hashed_par_id(Some("task-1")), hashed_par_id(Some("task-1")));
+        assert_ne!(hashed_par_id(Some("task-1")), hashed_par_id(Some("task-2")));
+    }
+
+    #[test]
+    fn hashed_par_id_has_tim_format() {
+        for seed in [Some("task-1"), Some(""), None] {
+            let par_id = hashed_par_id(seed);
+            assert_eq!(par_id.len(), 12, "{}", par_id);
+            assert!(
+                par_id.chars().all(|c| c.is_ascii_alphanumeric()),
+                "{}",
+                par_id
+            );
+        }
+    }
+
+    #[test]
+    fn hashed_par_ids_of_different_seeds_are_unique() {
+        let par_ids = (0..10_000)
+            .map(|i| hashed_par_id(Some(&format!("task-{}", i))))
+            .collect::<HashSet<_>>();
+        assert_eq!(par_ids.len(), 10_000);
+    }
+
+    #[tokio::test]
+    async fn errors_include_error_field_of_json_body() {
+        let server = MockServer::start(|_| {
+            MockResponse::json_status(
+                400,
+                json!({"error": "Item with a same name already exists."}),
+            )
+        })
+        .await;
+        let error = server
+            .client()
+            .create_item(ItemType::Document, "doc", "Doc", None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.ends_with("Server answered with: Item with a same name already exists."),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_include_plain_text_body() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/itemInfo/doc" => MockResponse::json(item_json(1, "doc", "document", "Doc")),
+            _ => MockResponse::text(403, "  Sorry, you don't have permission.\n"),
+        })
+        .await;
+        let error = server
+            .client()
+            .set_item_title("doc", "New title")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.ends_with("Server answered with: Sorry, you don't have permission."),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_truncate_long_bodies() {
+        let server = MockServer::start(|_| {
+            MockResponse::text(500, &format!("<html>{}</html>", "x".repeat(1000)))
+        })
+        .await;
+        let error = server
+            .client()
+            .create_item(ItemType::Document, "doc", "Doc", None)
+            .await
+            .unwrap_err()
+            .to_string();
+        let body = error.split("Server answered with: ").nth(1).unwrap();
+        assert_eq!(body.chars().count(), MAX_ERROR_TEXT_LENGTH + 3);
+        assert!(
+            body.starts_with("<html>xxx") && body.ends_with("..."),
+            "{}",
+            body
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_mark_empty_bodies() {
+        let server = MockServer::start(|_| MockResponse::status(502)).await;
+        let error = server
+            .client()
+            .create_item(ItemType::Document, "doc", "Doc", None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.ends_with("Server answered with: <none>"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn refresh_xsrf_token_fails_without_cookie() {
+        let server = MockServer::start(|_| MockResponse::text(200, "<html>Not TIM</html>")).await;
+        let error = server.client().refresh_xsrf_token().await.unwrap_err();
+        assert!(
+            matches!(
+                error.downcast_ref::<TimClientErrors>(),
+                Some(TimClientErrors::NotTimServer(host, _)) if host == server.url()
+            ),
+            "{}",
+            error
+        );
+        assert!(
+            error.to_string().contains("did not set an XSRF token"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_xsrf_token_reads_cookie() {
+        let server = MockServer::start(|_| {
+            MockResponse::text(200, "").with_header("Set-Cookie", "XSRF-TOKEN=abc123; Path=/")
+        })
+        .await;
+        let mut client = server.client();
+        client.refresh_xsrf_token().await.unwrap();
+        assert_eq!(client.xsrf_token, "abc123");
+    }
+
+    #[tokio::test]
+    async fn set_document_settings_sends_settings_as_json() {
+        let server = MockServer::start(|_| MockResponse::json(json!({}))).await;
+        let settings = json!({ "css": "lecture.css", "print": { "paper_size": "A4" } });
+
+        server
+            .client()
+            .set_document_settings(1, settings.as_object().unwrap())
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/documentSettings/1");
+        assert_eq!(requests[0].json(), json!({ "settings": settings }));
+    }
+
+    fn gzip(body: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn built_client_accepts_compressed_responses() {
+        let server = MockServer::start(|request| {
+            let accepts_gzip = request
+                .header("accept-encoding")
+                .is_some_and(|encodings| encodings.contains("gzip"));
+            let (response, body) = match request.path.as_str() {
+                "/ping" => {
+                    let body = json!({ "status": "ok" }).to_string();
+                    (MockResponse::json_status(200, json!({})), body)
+                }
+                _ => {
+                    let response = MockResponse::status(200)
+                        .with_header("Set-Cookie", "XSRF-TOKEN=abc123; Path=/");
+                    (response, "<html>TIM</html>".to_string())
+                }
+            };
+            if accepts_gzip {
+                response
+                    .with_header("Content-Encoding", "gzip")
+                    .with_body(gzip(&body))
+            } else {
+                response.with_body(body.into_bytes())
+            }
+        })
+        .await;
+
+        let client = TimClientBuilder::new()
+            .tim_host(server.url())
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(client.xsrf_token, "abc123");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for request in requests.iter() {
+            let encodings = request.header("accept-encoding").unwrap_or_default();
+            assert!(
+                encodings.contains("gzip") && encodings.contains("deflate"),
+                "{}",
+                encodings
+            );
+        }
+    }
+
+    /// Serve the document `course/doc` with ID 1, whose markdown can be changed during the test
+    /// like a user editing the document in TIM.
+    /// Like TIM, updates are rejected if their original differs from the current markdown.
+    async fn document_server(markdown: &Arc<Mutex<String>>) -> MockServer {
+        let markdown = markdown.clone();
+        MockServer::start(move |request| match request.path.as_str() {
+            "/itemInfo/course/doc" => {
+                MockResponse::json(item_json(1, "course/doc", "document", "Doc"))
+            }
+            "/download/1" => MockResponse::text(200, &markdown.lock().unwrap()),
+            "/update/1" => {
+                let update = request.json();
+                let mut markdown = markdown.lock().unwrap();
+                if update["original"] != *markdown {
+                    return MockResponse::json_status(
+                        400,
+                        json!({"error": "The document has been modified since it was loaded."}),
+                    );
+                }
+                *markdown = update["fulltext"].as_str().unwrap().to_string();
+                MockResponse::json(json!({}))
+            }
+            _ => MockResponse::json(json!({})),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn download_markdown_is_cached() {
+        let markdown = Arc::new(Mutex::new("First".to_string()));
+        let server = document_server(&markdown).await;
+        let client = server.client();
+
+        assert_eq!(
+            client.download_markdown("course/doc").await.unwrap(),
+            "First"
+        );
+        *markdown.lock().unwrap() = "Second".to_string();
+        assert_eq!(
+            client.download_markdown("course/doc").await.unwrap(),
+            "First"
+        );
+        assert_eq!(server.count("GET", "/download/"), 1);
+
+        assert_eq!(
+            client
+                .download_markdown_uncached("course/doc")
+                .await
+                .unwrap(),
+            "Second"
+        );
+        assert_eq!(
+            client.download_markdown("course/doc").await.unwrap(),
+            "Second"
+        );
+        assert_eq!(server.count("GET", "/download/"), 2);
+    }
+
+    #[tokio::test]
+    async fn try_download_markdown_returns_none_for_missing_documents() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/itemInfo/course/doc" => {
+                MockResponse::json(item_json(1, "course/doc", "document", "Doc"))
+            }
+            "/itemInfo/course/locked" => {
+                MockResponse::json(item_json(2, "course/locked", "document", "Locked"))
+            }
+            "/download/1" => MockResponse::text(200, "Contents"),
+            "/download/2" => MockResponse::text(403, "Forbidden"),
+            _ => MockResponse::text(404, "Not found"),
+        })
+        .await;
+        let client = server.client();
+
+        assert_eq!(
+            client.try_download_markdown("course/doc").await.unwrap(),
+            Some("Contents".to_string())
+        );
+        assert_eq!(
+            client
+                .try_download_markdown("course/missing")
+                .await
+                .unwrap(),
+            None
+        );
+        // Other errors than a missing document are not hidden
+        assert!(client.try_download_markdown("course/locked").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn upload_markdown_invalidates_cached_markdown() {
+        let markdown = Arc::new(Mutex::new("First".to_string()));
+        let server = document_server(&markdown).await;
+        let client = server.client();
+
+        client.download_markdown("course/doc").await.unwrap();
+        client
+            .upload_markdown("course/doc", "Second", Some("First"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.download_markdown("course/doc").await.unwrap(),
+            "Second"
+        );
+        assert_eq!(server.count("POST", "/update/1"), 1);
+    }
+
+    #[tokio::test]
+    async fn upload_markdown_detects_edits_after_cached_download() {
+        let markdown = Arc::new(Mutex::new("First".to_string()));
+        let server = document_server(&markdown).await;
+        let client = server.client();
+
+        let original = client.download_markdown("course/doc").await.unwrap();
+        *markdown.lock().unwrap() = "Edited in TIM".to_string();
+        let error = client
+            .upload_markdown("course/doc", "Second", Some(&original))
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                error.downcast_ref::<TimClientErrors>(),
+                Some(TimClientErrors::RemoteModified(_))
+            ),
+            "{}",
+            error
+        );
+        assert_eq!(server.count("POST", "/update/1"), 1);
+        assert_eq!(*markdown.lock().unwrap(), "Edited in TIM");
+    }
+
+    #[tokio::test]
+    async fn upload_markdown_sends_the_given_original_without_downloading() {
+        let markdown = Arc::new(Mutex::new("First".to_string()));
+        let server = document_server(&markdown).await;
+        let client = server.client();
+
+        let original = client.download_markdown("course/doc").await.unwrap();
+        client
+            .upload_markdown("course/doc", "Second", Some(&original))
+            .await
+            .unwrap();
+
+        assert_eq!(*markdown.lock().unwrap(), "Second");
+        assert_eq!(server.count("GET", "/download/"), 1);
+    }
+
+    #[tokio::test]
+    async fn upload_markdown_without_original_overwrites_edits() {
+        let markdown = Arc::new(Mutex::new("First".to_string()));
+        let server = document_server(&markdown).await;
+        let client = server.client();
+
+        client.download_markdown("course/doc").await.unwrap();
+        *markdown.lock().unwrap() = "Edited in TIM".to_string();
+        client
+            .upload_markdown("course/doc", "Second", None)
+            .await
+            .unwrap();
+
+        assert_eq!(*markdown.lock().unwrap(), "Second");
+    }
+
+    #[tokio::test]
+    async fn upload_markdown_reports_other_errors_as_is() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/itemInfo/course/doc" => {
+                MockResponse::json(item_json(1, "course/doc", "document", "Doc"))
+            }
+            "/download/1" => MockResponse::text(200, "First"),
+            "/update/1" => MockResponse::json_status(400, json!({"error": "Invalid markdown"})),
+            _ => MockResponse::json(json!({})),
+        })
+        .await;
+        let error = server
+            .client()
+            .upload_markdown("course/doc", "Second", Some("First"))
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                error.downcast_ref::<TimClientErrors>(),
+                Some(TimClientErrors::ItemError(_, _, message)) if message == "Invalid markdown"
+            ),
+            "{}",
+            error
+        );
+    }
+}